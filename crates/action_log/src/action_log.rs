@@ -618,7 +618,7 @@ impl ActionLog {
                             .entry_id(cx)
                             .and_then(|entry_id| {
                                 self.project.update(cx, |project, cx| {
-                                    project.delete_entry(entry_id, false, cx)
+                                    project.delete_entry(entry_id, None, cx)
                                 })
                             })
                             .unwrap_or(Task::ready(Ok(())))
@@ -1546,14 +1546,14 @@ mod tests {
         action_log.update(cx, |log, cx| log.will_delete_buffer(buffer2.clone(), cx));
         project
             .update(cx, |project, cx| {
-                project.delete_file(file1_path.clone(), false, cx)
+                project.delete_file(file1_path.clone(), Some(false), cx)
             })
             .unwrap()
             .await
             .unwrap();
         project
             .update(cx, |project, cx| {
-                project.delete_file(file2_path.clone(), false, cx)
+                project.delete_file(file2_path.clone(), Some(false), cx)
             })
             .unwrap()
             .await
@@ -1855,7 +1855,7 @@ mod tests {
         });
         project
             .update(cx, |project, cx| {
-                project.delete_file(file_path.clone(), false, cx)
+                project.delete_file(file_path.clone(), Some(false), cx)
             })
             .unwrap()
             .await
@@ -166,7 +166,7 @@ impl AgentTool for DeletePathTool {
 
             let deletion_task = project
                 .update(cx, |project, cx| {
-                    project.delete_file(project_path, false, cx)
+                    project.delete_file(project_path, None, cx)
                 })
                 .with_context(|| {
                     format!("Couldn't delete {path} because that path isn't in this project.")
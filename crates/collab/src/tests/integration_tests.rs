@@ -3381,7 +3381,7 @@ async fn test_fs_operations(
 
     project_b
         .update(cx_b, |project, cx| {
-            project.delete_entry(dir_entry.id, false, cx).unwrap()
+            project.delete_entry(dir_entry.id, Some(false), cx).unwrap()
         })
         .await
         .unwrap();
@@ -3409,7 +3409,7 @@ async fn test_fs_operations(
 
     project_b
         .update(cx_b, |project, cx| {
-            project.delete_entry(entry.id, false, cx).unwrap()
+            project.delete_entry(entry.id, Some(false), cx).unwrap()
         })
         .await
         .unwrap();
@@ -308,7 +308,10 @@ impl LicenseDetectionWatcher {
                         }
                     }
                 }
-                worktree::Event::DeletedEntry(_) | worktree::Event::UpdatedGitRepositories(_) => {}
+                worktree::Event::DeletedEntry(_)
+                | worktree::Event::UpdatedGitRepositories(_)
+                | worktree::Event::RootChanged { .. }
+                | worktree::Event::GitConfigChanged { .. } => {}
             });
 
         let worktree_snapshot = worktree.read(cx).snapshot();
@@ -3,11 +3,12 @@ use anyhow::{Context as _, Result, bail};
 use collections::{HashMap, HashSet};
 use futures::future::{self, BoxFuture, join_all};
 use git::{
-    Oid, RunHook,
+    MERGE_MESSAGE, Oid, REBASE_MERGE_DIR, RunHook, SQUASH_MESSAGE,
     blame::Blame,
     repository::{
         AskPassDelegate, Branch, CommitDetails, CommitOptions, FetchOptions, GitRepository,
-        GitRepositoryCheckpoint, PushOptions, Remote, RepoPath, ResetMode, Worktree,
+        GitRepositoryCheckpoint, IndexEntry, PushOptions, RebaseProgress, Remote, RepoPath,
+        ResetMode, TagEntry, Upstream, UpstreamTrackingStatus, Worktree,
     },
     status::{
         DiffTreeType, FileStatus, GitStatus, StatusCode, TrackedStatus, TreeDiff, TreeDiffStatus,
@@ -45,10 +46,16 @@ pub struct FakeGitRepositoryState {
     pub blames: HashMap<RepoPath, Blame>,
     pub current_branch_name: Option<String>,
     pub branches: HashSet<String>,
+    /// How far each branch is ahead of and behind its `origin` upstream, keyed by branch name.
+    /// Branches without an entry have no upstream.
+    pub upstream_tracking: HashMap<String, UpstreamTrackingStatus>,
     /// List of remotes, keys are names and values are URLs
     pub remotes: HashMap<String, String>,
+    /// Git config entries, keyed by their normalized name such as `core.autocrlf`.
+    pub config: HashMap<String, String>,
     pub simulated_index_write_error_message: Option<String>,
     pub refs: HashMap<String, String>,
+    pub tags: Vec<TagEntry>,
 }
 
 impl FakeGitRepositoryState {
@@ -61,11 +68,14 @@ impl FakeGitRepositoryState {
             blames: Default::default(),
             current_branch_name: Default::default(),
             branches: Default::default(),
+            upstream_tracking: Default::default(),
             simulated_index_write_error_message: Default::default(),
             refs: HashMap::from_iter([("HEAD".into(), "abc".into())]),
             merge_base_contents: Default::default(),
             oids: Default::default(),
             remotes: HashMap::default(),
+            config: HashMap::default(),
+            tags: Vec::new(),
         }
     }
 }
@@ -158,6 +168,10 @@ impl GitRepository for FakeGitRepository {
         async move { fut.await.ok() }.boxed()
     }
 
+    fn config_values(&self) -> BoxFuture<'_, Result<HashMap<String, String>>> {
+        self.with_state_async(false, |state| Ok(state.config.clone()))
+    }
+
     fn diff_tree(&self, _request: DiffTreeType) -> BoxFuture<'_, Result<TreeDiff>> {
         let mut entries = HashMap::default();
         self.with_state_async(false, |state| {
@@ -195,6 +209,22 @@ impl GitRepository for FakeGitRepository {
         })
     }
 
+    fn head_blob_oid(&self, path: RepoPath) -> BoxFuture<'_, Result<Option<Oid>>> {
+        self.with_state_async(false, move |state| {
+            state
+                .head_contents
+                .get(&path)
+                .map(|content| Oid::hash_blob(content.as_bytes()))
+                .transpose()
+        })
+    }
+
+    fn is_path_tracked(&self, path: RepoPath) -> BoxFuture<'_, Result<bool>> {
+        self.with_state_async(false, move |state| {
+            Ok(state.index_contents.contains_key(&path) || state.head_contents.contains_key(&path))
+        })
+    }
+
     fn show(&self, commit: String) -> BoxFuture<'_, Result<CommitDetails>> {
         async {
             Ok(CommitDetails {
@@ -233,7 +263,26 @@ impl GitRepository for FakeGitRepository {
     }
 
     fn merge_message(&self) -> BoxFuture<'_, Option<String>> {
-        async move { None }.boxed()
+        async move {
+            for file_name in [MERGE_MESSAGE, SQUASH_MESSAGE] {
+                let path = self.repository_dir_path.join(file_name);
+                if let Ok(message) = self.fs.load(&path).await {
+                    return Some(message);
+                }
+            }
+            None
+        }
+        .boxed()
+    }
+
+    fn rebase_progress(&self) -> BoxFuture<'_, Option<RebaseProgress>> {
+        async move {
+            let rebase_dir = self.repository_dir_path.join(REBASE_MERGE_DIR);
+            let msgnum = self.fs.load(&rebase_dir.join("msgnum")).await.ok()?;
+            let end = self.fs.load(&rebase_dir.join("end")).await.ok()?;
+            RebaseProgress::parse(&msgnum, &end)
+        }
+        .boxed()
     }
 
     fn status(&self, path_prefixes: &[RepoPath]) -> Task<Result<GitStatus>> {
@@ -371,10 +420,42 @@ impl GitRepository for FakeGitRepository {
         })
     }
 
+    fn index_entries(&self) -> BoxFuture<'_, Result<Vec<(RepoPath, IndexEntry)>>> {
+        self.with_state_async(false, |state| {
+            // Conflicted paths are modeled as a single stage-1 entry identifying the conflict.
+            let mut entries = state
+                .index_contents
+                .iter()
+                .map(|(path, content)| (path, 0, content.clone()))
+                .chain(
+                    state
+                        .unmerged_paths
+                        .iter()
+                        .map(|(path, status)| (path, 1, format!("{status:?}"))),
+                )
+                .map(|(path, stage, content)| {
+                    let entry = IndexEntry {
+                        oid: Oid::hash_blob(content.as_bytes())?,
+                        mode: 0o100644,
+                        stage,
+                    };
+                    Ok((path.clone(), entry))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            entries
+                .sort_by(|(a_path, a), (b_path, b)| a_path.cmp(b_path).then(a.stage.cmp(&b.stage)));
+            Ok(entries)
+        })
+    }
+
     fn stash_entries(&self) -> BoxFuture<'_, Result<git::stash::GitStash>> {
         async { Ok(git::stash::GitStash::default()) }.boxed()
     }
 
+    fn tags(&self) -> BoxFuture<'_, Result<Vec<TagEntry>>> {
+        self.with_state_async(false, |state| Ok(state.tags.clone()))
+    }
+
     fn branches(&self) -> BoxFuture<'_, Result<Vec<Branch>>> {
         self.with_state_async(false, move |state| {
             let current_branch = &state.current_branch_name;
@@ -387,17 +468,40 @@ impl GitRepository for FakeGitRepository {
                     } else {
                         format!("refs/heads/{branch_name}").into()
                     };
+                    let upstream =
+                        state
+                            .upstream_tracking
+                            .get(branch_name)
+                            .map(|tracking| Upstream {
+                                ref_name: format!("refs/remotes/origin/{branch_name}").into(),
+                                tracking: (*tracking).into(),
+                            });
                     Branch {
                         is_head: Some(branch_name) == current_branch.as_ref(),
                         ref_name,
                         most_recent_commit: None,
-                        upstream: None,
+                        upstream,
                     }
                 })
                 .collect())
         })
     }
 
+    fn upstream_tracking_status(
+        &self,
+        branch: String,
+        _upstream: String,
+    ) -> BoxFuture<'_, Result<UpstreamTrackingStatus>> {
+        self.with_state_async(false, move |state| {
+            let branch_name = branch.strip_prefix("refs/heads/").unwrap_or(&branch);
+            state
+                .upstream_tracking
+                .get(branch_name)
+                .copied()
+                .with_context(|| format!("branch {branch_name} has no upstream"))
+        })
+    }
+
     fn worktrees(&self) -> BoxFuture<'_, Result<Vec<Worktree>>> {
         unimplemented!()
     }
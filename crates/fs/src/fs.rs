@@ -18,7 +18,7 @@ use gpui::Global;
 use gpui::ReadGlobal as _;
 use gpui::SharedString;
 use std::borrow::Cow;
-use util::command::new_smol_command;
+use util::{command::new_smol_command, paths::extended_length_path};
 
 #[cfg(unix)]
 use std::os::fd::{AsFd, AsRawFd};
@@ -54,7 +54,8 @@ use collections::{BTreeMap, btree_map};
 use fake_git_repo::FakeGitRepositoryState;
 #[cfg(any(test, feature = "test-support"))]
 use git::{
-    repository::{RepoPath, repo_path},
+    PACKED_REFS, REPO_CONFIG,
+    repository::{RepoPath, TagEntry, UpstreamTrackingStatus, repo_path},
     status::{FileStatus, StatusCode, TrackedStatus, UnmergedStatus},
 };
 
@@ -121,6 +122,8 @@ pub trait Fs: Send + Sync {
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
     async fn write(&self, path: &Path, content: &[u8]) -> Result<()>;
+    /// Sets the modification time of the existing file at `path` to now, leaving its content as is.
+    async fn touch(&self, path: &Path) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
     async fn is_dir(&self, path: &Path) -> bool;
@@ -145,6 +148,10 @@ pub trait Fs: Send + Sync {
         abs_dot_git: &Path,
         system_git_binary_path: Option<&Path>,
     ) -> Option<Arc<dyn GitRepository>>;
+    /// Reads `key` from the local config of the repository at `abs_dot_git`, without consulting
+    /// the global and system configs. Returns `None` if the key isn't set there.
+    async fn git_local_config_value(&self, abs_dot_git: &Path, key: &str)
+    -> Result<Option<String>>;
     async fn git_init(&self, abs_work_directory: &Path, fallback_branch_name: String)
     -> Result<()>;
     async fn git_clone(&self, repo_url: &str, abs_work_directory: &Path) -> Result<()>;
@@ -508,6 +515,7 @@ impl RealFs {
 #[async_trait::async_trait]
 impl Fs for RealFs {
     async fn create_dir(&self, path: &Path) -> Result<()> {
+        let path = extended_length_path(path);
         Ok(smol::fs::create_dir_all(path).await?)
     }
 
@@ -538,6 +546,7 @@ impl Fs for RealFs {
     }
 
     async fn create_file(&self, path: &Path, options: CreateOptions) -> Result<()> {
+        let path = extended_length_path(path);
         let mut open_options = smol::fs::OpenOptions::new();
         open_options.write(true).create(true);
         if options.overwrite {
@@ -546,7 +555,7 @@ impl Fs for RealFs {
             open_options.create_new(true);
         }
         open_options
-            .open(path)
+            .open(&path)
             .await
             .with_context(|| format!("Failed to create file at {:?}", path))?;
         Ok(())
@@ -557,6 +566,7 @@ impl Fs for RealFs {
         path: &Path,
         content: Pin<&mut (dyn AsyncRead + Send)>,
     ) -> Result<()> {
+        let path = extended_length_path(path);
         let mut file = smol::fs::File::create(&path)
             .await
             .with_context(|| format!("Failed to create file at {:?}", path))?;
@@ -574,7 +584,9 @@ impl Fs for RealFs {
     }
 
     async fn copy_file(&self, source: &Path, target: &Path, options: CopyOptions) -> Result<()> {
-        if !options.overwrite && smol::fs::metadata(target).await.is_ok() {
+        let source = extended_length_path(source);
+        let target = extended_length_path(target);
+        if !options.overwrite && smol::fs::metadata(&target).await.is_ok() {
             if options.ignore_if_exists {
                 return Ok(());
             } else {
@@ -582,12 +594,14 @@ impl Fs for RealFs {
             }
         }
 
-        smol::fs::copy(source, target).await?;
+        smol::fs::copy(&source, &target).await?;
         Ok(())
     }
 
     async fn rename(&self, source: &Path, target: &Path, options: RenameOptions) -> Result<()> {
-        if !options.overwrite && smol::fs::metadata(target).await.is_ok() {
+        let source = extended_length_path(source);
+        let target = extended_length_path(target);
+        if !options.overwrite && smol::fs::metadata(&target).await.is_ok() {
             if options.ignore_if_exists {
                 return Ok(());
             } else {
@@ -601,11 +615,12 @@ impl Fs for RealFs {
             }
         }
 
-        smol::fs::rename(source, target).await?;
+        smol::fs::rename(&source, &target).await?;
         Ok(())
     }
 
     async fn remove_dir(&self, path: &Path, options: RemoveOptions) -> Result<()> {
+        let path = extended_length_path(path);
         let result = if options.recursive {
             smol::fs::remove_dir_all(path).await
         } else {
@@ -637,7 +652,7 @@ impl Fs for RealFs {
             return Ok(());
         }
 
-        match smol::fs::remove_file(path).await {
+        match smol::fs::remove_file(extended_length_path(path)).await {
             Ok(()) => Ok(()),
             Err(err) if err.kind() == io::ErrorKind::NotFound && options.ignore_if_not_exists => {
                 Ok(())
@@ -678,7 +693,7 @@ impl Fs for RealFs {
             // TODO: trash_file does not support trashing symlinks yet - https://github.com/bilelmoussaoui/ashpd/issues/255
             return self.remove_file(path, RemoveOptions::default()).await;
         }
-        let file = smol::fs::File::open(path).await?;
+        let file = smol::fs::File::open(extended_length_path(path)).await?;
         match trash::trash_file(&file.as_fd()).await {
             Ok(_) => Ok(()),
             Err(err) => {
@@ -736,7 +751,7 @@ impl Fs for RealFs {
     }
 
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read + Send + Sync>> {
-        Ok(Box::new(std::fs::File::open(path)?))
+        Ok(Box::new(std::fs::File::open(extended_length_path(path))?))
     }
 
     async fn open_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>> {
@@ -747,11 +762,11 @@ impl Fs for RealFs {
             use std::os::windows::fs::OpenOptionsExt;
             options.custom_flags(windows::Win32::Storage::FileSystem::FILE_FLAG_BACKUP_SEMANTICS.0);
         }
-        Ok(Arc::new(options.open(path)?))
+        Ok(Arc::new(options.open(extended_length_path(path))?))
     }
 
     async fn load(&self, path: &Path) -> Result<String> {
-        let path = path.to_path_buf();
+        let path = extended_length_path(path).into_owned();
         self.executor
             .spawn(async move {
                 std::fs::read_to_string(&path)
@@ -761,7 +776,7 @@ impl Fs for RealFs {
     }
 
     async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>> {
-        let path = path.to_path_buf();
+        let path = extended_length_path(path).into_owned();
         let bytes = self
             .executor
             .spawn(async move { std::fs::read(path) })
@@ -822,7 +837,7 @@ impl Fs for RealFs {
                 .await
                 .with_context(|| format!("Failed to create directory at {:?}", path))?;
         }
-        let file = smol::fs::File::create(path)
+        let file = smol::fs::File::create(extended_length_path(path))
             .await
             .with_context(|| format!("Failed to create file at {:?}", path))?;
         let mut writer = smol::io::BufWriter::with_capacity(buffer_size, file);
@@ -839,7 +854,7 @@ impl Fs for RealFs {
                 .await
                 .with_context(|| format!("Failed to create directory at {:?}", path))?;
         }
-        let path = path.to_owned();
+        let path = extended_length_path(path).into_owned();
         let contents = content.to_owned();
         self.executor
             .spawn(async move {
@@ -849,6 +864,21 @@ impl Fs for RealFs {
             .await
     }
 
+    async fn touch(&self, path: &Path) -> Result<()> {
+        let path = extended_length_path(path).into_owned();
+        self.executor
+            .spawn(async move {
+                std::fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .with_context(|| format!("opening {path:?}"))?
+                    .set_modified(SystemTime::now())
+                    .with_context(|| format!("setting the modification time of {path:?}"))?;
+                Ok(())
+            })
+            .await
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         let path = path.to_owned();
         self.executor
@@ -865,20 +895,22 @@ impl Fs for RealFs {
     }
 
     async fn is_file(&self, path: &Path) -> bool {
-        let path = path.to_owned();
+        let path = extended_length_path(path).into_owned();
         self.executor
             .spawn(async move { std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) })
             .await
     }
 
     async fn is_dir(&self, path: &Path) -> bool {
-        let path = path.to_owned();
+        let path = extended_length_path(path).into_owned();
         self.executor
             .spawn(async move { std::fs::metadata(path).is_ok_and(|metadata| metadata.is_dir()) })
             .await
     }
 
     async fn metadata(&self, path: &Path) -> Result<Option<Metadata>> {
+        let extended_path = extended_length_path(path);
+        let path: &Path = &extended_path;
         let path_buf = path.to_owned();
         let symlink_metadata = match self
             .executor
@@ -951,7 +983,7 @@ impl Fs for RealFs {
     }
 
     async fn read_link(&self, path: &Path) -> Result<PathBuf> {
-        let path = path.to_owned();
+        let path = extended_length_path(path).into_owned();
         let path = self
             .executor
             .spawn(async move { std::fs::read_link(&path) })
@@ -963,14 +995,17 @@ impl Fs for RealFs {
         &self,
         path: &Path,
     ) -> Result<Pin<Box<dyn Send + Stream<Item = Result<PathBuf>>>>> {
-        let path = path.to_owned();
+        let dir_path = path.to_owned();
+        let path = extended_length_path(path).into_owned();
         let result = iter(
             self.executor
                 .spawn(async move { std::fs::read_dir(path) })
                 .await?,
         )
-        .map(|entry| match entry {
-            Ok(entry) => Ok(entry.path()),
+        // Join the names onto the path as given, so that callers don't see the extended-length
+        // prefix on the children.
+        .map(move |entry| match entry {
+            Ok(entry) => Ok(dir_path.join(entry.file_name())),
             Err(error) => Err(anyhow!("failed to read dir entry {error:?}")),
         });
         Ok(Box::pin(result))
@@ -1109,6 +1144,32 @@ impl Fs for RealFs {
         )?))
     }
 
+    async fn git_local_config_value(
+        &self,
+        abs_dot_git: &Path,
+        key: &str,
+    ) -> Result<Option<String>> {
+        let output = new_smol_command("git")
+            .arg("--git-dir")
+            .arg(abs_dot_git)
+            .args(["config", "--local", "--get", key])
+            .output()
+            .await?;
+
+        if output.status.success() {
+            let value = String::from_utf8_lossy(&output.stdout);
+            Ok(Some(value.trim_end_matches('\n').to_string()))
+        } else if output.status.code() == Some(1) {
+            // `git config --get` exits with 1 when the key isn't set.
+            Ok(None)
+        } else {
+            Err(anyhow!(
+                "git config failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+
     async fn git_init(
         &self,
         abs_work_directory_path: &Path,
@@ -1242,8 +1303,15 @@ struct FakeFsState {
     buffered_events: Vec<PathEvent>,
     metadata_call_count: usize,
     read_dir_call_count: usize,
+    read_dirs_in_flight: usize,
+    max_concurrent_read_dirs: usize,
+    read_dir_failures: std::collections::HashMap<PathBuf, usize>,
+    open_file_call_count: usize,
+    bytes_read_count: usize,
     path_write_counts: std::collections::HashMap<PathBuf, usize>,
+    path_read_counts: std::collections::HashMap<PathBuf, usize>,
     moves: std::collections::HashMap<u64, PathBuf>,
+    trashed_paths: Vec<PathBuf>,
     job_event_subscribers: Arc<Mutex<Vec<JobEventSender>>>,
 }
 
@@ -1526,9 +1594,16 @@ impl FakeFs {
                 buffered_events: Vec::new(),
                 events_paused: false,
                 read_dir_call_count: 0,
+                read_dirs_in_flight: 0,
+                max_concurrent_read_dirs: 0,
+                read_dir_failures: Default::default(),
                 metadata_call_count: 0,
+                open_file_call_count: 0,
+                bytes_read_count: 0,
                 path_write_counts: Default::default(),
+                path_read_counts: Default::default(),
                 moves: Default::default(),
+                trashed_paths: Vec::new(),
                 job_event_subscribers: Arc::new(Mutex::new(Vec::new())),
             })),
         });
@@ -1676,8 +1751,11 @@ impl FakeFs {
         let path = normalize_path(path);
         self.simulate_random_delay().await;
         let mut state = self.state.lock();
-        let entry = state.entry(&path)?;
-        entry.file_content(&path).cloned()
+        state.open_file_call_count += 1;
+        *state.path_read_counts.entry(path.clone()).or_insert(0) += 1;
+        let content = state.entry(&path)?.file_content(&path).cloned()?;
+        state.bytes_read_count += content.len();
+        Ok(content)
     }
 
     pub fn pause_events(&self) {
@@ -1887,6 +1965,39 @@ impl FakeFs {
         .unwrap();
     }
 
+    pub fn set_config_for_repo(
+        &self,
+        dot_git: &Path,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) {
+        self.with_git_state(dot_git, false, |state| {
+            state.config.insert(key.into(), value.into());
+        })
+        .unwrap();
+        self.emit_fs_event(dot_git.join(REPO_CONFIG), Some(PathEventKind::Changed));
+    }
+
+    pub fn set_tags_for_repo(&self, dot_git: &Path, tags: Vec<TagEntry>) {
+        self.with_git_state(dot_git, false, |state| {
+            state.tags = tags;
+        })
+        .unwrap();
+        self.emit_fs_event(dot_git.join(PACKED_REFS), Some(PathEventKind::Changed));
+    }
+
+    pub fn set_upstream_tracking_for_repo(
+        &self,
+        dot_git: &Path,
+        branch: &str,
+        tracking: UpstreamTrackingStatus,
+    ) {
+        self.with_git_state(dot_git, true, |state| {
+            state.upstream_tracking.insert(branch.to_string(), tracking);
+        })
+        .unwrap();
+    }
+
     pub fn insert_branches(&self, dot_git: &Path, branches: &[&str]) {
         self.with_git_state(dot_git, true, |state| {
             if let Some(first) = branches.first()
@@ -2171,6 +2282,19 @@ impl FakeFs {
         self.state.lock().read_dir_call_count
     }
 
+    /// The largest number of `read_dir` calls that were in progress at the same time.
+    pub fn max_concurrent_read_dirs(&self) -> usize {
+        self.state.lock().max_concurrent_read_dirs
+    }
+
+    /// Makes the next `count` calls to `read_dir` for `path` fail with a permission error.
+    pub fn fail_read_dir(&self, path: impl AsRef<Path>, count: usize) {
+        self.state
+            .lock()
+            .read_dir_failures
+            .insert(normalize_path(path.as_ref()), count);
+    }
+
     pub fn watched_paths(&self) -> Vec<PathBuf> {
         let state = self.state.lock();
         state
@@ -2180,11 +2304,26 @@ impl FakeFs {
             .collect()
     }
 
+    /// The paths that were moved to the trash rather than removed, in the order they were trashed.
+    pub fn trashed_paths(&self) -> Vec<PathBuf> {
+        self.state.lock().trashed_paths.clone()
+    }
+
     /// How many `metadata` calls have been issued.
     pub fn metadata_call_count(&self) -> usize {
         self.state.lock().metadata_call_count
     }
 
+    /// How many times a file's contents have been opened for reading.
+    pub fn open_file_call_count(&self) -> usize {
+        self.state.lock().open_file_call_count
+    }
+
+    /// How many bytes of file contents have been read.
+    pub fn bytes_read_count(&self) -> usize {
+        self.state.lock().bytes_read_count
+    }
+
     /// How many write operations have been issued for a specific path.
     pub fn write_count_for_path(&self, path: impl AsRef<Path>) -> usize {
         let path = path.as_ref().to_path_buf();
@@ -2196,6 +2335,17 @@ impl FakeFs {
             .unwrap_or(0)
     }
 
+    /// How many times the contents of a specific path have been read.
+    pub fn read_count_for_path(&self, path: impl AsRef<Path>) -> usize {
+        let path = path.as_ref().to_path_buf();
+        self.state
+            .lock()
+            .path_read_counts
+            .get(&path)
+            .copied()
+            .unwrap_or(0)
+    }
+
     pub fn emit_fs_event(&self, path: impl Into<PathBuf>, event: Option<PathEventKind>) {
         self.state.lock().emit_event(std::iter::once((path, event)));
     }
@@ -2560,6 +2710,18 @@ impl Fs for FakeFs {
         Ok(())
     }
 
+    async fn trash_file(&self, path: &Path, options: RemoveOptions) -> Result<()> {
+        self.remove_file(path, options).await?;
+        self.state.lock().trashed_paths.push(normalize_path(path));
+        Ok(())
+    }
+
+    async fn trash_dir(&self, path: &Path, options: RemoveOptions) -> Result<()> {
+        self.remove_dir(path, options).await?;
+        self.state.lock().trashed_paths.push(normalize_path(path));
+        Ok(())
+    }
+
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read + Send + Sync>> {
         let bytes = self.load_internal(path).await?;
         Ok(Box::new(io::Cursor::new(bytes)))
@@ -2616,6 +2778,16 @@ impl Fs for FakeFs {
         Ok(())
     }
 
+    async fn touch(&self, path: &Path) -> Result<()> {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        self.metadata(&path)
+            .await?
+            .with_context(|| format!("path does not exist: {path:?}"))?;
+        self.touch_path(path).await;
+        Ok(())
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         let path = normalize_path(path);
         self.simulate_random_delay().await;
@@ -2706,10 +2878,24 @@ impl Fs for FakeFs {
         &self,
         path: &Path,
     ) -> Result<Pin<Box<dyn Send + Stream<Item = Result<PathBuf>>>>> {
+        {
+            let mut state = self.state.lock();
+            state.read_dirs_in_flight += 1;
+            state.max_concurrent_read_dirs = state
+                .max_concurrent_read_dirs
+                .max(state.read_dirs_in_flight);
+        }
         self.simulate_random_delay().await;
         let path = normalize_path(path);
         let mut state = self.state.lock();
+        state.read_dirs_in_flight -= 1;
         state.read_dir_call_count += 1;
+        if let Some(remaining_failures) = state.read_dir_failures.get_mut(&path)
+            && *remaining_failures > 0
+        {
+            *remaining_failures -= 1;
+            return Err(io::Error::from(io::ErrorKind::PermissionDenied).into());
+        }
         let entry = state.entry(&path)?;
         let children = entry.dir_entries(&path)?;
         let paths = children
@@ -2731,12 +2917,19 @@ impl Fs for FakeFs {
         let (tx, rx) = smol::channel::unbounded();
         let path = path.to_path_buf();
         self.state.lock().event_txs.push((path.clone(), tx.clone()));
+        let mut prefixes = vec![path.clone()];
+        // Like the real watcher, also watch the target when the watched path is a symlink.
+        if let Ok(target) = self.canonicalize(&path).await
+            && target != path
+        {
+            prefixes.push(target);
+        }
         let executor = self.executor.clone();
         let watcher = Arc::new(FakeWatcher {
             tx,
             original_path: path.to_owned(),
             fs_state: self.state.clone(),
-            prefixes: Mutex::new(vec![path]),
+            prefixes: Mutex::new(prefixes),
         });
         (
             Box::pin(futures::StreamExt::filter(rx, {
@@ -2784,6 +2977,14 @@ impl Fs for FakeFs {
         .log_err()
     }
 
+    async fn git_local_config_value(
+        &self,
+        abs_dot_git: &Path,
+        key: &str,
+    ) -> Result<Option<String>> {
+        self.with_git_state(abs_dot_git, false, |state| state.config.get(key).cloned())
+    }
+
     async fn git_init(
         &self,
         abs_work_directory_path: &Path,
@@ -22,8 +22,19 @@ pub const GITIGNORE: &str = ".gitignore";
 pub const FSMONITOR_DAEMON: &str = "fsmonitor--daemon";
 pub const LFS_DIR: &str = "lfs";
 pub const COMMIT_MESSAGE: &str = "COMMIT_EDITMSG";
+pub const MERGE_MESSAGE: &str = "MERGE_MSG";
+pub const SQUASH_MESSAGE: &str = "SQUASH_MSG";
 pub const INDEX_LOCK: &str = "index.lock";
 pub const REPO_EXCLUDE: &str = "info/exclude";
+pub const REPO_CONFIG: &str = "config";
+/// The directory holding loose refs, including tags.
+pub const REFS_DIR: &str = "refs";
+/// The file holding refs that git has packed out of [`REFS_DIR`].
+pub const PACKED_REFS: &str = "packed-refs";
+/// The directory holding the state of an in-progress interactive rebase.
+pub const REBASE_MERGE_DIR: &str = "rebase-merge";
+/// The reflog for `HEAD`, which git appends to whenever `HEAD` moves (commits, checkouts, resets).
+pub const HEAD_LOG: &str = "logs/HEAD";
 
 actions!(
     git,
@@ -144,6 +155,13 @@ impl Oid {
         Self::from_bytes(&bytes).unwrap()
     }
 
+    /// Computes the ID git assigns to a blob with the given content, without writing it.
+    pub fn hash_blob(content: &[u8]) -> Result<Self> {
+        let oid = libgit::Oid::hash_object(libgit::ObjectType::Blob, content)
+            .context("failed to hash blob content")?;
+        Ok(Self(oid))
+    }
+
     pub fn as_bytes(&self) -> &[u8] {
         self.0.as_bytes()
     }
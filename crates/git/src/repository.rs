@@ -1,7 +1,7 @@
 use crate::commit::parse_git_diff_name_status;
 use crate::stash::GitStash;
 use crate::status::{DiffTreeType, GitStatus, StatusCode, TreeDiff};
-use crate::{Oid, RunHook, SHORT_SHA_LENGTH};
+use crate::{MERGE_MESSAGE, Oid, REBASE_MERGE_DIR, RunHook, SHORT_SHA_LENGTH, SQUASH_MESSAGE};
 use anyhow::{Context as _, Result, anyhow, bail};
 use collections::HashMap;
 use futures::future::BoxFuture;
@@ -269,6 +269,58 @@ pub struct Remote {
     pub name: SharedString,
 }
 
+/// How far an interactive rebase has progressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebaseProgress {
+    /// The 1-based index of the commit currently being applied.
+    pub current: u32,
+    /// The number of commits being rebased.
+    pub total: u32,
+}
+
+impl RebaseProgress {
+    /// Parses the contents of the `msgnum` and `end` files in `.git/rebase-merge`.
+    pub fn parse(msgnum: &str, end: &str) -> Option<Self> {
+        Some(Self {
+            current: msgnum.trim().parse().ok()?,
+            total: end.trim().parse().ok()?,
+        })
+    }
+}
+
+/// An entry in the repository's index. Conflicted paths have one entry per stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub oid: Oid,
+    pub mode: u32,
+    pub stage: u8,
+}
+
+/// A tag in the repository, as listed from `refs/tags` and `packed-refs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagEntry {
+    /// The tag's name, without the `refs/tags/` prefix.
+    pub name: SharedString,
+    /// The object the tag points to. Annotated tags are peeled to their target.
+    pub target_oid: Oid,
+}
+
+impl TagEntry {
+    /// Parses a line of `git for-each-ref` output formatted with [`TAG_FORMAT`].
+    fn parse(line: &str) -> Result<Self> {
+        let mut fields = line.split('\0');
+        let name = fields.next().context("missing tag name")?;
+        let object = fields.next().context("missing tag object")?;
+        let peeled_object = fields.next().filter(|object| !object.is_empty());
+        Ok(Self {
+            name: name.to_string().into(),
+            target_oid: peeled_object.unwrap_or(object).parse()?,
+        })
+    }
+}
+
+const TAG_FORMAT: &str = "%(refname:strip=2)%00%(objectname)%00%(*objectname)";
+
 pub enum ResetMode {
     /// Reset the branch pointer, leave index and worktree unchanged (this will make it look like things that were
     /// committed are now staged).
@@ -445,6 +497,12 @@ pub trait GitRepository: Send + Sync {
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> BoxFuture<'_, Option<String>>;
 
+    /// Returns every entry of the repository's config, merged with the global and system configs.
+    ///
+    /// Keys are normalized the way git does, with lowercase section and variable names. Files
+    /// pulled in through `include.path` are read as well.
+    fn config_values(&self) -> BoxFuture<'_, Result<HashMap<String, String>>>;
+
     /// Resolve a list of refs to SHAs.
     fn revparse_batch(&self, revs: Vec<String>) -> BoxFuture<'_, Result<Vec<Option<String>>>>;
 
@@ -460,15 +518,52 @@ pub trait GitRepository: Send + Sync {
         .boxed()
     }
 
+    /// The ID of the blob stored for `path` in the `HEAD` commit, or `None` if the path isn't a
+    /// file there. This identifies the committed content without loading it.
+    fn head_blob_oid(&self, path: RepoPath) -> BoxFuture<'_, Result<Option<Oid>>>;
+
+    /// Whether `path` is tracked by the repository, i.e. has an entry in the index or in the
+    /// `HEAD` commit. Unlike the status, this also holds for files without changes.
+    fn is_path_tracked(&self, path: RepoPath) -> BoxFuture<'_, Result<bool>> {
+        async move {
+            let path = path.as_unix_str();
+            let oids = self
+                .revparse_batch(vec![format!(":{path}"), format!("HEAD:{path}")])
+                .await?;
+            Ok(oids.iter().any(Option::is_some))
+        }
+        .boxed()
+    }
+
+    /// The message git prepared for the next commit, read from `MERGE_MSG` or `SQUASH_MSG`.
     fn merge_message(&self) -> BoxFuture<'_, Option<String>>;
 
+    /// Returns the progress of the interactive rebase in progress, if any.
+    fn rebase_progress(&self) -> BoxFuture<'_, Option<RebaseProgress>>;
+
     fn status(&self, path_prefixes: &[RepoPath]) -> Task<Result<GitStatus>>;
+
+    /// Lists the entries of the repository's index, sorted by path and stage.
+    fn index_entries(&self) -> BoxFuture<'_, Result<Vec<(RepoPath, IndexEntry)>>>;
+
     fn diff_tree(&self, request: DiffTreeType) -> BoxFuture<'_, Result<TreeDiff>>;
 
     fn stash_entries(&self) -> BoxFuture<'_, Result<GitStash>>;
 
     fn branches(&self) -> BoxFuture<'_, Result<Vec<Branch>>>;
 
+    /// Lists the repository's tags, sorted by name.
+    fn tags(&self) -> BoxFuture<'_, Result<Vec<TagEntry>>>;
+
+    /// Counts the commits on `branch` that aren't on `upstream` and vice versa.
+    ///
+    /// This walks the commit graph, so it can be expensive on large histories.
+    fn upstream_tracking_status(
+        &self,
+        branch: String,
+        upstream: String,
+    ) -> BoxFuture<'_, Result<UpstreamTrackingStatus>>;
+
     fn change_branch(&self, name: String) -> BoxFuture<'_, Result<()>>;
     fn create_branch(&self, name: String, base_branch: Option<String>)
     -> BoxFuture<'_, Result<()>>;
@@ -805,7 +900,7 @@ impl GitRepository for RealGitRepository {
                         "--no-optional-locks",
                         "show",
                         "--no-patch",
-                        "--format=%H%x00%B%x00%at%x00%ae%x00%an%x00",
+                        "--format=%H%x00%B%x00%at%x00%aE%x00%aN%x00",
                         &commit,
                     ])
                     .output()
@@ -1167,48 +1262,38 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
+    fn config_values(&self) -> BoxFuture<'_, Result<HashMap<String, String>>> {
+        let repo = self.repository.clone();
+        self.executor
+            .spawn(async move {
+                let repo = repo.lock();
+                let config = repo.config()?;
+                let mut values = HashMap::default();
+                // Entries come in order of precedence, so later values override earlier ones.
+                for entry in &config.entries(None)? {
+                    let entry = entry?;
+                    if let (Some(name), Some(value)) = (entry.name(), entry.value()) {
+                        values.insert(name.to_string(), value.to_string());
+                    }
+                }
+                Ok(values)
+            })
+            .boxed()
+    }
+
     fn revparse_batch(&self, revs: Vec<String>) -> BoxFuture<'_, Result<Vec<Option<String>>>> {
         let working_directory = self.working_directory();
         let git_binary_path = self.any_git_binary_path.clone();
         self.executor
             .spawn(async move {
                 let working_directory = working_directory?;
-                let mut process = new_smol_command(&git_binary_path)
-                    .current_dir(&working_directory)
-                    .args([
-                        "--no-optional-locks",
-                        "cat-file",
-                        "--batch-check=%(objectname)",
-                    ])
-                    .stdin(Stdio::piped())
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .spawn()?;
-
-                let stdin = process
-                    .stdin
-                    .take()
-                    .context("no stdin for git cat-file subprocess")?;
-                let mut stdin = BufWriter::new(stdin);
-                for rev in &revs {
-                    stdin.write_all(rev.as_bytes()).await?;
-                    stdin.write_all(b"\n").await?;
-                }
-                stdin.flush().await?;
-                drop(stdin);
-
-                let output = process.output().await?;
-                let output = std::str::from_utf8(&output.stdout)?;
-                let shas = output
-                    .lines()
-                    .map(|line| {
-                        if line.ends_with("missing") {
-                            None
-                        } else {
-                            Some(line.to_string())
-                        }
-                    })
-                    .collect::<Vec<_>>();
+                let shas = cat_file_batch_check(
+                    &git_binary_path,
+                    &working_directory,
+                    "%(objectname)",
+                    &revs,
+                )
+                .await?;
 
                 if shas.len() != revs.len() {
                     // In an octopus merge, git cat-file still only outputs the first sha from MERGE_HEAD.
@@ -1220,10 +1305,53 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
+    fn head_blob_oid(&self, path: RepoPath) -> BoxFuture<'_, Result<Option<Oid>>> {
+        let working_directory = self.working_directory();
+        let git_binary_path = self.any_git_binary_path.clone();
+        self.executor
+            .spawn(async move {
+                let working_directory = working_directory?;
+                let rev = format!("HEAD:{}", path.as_unix_str());
+                let line = cat_file_batch_check(
+                    &git_binary_path,
+                    &working_directory,
+                    "%(objecttype) %(objectname)",
+                    &[rev],
+                )
+                .await?
+                .into_iter()
+                .next()
+                .flatten();
+                // Directories and submodules resolve to trees and commits, which have no content.
+                match line.as_deref().and_then(|line| line.split_once(' ')) {
+                    Some(("blob", oid)) => Ok(Some(oid.parse()?)),
+                    _ => Ok(None),
+                }
+            })
+            .boxed()
+    }
+
     fn merge_message(&self) -> BoxFuture<'_, Option<String>> {
-        let path = self.path().join("MERGE_MSG");
+        let repository_dir = self.path();
         self.executor
-            .spawn(async move { std::fs::read_to_string(&path).ok() })
+            .spawn(async move {
+                [MERGE_MESSAGE, SQUASH_MESSAGE]
+                    .into_iter()
+                    .find_map(|file_name| {
+                        std::fs::read_to_string(repository_dir.join(file_name)).ok()
+                    })
+            })
+            .boxed()
+    }
+
+    fn rebase_progress(&self) -> BoxFuture<'_, Option<RebaseProgress>> {
+        let rebase_dir = self.path().join(REBASE_MERGE_DIR);
+        self.executor
+            .spawn(async move {
+                let msgnum = std::fs::read_to_string(rebase_dir.join("msgnum")).ok()?;
+                let end = std::fs::read_to_string(rebase_dir.join("end")).ok()?;
+                RebaseProgress::parse(&msgnum, &end)
+            })
             .boxed()
     }
 
@@ -1251,6 +1379,29 @@ impl GitRepository for RealGitRepository {
         })
     }
 
+    fn index_entries(&self) -> BoxFuture<'_, Result<Vec<(RepoPath, IndexEntry)>>> {
+        let repo = self.repository.clone();
+        self.executor
+            .spawn(async move {
+                let repo = repo.lock();
+                let mut index = repo.index()?;
+                index.read(false)?;
+                index
+                    .iter()
+                    .map(|entry| {
+                        let path = RepoPath::new(str::from_utf8(&entry.path)?)?;
+                        let entry = IndexEntry {
+                            oid: Oid(entry.id),
+                            mode: entry.mode,
+                            stage: ((entry.flags >> 12) & 0b11) as u8,
+                        };
+                        Ok((path, entry))
+                    })
+                    .collect()
+            })
+            .boxed()
+    }
+
     fn diff_tree(&self, request: DiffTreeType) -> BoxFuture<'_, Result<TreeDiff>> {
         let git_binary_path = self.any_git_binary_path.clone();
         let working_directory = match self.working_directory() {
@@ -1384,6 +1535,51 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
+    fn tags(&self) -> BoxFuture<'_, Result<Vec<TagEntry>>> {
+        let working_directory = self.working_directory();
+        let git_binary_path = self.any_git_binary_path.clone();
+        self.executor
+            .spawn(async move {
+                let output = new_smol_command(&git_binary_path)
+                    .current_dir(working_directory?)
+                    .args(["for-each-ref", "refs/tags", "--format", TAG_FORMAT])
+                    .output()
+                    .await?;
+
+                anyhow::ensure!(
+                    output.status.success(),
+                    "Failed to list git tags:\n{}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(TagEntry::parse)
+                    .collect()
+            })
+            .boxed()
+    }
+
+    fn upstream_tracking_status(
+        &self,
+        branch: String,
+        upstream: String,
+    ) -> BoxFuture<'_, Result<UpstreamTrackingStatus>> {
+        let repo = self.repository.clone();
+        self.executor
+            .spawn(async move {
+                let repo = repo.lock();
+                let branch_oid = repo.revparse_single(&branch)?.peel_to_commit()?.id();
+                let upstream_oid = repo.revparse_single(&upstream)?.peel_to_commit()?.id();
+                let (ahead, behind) = repo.graph_ahead_behind(branch_oid, upstream_oid)?;
+                Ok(UpstreamTrackingStatus {
+                    ahead: ahead as u32,
+                    behind: behind as u32,
+                })
+            })
+            .boxed()
+    }
+
     fn worktrees(&self) -> BoxFuture<'_, Result<Vec<Worktree>>> {
         let git_binary_path = self.any_git_binary_path.clone();
         let working_directory = self.working_directory();
@@ -1592,7 +1788,7 @@ impl GitRepository for RealGitRepository {
                     concat!("<<COMMIT_END-", "3f8a9c2e-7d4b-4e1a-9f6c-8b5d2a1e4c3f>>",);
 
                 let format_string = format!(
-                    "--pretty=format:%H%x00%s%x00%B%x00%at%x00%an%x00%ae{}",
+                    "--pretty=format:%H%x00%s%x00%B%x00%at%x00%aN%x00%aE{}",
                     commit_delimiter
                 );
 
@@ -2414,6 +2610,50 @@ impl GitRepository for RealGitRepository {
     }
 }
 
+/// Describes each of `revs` with `git cat-file --batch-check`, using the given format, or
+/// `None` for the revisions that don't name an object.
+async fn cat_file_batch_check(
+    git_binary_path: &Path,
+    working_directory: &Path,
+    format: &str,
+    revs: &[String],
+) -> Result<Vec<Option<String>>> {
+    let mut process = new_smol_command(git_binary_path)
+        .current_dir(working_directory)
+        .arg("--no-optional-locks")
+        .arg("cat-file")
+        .arg(format!("--batch-check={format}"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdin = process
+        .stdin
+        .take()
+        .context("no stdin for git cat-file subprocess")?;
+    let mut stdin = BufWriter::new(stdin);
+    for rev in revs {
+        stdin.write_all(rev.as_bytes()).await?;
+        stdin.write_all(b"\n").await?;
+    }
+    stdin.flush().await?;
+    drop(stdin);
+
+    let output = process.output().await?;
+    let output = std::str::from_utf8(&output.stdout)?;
+    Ok(output
+        .lines()
+        .map(|line| {
+            if line.ends_with("missing") {
+                None
+            } else {
+                Some(line.to_string())
+            }
+        })
+        .collect())
+}
+
 fn git_status_args(path_prefixes: &[RepoPath]) -> Vec<OsString> {
     let mut args = vec![
         OsString::from("--no-optional-locks"),
@@ -2865,6 +3105,38 @@ mod tests {
         }
     }
 
+    /// Creates a repository in a temporary directory, with an initial commit by `signature`
+    /// containing `files`.
+    fn init_repo_with_commit(
+        files: &[(&str, &str)],
+        signature: &git2::Signature,
+    ) -> (tempfile::TempDir, git2::Repository) {
+        let repo_dir = tempfile::tempdir().unwrap();
+        let repository = git2::Repository::init(repo_dir.path()).unwrap();
+        let mut index = repository.index().unwrap();
+        for (path, content) in files {
+            let abs_path = repo_dir.path().join(path);
+            std::fs::create_dir_all(abs_path.parent().unwrap()).unwrap();
+            std::fs::write(abs_path, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repository.find_tree(tree_id).unwrap();
+        repository
+            .commit(
+                Some("HEAD"),
+                signature,
+                signature,
+                "Initial commit",
+                &tree,
+                &[],
+            )
+            .unwrap();
+        drop(tree);
+        (repo_dir, repository)
+    }
+
     #[gpui::test]
     async fn test_checkpoint_basic(cx: &mut TestAppContext) {
         disable_git_global_config();
@@ -3106,6 +3378,246 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_config_include_path(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let dot_git = repo_dir.path().join(".git");
+        smol::fs::write(
+            dot_git.join("remotes.config"),
+            "[remote \"origin\"]\n\turl = https://example.com/zed.git\n",
+        )
+        .await
+        .unwrap();
+        let mut config = smol::fs::read_to_string(dot_git.join("config"))
+            .await
+            .unwrap();
+        config.push_str("[include]\n\tpath = remotes.config\n");
+        smol::fs::write(dot_git.join("config"), config)
+            .await
+            .unwrap();
+
+        let repo =
+            RealGitRepository::new(&dot_git, None, Some("git".into()), cx.executor()).unwrap();
+
+        assert_eq!(
+            repo.config_values()
+                .await
+                .unwrap()
+                .get("remote.origin.url")
+                .map(String::as_str),
+            Some("https://example.com/zed.git")
+        );
+        assert_eq!(
+            repo.remote_url("origin").await.as_deref(),
+            Some("https://example.com/zed.git")
+        );
+        let remotes = repo.get_all_remotes().await.unwrap();
+        assert_eq!(
+            remotes
+                .iter()
+                .map(|remote| remote.name.to_string())
+                .collect::<Vec<_>>(),
+            ["origin"]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_packed_refs(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        let repository = git2::Repository::init(repo_dir.path()).unwrap();
+        let signature = git2::Signature::now("test", "test@zed.dev").unwrap();
+        let tree_id = repository.index().unwrap().write_tree().unwrap();
+        let tree = repository.find_tree(tree_id).unwrap();
+        let commit_id = repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Initial commit",
+                &tree,
+                &[],
+            )
+            .unwrap();
+        let commit = repository.find_commit(commit_id).unwrap();
+        repository.branch("feature", &commit, false).unwrap();
+        repository
+            .tag_lightweight("v1", commit.as_object(), false)
+            .unwrap();
+        repository
+            .tag(
+                "v2",
+                commit.as_object(),
+                &signature,
+                "Second release",
+                false,
+            )
+            .unwrap();
+
+        let dot_git = repo_dir.path().join(".git");
+        let output = new_smol_command("git")
+            .current_dir(repo_dir.path())
+            .args(["pack-refs", "--all"])
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert!(!dot_git.join("refs/heads/feature").exists());
+        assert!(!dot_git.join("refs/tags/v1").exists());
+
+        let repo =
+            RealGitRepository::new(&dot_git, None, Some("git".into()), cx.executor()).unwrap();
+
+        let branches = repo.branches().await.unwrap();
+        assert_eq!(branches.len(), 2);
+        let feature = branches
+            .iter()
+            .find(|branch| branch.name() == "feature")
+            .unwrap();
+        assert!(!feature.is_head);
+        assert!(branches.iter().any(|branch| branch.is_head));
+
+        let commit_oid = commit_id.to_string().parse().unwrap();
+        assert_eq!(
+            repo.tags().await.unwrap(),
+            [
+                TagEntry {
+                    name: "v1".into(),
+                    target_oid: commit_oid,
+                },
+                TagEntry {
+                    name: "v2".into(),
+                    target_oid: commit_oid,
+                },
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_head_blob_oid(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let signature = git2::Signature::now("test", "test@zed.dev").unwrap();
+        let (repo_dir, _repository) =
+            init_repo_with_commit(&[("dir/file.txt", "committed")], &signature);
+        std::fs::write(repo_dir.path().join("untracked.txt"), "untracked").unwrap();
+        std::fs::write(repo_dir.path().join("dir/file.txt"), "modified").unwrap();
+
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repo.head_blob_oid(repo_path("dir/file.txt")).await.unwrap(),
+            Some(Oid::hash_blob(b"committed").unwrap())
+        );
+        assert_eq!(repo.head_blob_oid(repo_path("dir")).await.unwrap(), None);
+        assert_eq!(
+            repo.head_blob_oid(repo_path("untracked.txt"))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[gpui::test]
+    async fn test_is_path_tracked(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let signature = git2::Signature::now("test", "test@zed.dev").unwrap();
+        let (repo_dir, repository) =
+            init_repo_with_commit(&[("committed.txt", "committed")], &signature);
+        std::fs::write(repo_dir.path().join("staged.txt"), "staged").unwrap();
+        std::fs::write(repo_dir.path().join("untracked.txt"), "untracked").unwrap();
+        let mut index = repository.index().unwrap();
+        index.add_path(Path::new("staged.txt")).unwrap();
+        index.write().unwrap();
+
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+
+        let status = repo
+            .status(&[RepoPath::from_rel_path(RelPath::empty())])
+            .await
+            .unwrap();
+        assert!(
+            !status
+                .entries
+                .iter()
+                .any(|(path, _)| path == &repo_path("committed.txt"))
+        );
+        assert!(
+            repo.is_path_tracked(repo_path("committed.txt"))
+                .await
+                .unwrap()
+        );
+        assert!(repo.is_path_tracked(repo_path("staged.txt")).await.unwrap());
+        assert!(
+            !repo
+                .is_path_tracked(repo_path("untracked.txt"))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[gpui::test]
+    async fn test_mailmap(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let signature = git2::Signature::now("Old Name", "old@example.com").unwrap();
+        let (repo_dir, _repository) = init_repo_with_commit(&[("file", "line\n")], &signature);
+        smol::fs::write(
+            repo_dir.path().join(".mailmap"),
+            "Canonical Name <canonical@example.com> <old@example.com>\n",
+        )
+        .await
+        .unwrap();
+
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+
+        let head_commit = repo.show("HEAD".into()).await.unwrap();
+        assert_eq!(head_commit.author_name.as_ref(), "Canonical Name");
+        assert_eq!(head_commit.author_email.as_ref(), "canonical@example.com");
+
+        let history = repo.file_history(repo_path("file")).await.unwrap();
+        assert_eq!(history.entries[0].author_name.as_ref(), "Canonical Name");
+
+        let blame = repo
+            .blame(repo_path("file"), Rope::from("line\n"), LineEnding::Unix)
+            .await
+            .unwrap();
+        assert_eq!(blame.entries[0].author.as_deref(), Some("Canonical Name"));
+    }
+
     #[test]
     fn test_branches_parsing() {
         // suppress "help: octal escapes are not supported, `\0` is always null"
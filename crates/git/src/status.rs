@@ -141,6 +141,23 @@ impl FileStatus {
         Ok(status)
     }
 
+    /// The two-character code that `git status --porcelain` prints for this status, such as
+    /// `" M"`, `"A "`, `"??"` or `"UU"`.
+    pub fn to_bytes(self) -> [u8; 2] {
+        match self {
+            FileStatus::Untracked => *b"??",
+            FileStatus::Ignored => *b"!!",
+            FileStatus::Unmerged(UnmergedStatus {
+                first_head,
+                second_head,
+            }) => [first_head.to_byte(), second_head.to_byte()],
+            FileStatus::Tracked(TrackedStatus {
+                index_status,
+                worktree_status,
+            }) => [index_status.to_byte(), worktree_status.to_byte()],
+        }
+    }
+
     pub fn staging(self) -> StageStatus {
         match self {
             FileStatus::Untracked | FileStatus::Ignored | FileStatus::Unmerged { .. } => {
@@ -236,6 +253,18 @@ impl StatusCode {
         }
     }
 
+    fn to_byte(self) -> u8 {
+        match self {
+            StatusCode::Modified => b'M',
+            StatusCode::TypeChanged => b'T',
+            StatusCode::Added => b'A',
+            StatusCode::Deleted => b'D',
+            StatusCode::Renamed => b'R',
+            StatusCode::Copied => b'C',
+            StatusCode::Unmodified => b' ',
+        }
+    }
+
     fn to_summary(self) -> TrackedSummary {
         match self {
             StatusCode::Modified | StatusCode::TypeChanged => TrackedSummary {
@@ -280,6 +309,14 @@ impl UnmergedStatusCode {
             _ => anyhow::bail!("Invalid unmerged status code: {byte}"),
         }
     }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            UnmergedStatusCode::Added => b'A',
+            UnmergedStatusCode::Deleted => b'D',
+            UnmergedStatusCode::Updated => b'U',
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Copy, PartialEq, Eq)]
@@ -580,9 +617,23 @@ mod tests {
 
     use crate::{
         repository::RepoPath,
-        status::{TreeDiff, TreeDiffStatus},
+        status::{FileStatus, StatusCode, TreeDiff, TreeDiffStatus},
     };
 
+    #[test]
+    fn test_file_status_to_bytes() {
+        for code in [
+            *b"??", *b"!!", *b" M", *b"A ", *b"MM", *b"DA", *b"UU", *b"AU", *b"UD",
+        ] {
+            assert_eq!(FileStatus::from_bytes(code).unwrap().to_bytes(), code);
+        }
+        assert_eq!(FileStatus::Untracked.to_bytes(), *b"??");
+        assert_eq!(
+            FileStatus::worktree(StatusCode::Modified).to_bytes(),
+            *b" M"
+        );
+    }
+
     #[test]
     fn test_tree_diff_parsing() {
         let input = ":000000 100644 0000000000000000000000000000000000000000 0062c311b8727c3a2e3cd7a41bc9904feacf8f98 A\x00.zed/settings.json\x00".to_owned() +
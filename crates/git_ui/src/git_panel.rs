@@ -1456,7 +1456,7 @@ impl GitPanel {
                     let task = workspace.update(cx, |workspace, cx| {
                         workspace
                             .project()
-                            .update(cx, |project, cx| project.delete_file(path, true, cx))
+                            .update(cx, |project, cx| project.delete_file(path, None, cx))
                     })?;
                     if let Some(task) = task {
                         task.await?;
@@ -1650,7 +1650,7 @@ impl GitPanel {
                             let project_path = active_repo
                                 .read(cx)
                                 .repo_path_to_project_path(&entry.repo_path, cx)?;
-                            project.delete_file(project_path, true, cx)
+                            project.delete_file(project_path, None, cx)
                         })
                     })
                     .collect::<Vec<_>>()
@@ -2389,12 +2389,12 @@ impl GitPanel {
 
     /// Suggests a commit message based on the changed files and their statuses
     pub fn suggest_commit_message(&self, cx: &App) -> Option<String> {
-        if let Some(merge_message) = self
+        if let Some(prepared_message) = self
             .active_repository
             .as_ref()
-            .and_then(|repo| repo.read(cx).merge.message.as_ref())
+            .and_then(|repo| repo.read(cx).prepared_commit_message())
         {
-            return Some(merge_message.to_string());
+            return Some(prepared_message);
         }
 
         let git_status_entry = if let Some(staged_entry) = &self.single_staged_entry {
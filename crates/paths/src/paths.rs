@@ -431,6 +431,13 @@ pub fn local_settings_file_relative_path() -> &'static RelPath {
     *CACHED
 }
 
+/// Returns the relative path to a `worktree.json` file within a project.
+pub fn local_worktree_file_relative_path() -> &'static RelPath {
+    static CACHED: LazyLock<&'static RelPath> =
+        LazyLock::new(|| RelPath::unix(".zed/worktree.json").unwrap());
+    *CACHED
+}
+
 /// Returns the relative path to a `tasks.json` file within a project.
 pub fn local_tasks_file_relative_path() -> &'static RelPath {
     static CACHED: LazyLock<&'static RelPath> =
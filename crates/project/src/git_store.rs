@@ -6,6 +6,7 @@ pub mod pending_op;
 use crate::{
     ProjectEnvironment, ProjectItem, ProjectPath,
     buffer_store::{BufferStore, BufferStoreEvent},
+    project_settings::ProjectSettings,
     worktree_store::{WorktreeStore, WorktreeStoreEvent},
 };
 use anyhow::{Context as _, Result, anyhow, bail};
@@ -25,13 +26,14 @@ use futures::{
     stream::FuturesOrdered,
 };
 use git::{
-    BuildPermalinkParams, GitHostingProviderRegistry, Oid, RunHook,
+    BuildPermalinkParams, DOT_GIT, GitHostingProviderRegistry, Oid, RunHook,
     blame::Blame,
     parse_git_remote_url,
     repository::{
         Branch, CommitDetails, CommitDiff, CommitFile, CommitOptions, DiffType, FetchOptions,
-        GitRepository, GitRepositoryCheckpoint, PushOptions, Remote, RemoteCommandOutput, RepoPath,
-        ResetMode, UpstreamTrackingStatus, Worktree as GitWorktree,
+        GitRepository, GitRepositoryCheckpoint, IndexEntry, PushOptions, RebaseProgress, Remote,
+        RemoteCommandOutput, RepoPath, ResetMode, TagEntry, UpstreamTrackingStatus,
+        Worktree as GitWorktree,
     },
     stash::{GitStash, StashEntry},
     status::{
@@ -55,11 +57,12 @@ use rpc::{
     proto::{self, git_reset, split_repository_update},
 };
 use serde::Deserialize;
-use settings::WorktreeId;
+use settings::{Settings as _, SettingsLocation, WorktreeId};
 use smol::future::yield_now;
 use std::{
     cmp::Ordering,
     collections::{BTreeSet, HashSet, VecDeque},
+    ffi::OsStr,
     future::Future,
     mem,
     ops::Range,
@@ -67,9 +70,9 @@ use std::{
     str::FromStr,
     sync::{
         Arc,
-        atomic::{self, AtomicU64},
+        atomic::{self, AtomicU64, AtomicUsize},
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 use sum_tree::{Edit, SumTree, TreeSet};
 use task::Shell;
@@ -82,7 +85,7 @@ use util::{
 };
 use worktree::{
     File, PathChange, PathKey, PathProgress, PathSummary, PathTarget, ProjectEntryId,
-    UpdatedGitRepositoriesSet, UpdatedGitRepository, Worktree,
+    UpdatedGitRepositoriesSet, UpdatedGitRepository, WorkDirectory, Worktree,
 };
 use zeroize::Zeroize;
 
@@ -250,6 +253,18 @@ pub struct MergeDetails {
     pub conflicted_paths: TreeSet<RepoPath>,
     pub message: Option<SharedString>,
     pub heads: Vec<Option<SharedString>>,
+    /// Only populated for local repositories.
+    pub rebase_progress: Option<RebaseProgress>,
+}
+
+/// The number of changed paths in a repository by kind, with each path counted once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatusCounts {
+    pub modified: usize,
+    pub added: usize,
+    pub deleted: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -264,7 +279,18 @@ pub struct RepositorySnapshot {
     pub merge: MergeDetails,
     pub remote_origin_url: Option<String>,
     pub remote_upstream_url: Option<String>,
+    /// Entries of the repository's git config merged with the global config. Only populated
+    /// for local repositories.
+    pub config_values: Arc<HashMap<String, String>>,
     pub stash_entries: GitStash,
+    /// The repository's tags, sorted by name. Only populated for local repositories.
+    pub tags: Arc<Vec<TagEntry>>,
+    /// Whether this repository is a submodule, whose git directory lives under the `modules`
+    /// directory of its superproject. Only populated for local repositories.
+    pub is_submodule: bool,
+    /// The repository's index as of the last status scan, used to narrow the next scan to the
+    /// paths whose index entries changed. Only populated for local repositories.
+    pub index_entries: Option<Arc<Vec<(RepoPath, IndexEntry)>>>,
 }
 
 type JobId = u64;
@@ -283,6 +309,18 @@ pub struct Repository {
     // For a local repository, holds paths that have had worktree events since the last status scan completed,
     // and that should be examined during the next status scan.
     paths_needing_status_update: BTreeSet<RepoPath>,
+    #[cfg(any(test, feature = "test-support"))]
+    status_entries_recomputed: usize,
+    /// Whether the next status scan should re-read the git config, because it hasn't been
+    /// read yet or the worktree observed a change to it.
+    config_needs_reload: bool,
+    /// Whether the next status scan should re-list the tags, because they haven't been listed
+    /// yet or the worktree observed a change to the repository's refs.
+    tags_need_reload: bool,
+    #[cfg(any(test, feature = "test-support"))]
+    status_scans_completed: usize,
+    pending_status_refreshes: Arc<AtomicUsize>,
+    debounced_status_scan: Option<Task<()>>,
     job_sender: mpsc::UnboundedSender<GitJob>,
     active_jobs: HashMap<JobId, JobInfo>,
     pending_ops: SumTree<PendingOps>,
@@ -929,8 +967,44 @@ impl GitStore {
         project_path: &ProjectPath,
         cx: &App,
     ) -> Option<FileStatus> {
+        let (_, status) = self.repository_and_status_for_project_path(project_path, cx)?;
+        status
+    }
+
+    /// The innermost repository containing `project_path`, along with the path's status in it.
+    /// The status is `None` for paths that are unchanged in the repository.
+    pub fn repository_and_status_for_project_path(
+        &self,
+        project_path: &ProjectPath,
+        cx: &App,
+    ) -> Option<(Entity<Repository>, Option<FileStatus>)> {
         let (repo, repo_path) = self.repository_and_path_for_project_path(project_path, cx)?;
-        Some(repo.read(cx).status_for_path(&repo_path)?.status)
+        let status = repo
+            .read(cx)
+            .status_for_path(&repo_path)
+            .map(|entry| entry.status);
+        Some((repo, status))
+    }
+
+    /// Whether `project_path` is a file committed to a repository with a working tree, so that
+    /// blame information can be offered for it. Untracked, ignored and newly added files have
+    /// no history to blame.
+    pub fn can_blame(&self, project_path: &ProjectPath, cx: &App) -> bool {
+        let Some(entry) = self
+            .worktree_store
+            .read(cx)
+            .entry_for_path(project_path, cx)
+        else {
+            return false;
+        };
+        if !entry.is_file() || entry.is_ignored {
+            return false;
+        }
+        let Some((repo, status)) = self.repository_and_status_for_project_path(project_path, cx)
+        else {
+            return false;
+        };
+        !repo.read(cx).is_bare() && status.is_none_or(|status| !status.is_created())
     }
 
     pub fn checkpoint(&self, cx: &mut App) -> Task<Result<GitStoreCheckpoint>> {
@@ -1349,6 +1423,33 @@ impl GitStore {
         cx.emit(GitStoreEvent::JobsUpdated)
     }
 
+    /// How long status refreshes of the repository at `work_directory_abs_path` wait for further
+    /// changes, as configured for the worktree location containing its working directory.
+    fn status_refresh_debounce(
+        &self,
+        worktree_id: WorktreeId,
+        work_directory_abs_path: &Path,
+        cx: &App,
+    ) -> Duration {
+        let (worktree_id, path) = match self
+            .worktree_store
+            .read(cx)
+            .find_worktree(work_directory_abs_path, cx)
+        {
+            Some((worktree, path)) => (worktree.read(cx).id(), path),
+            None => (worktree_id, RelPath::empty().into()),
+        };
+        let location = SettingsLocation {
+            worktree_id,
+            path: &path,
+        };
+        Duration::from_millis(
+            ProjectSettings::get(Some(location), cx)
+                .git
+                .status_refresh_debounce_ms,
+        )
+    }
+
     /// Update our list of repositories and schedule git scans in response to a notification from a worktree,
     fn update_repositories_from_worktree(
         &mut self,
@@ -1378,9 +1479,17 @@ impl GitStore {
                         .entry(repo_id)
                         .or_insert_with(HashSet::new)
                         .insert(worktree_id);
+                    let debounce =
+                        self.status_refresh_debounce(worktree_id, &new_work_directory_abs_path, cx);
                     existing.update(cx, |existing, cx| {
                         existing.snapshot.work_directory_abs_path = new_work_directory_abs_path;
-                        existing.schedule_scan(updates_tx.clone(), cx);
+                        if update.config_changed {
+                            existing.config_needs_reload = true;
+                        }
+                        if update.refs_changed {
+                            existing.tags_need_reload = true;
+                        }
+                        existing.schedule_scan(updates_tx.clone(), debounce, cx);
                     });
                 } else {
                     if let Some(worktree_ids) = self.worktree_ids.get_mut(&repo_id) {
@@ -1394,12 +1503,14 @@ impl GitStore {
                 new_work_directory_abs_path: Some(work_directory_abs_path),
                 dot_git_abs_path: Some(dot_git_abs_path),
                 repository_dir_abs_path: Some(_repository_dir_abs_path),
-                common_dir_abs_path: Some(_common_dir_abs_path),
+                common_dir_abs_path: Some(common_dir_abs_path),
                 ..
             } = update
             {
                 let id = RepositoryId(next_repository_id.fetch_add(1, atomic::Ordering::Release));
                 let git_store = cx.weak_entity();
+                let debounce =
+                    self.status_refresh_debounce(worktree_id, work_directory_abs_path, cx);
                 let repo = cx.new(|cx| {
                     let mut repo = Repository::local(
                         id,
@@ -1410,13 +1521,14 @@ impl GitStore {
                         git_store,
                         cx,
                     );
+                    repo.snapshot.is_submodule = is_submodule_git_dir(common_dir_abs_path);
                     if let Some(updates_tx) = updates_tx.as_ref() {
                         // trigger an empty `UpdateRepository` to ensure remote active_repo_id is set correctly
                         updates_tx
                             .unbounded_send(DownstreamUpdate::UpdateRepository(repo.snapshot()))
                             .ok();
                     }
-                    repo.schedule_scan(updates_tx.clone(), cx);
+                    repo.schedule_scan(updates_tx.clone(), debounce, cx);
                     repo
                 });
                 self._subscriptions
@@ -1609,10 +1721,38 @@ impl GitStore {
         }
     }
 
+    /// The repositories in the project, in no particular order. Use
+    /// [`Self::repositories_sorted_by_path`] when the order matters.
     pub fn repositories(&self) -> &HashMap<RepositoryId, Entity<Repository>> {
         &self.repositories
     }
 
+    /// The repositories in the project, ordered by their work directory's absolute path, so
+    /// the order doesn't depend on when each repository was discovered.
+    pub fn repositories_sorted_by_path(&self, cx: &App) -> Vec<Entity<Repository>> {
+        let mut repositories = self.repositories.values().cloned().collect::<Vec<_>>();
+        repositories.sort_by_cached_key(|repo| repo.read(cx).work_directory_abs_path.clone());
+        repositories
+    }
+
+    /// Returns the repository rooted at the given work directory of a worktree.
+    pub fn repository_for_work_directory(
+        &self,
+        worktree_id: WorktreeId,
+        work_directory: &WorkDirectory,
+        cx: &App,
+    ) -> Option<Entity<Repository>> {
+        let worktree = self
+            .worktree_store
+            .read(cx)
+            .worktree_for_id(worktree_id, cx)?;
+        let abs_path = worktree.read(cx).work_directory_abs_path(work_directory);
+        self.repositories
+            .values()
+            .find(|repo| repo.read(cx).work_directory_abs_path.as_ref() == abs_path.as_path())
+            .cloned()
+    }
+
     pub fn status_for_buffer_id(&self, buffer_id: BufferId, cx: &App) -> Option<FileStatus> {
         let (repo, path) = self.repository_and_path_for_buffer_id(buffer_id, cx)?;
         let status = repo.read(cx).snapshot.status_for_path(&path)?;
@@ -2821,6 +2961,21 @@ impl GitStore {
             .collect()
     }
 
+    /// Each repository's work directory paired with the combined status of all its paths,
+    /// ordered by work directory.
+    pub fn git_status_summary_by_repo(&self, cx: &App) -> Vec<(Arc<Path>, GitSummary)> {
+        let mut summaries = self
+            .repositories
+            .values()
+            .map(|repo| {
+                let repo = repo.read(cx);
+                (repo.work_directory_abs_path.clone(), repo.status_summary())
+            })
+            .collect::<Vec<_>>();
+        summaries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        summaries
+    }
+
     fn process_updated_entries(
         &self,
         worktree: &Entity<Worktree>,
@@ -3279,7 +3434,11 @@ impl RepositorySnapshot {
             merge: Default::default(),
             remote_origin_url: None,
             remote_upstream_url: None,
+            config_values: Default::default(),
             stash_entries: Default::default(),
+            tags: Default::default(),
+            is_submodule: false,
+            index_entries: None,
             path_style,
         }
     }
@@ -3397,6 +3556,90 @@ impl RepositorySnapshot {
         self.statuses_by_path.summary().item_summary
     }
 
+    /// The combined status of every path within `directory`, read from the summaries that the
+    /// status tree maintains, so it takes logarithmic time rather than visiting each entry.
+    pub fn status_summary_for_directory(&self, directory: &RepoPath) -> GitSummary {
+        let mut cursor = self.statuses_by_path.cursor::<PathProgress>(());
+        cursor.seek(&PathTarget::Path(directory), Bias::Left);
+        cursor.summary(&PathTarget::Successor(directory), Bias::Left)
+    }
+
+    /// The status of `repo_path`. When `recursive` is set, this is the combined status of every
+    /// path within the directory at `repo_path`; otherwise it's the status of that path alone.
+    pub fn status_for_directory(&self, repo_path: &RepoPath, recursive: bool) -> GitSummary {
+        if recursive {
+            self.status_summary_for_directory(repo_path)
+        } else {
+            self.status_for_path(repo_path)
+                .map(|entry| entry.status.summary())
+                .unwrap_or_default()
+        }
+    }
+
+    /// The number of paths that have a git status, computed without iterating over them.
+    pub fn status_len(&self) -> usize {
+        self.status_summary().count
+    }
+
+    /// Tallies the changed paths by kind. A path with several kinds of changes is counted under
+    /// the first that applies of conflicted, untracked, deleted, added, and modified.
+    pub fn file_status_counts(&self) -> StatusCounts {
+        let mut counts = StatusCounts::default();
+        for entry in self.statuses_by_path.iter() {
+            let status = entry.status;
+            if status.is_conflicted() {
+                counts.conflicted += 1;
+            } else if status.is_untracked() {
+                counts.untracked += 1;
+            } else if status.is_deleted() {
+                counts.deleted += 1;
+            } else if status.is_created() {
+                counts.added += 1;
+            } else if status.is_modified() {
+                counts.modified += 1;
+            }
+        }
+        counts
+    }
+
+    /// Reads a value such as `core.autocrlf` from the repository's git config, merged with
+    /// the global config. Values are refreshed whenever `.git/config` changes.
+    pub fn config_value(&self, key: &str) -> Option<String> {
+        self.config_values.get(&normalize_config_key(key)).cloned()
+    }
+
+    /// How far the interactive rebase in progress has gotten, as recorded in `.git/rebase-merge`.
+    pub fn rebase_progress(&self) -> Option<RebaseProgress> {
+        self.merge.rebase_progress
+    }
+
+    /// The repository's tags, read from `refs/tags` and `packed-refs`. They're only re-listed
+    /// when the worktree observes a change to the repository's refs.
+    pub fn tags(&self) -> &[TagEntry] {
+        &self.tags
+    }
+
+    /// Whether the repository has no working tree, as declared by its `core.bare` config.
+    /// Bare repositories have no worktree statuses.
+    pub fn is_bare(&self) -> bool {
+        config_declares_bare(&self.config_values)
+    }
+
+    /// The commit message git prepared in `MERGE_MSG` or `SQUASH_MSG` during a merge or squash,
+    /// used to prefill the commit editor.
+    pub fn prepared_commit_message(&self) -> Option<String> {
+        self.merge
+            .message
+            .as_ref()
+            .map(|message| message.to_string())
+    }
+
+    /// The two-character `git status --porcelain` code for the path, such as `" M"` or `"??"`.
+    pub fn porcelain_status(&self, path: &RepoPath) -> Option<[u8; 2]> {
+        self.status_for_path(path)
+            .map(|entry| entry.status.to_bytes())
+    }
+
     pub fn status_for_path(&self, path: &RepoPath) -> Option<StatusEntry> {
         self.statuses_by_path
             .get(&PathKey(path.as_ref().clone()), ())
@@ -3476,6 +3719,7 @@ impl MergeDetails {
     ) -> Result<(MergeDetails, bool)> {
         log::debug!("load merge details");
         let message = backend.merge_message().await;
+        let rebase_progress = backend.rebase_progress().await;
         let heads = backend
             .revparse_batch(vec![
                 "MERGE_HEAD".into(),
@@ -3512,6 +3756,7 @@ impl MergeDetails {
                 return Ok((
                     MergeDetails {
                         message: message.map(SharedString::from),
+                        rebase_progress,
                         ..prev_snapshot.merge.clone()
                     },
                     false,
@@ -3526,6 +3771,7 @@ impl MergeDetails {
             conflicted_paths,
             message: message.map(SharedString::from),
             heads,
+            rebase_progress,
         };
         Ok((details, merge_heads_changed))
     }
@@ -3591,6 +3837,14 @@ impl Repository {
             commit_message_buffer: None,
             askpass_delegates: Default::default(),
             paths_needing_status_update: Default::default(),
+            #[cfg(any(test, feature = "test-support"))]
+            status_entries_recomputed: 0,
+            config_needs_reload: true,
+            tags_need_reload: true,
+            #[cfg(any(test, feature = "test-support"))]
+            status_scans_completed: 0,
+            pending_status_refreshes: Default::default(),
+            debounced_status_scan: None,
             latest_askpass_id: 0,
             job_sender,
             job_id: 0,
@@ -3618,6 +3872,14 @@ impl Repository {
             git_store,
             pending_ops: Default::default(),
             paths_needing_status_update: Default::default(),
+            #[cfg(any(test, feature = "test-support"))]
+            status_entries_recomputed: 0,
+            config_needs_reload: false,
+            tags_need_reload: false,
+            #[cfg(any(test, feature = "test-support"))]
+            status_scans_completed: 0,
+            pending_status_refreshes: Default::default(),
+            debounced_status_scan: None,
             job_sender,
             repository_state,
             askpass_delegates: Default::default(),
@@ -4102,6 +4364,32 @@ impl Repository {
         })
     }
 
+    /// The ID of the blob stored for `repo_path` in the `HEAD` commit, for identifying the
+    /// committed content without loading it. This isn't supported for remote repositories yet.
+    pub fn head_blob_oid(&mut self, repo_path: RepoPath) -> oneshot::Receiver<Result<Option<Oid>>> {
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.head_blob_oid(repo_path).await
+                }
+                RepositoryState::Remote(..) => anyhow::bail!("not implemented yet"),
+            }
+        })
+    }
+
+    /// Whether `repo_path` is tracked by the repository, including files that have no status
+    /// because they're unchanged. This isn't supported for remote repositories yet.
+    pub fn is_path_tracked(&mut self, repo_path: RepoPath) -> oneshot::Receiver<Result<bool>> {
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.is_path_tracked(repo_path).await
+                }
+                RepositoryState::Remote(..) => anyhow::bail!("not implemented yet"),
+            }
+        })
+    }
+
     pub fn load_commit_diff(&mut self, commit: String) -> oneshot::Receiver<Result<CommitDiff>> {
         let id = self.id;
         self.send_job(None, move |git_repo, cx| async move {
@@ -4753,6 +5041,60 @@ impl Repository {
         })
     }
 
+    /// Recomputes how many commits the current branch is ahead of and behind its upstream.
+    ///
+    /// Counting them requires walking the commit graph, so callers can ask for it explicitly
+    /// instead of waiting for the next scan of the repository.
+    pub fn recompute_tracking(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let Some((branch_ref, upstream_ref)) = self.snapshot.branch.as_ref().and_then(|branch| {
+            let upstream = branch.upstream.as_ref()?;
+            Some((branch.ref_name.clone(), upstream.ref_name.clone()))
+        }) else {
+            return Task::ready(Ok(()));
+        };
+
+        let updates_tx = self
+            .git_store()
+            .and_then(|git_store| match &git_store.read(cx).state {
+                GitStoreState::Local { downstream, .. } => downstream
+                    .as_ref()
+                    .map(|downstream| downstream.updates_tx.clone()),
+                _ => None,
+            });
+
+        let this = cx.weak_entity();
+        let rx = self.send_job(None, move |git_repo, mut cx| async move {
+            let RepositoryState::Local(LocalRepositoryState { backend, .. }) = git_repo else {
+                anyhow::bail!("not implemented yet");
+            };
+            let tracking = backend
+                .upstream_tracking_status(branch_ref.to_string(), upstream_ref.to_string())
+                .await?;
+            let snapshot = this.update(&mut cx, |this, cx| {
+                let upstream = this
+                    .snapshot
+                    .branch
+                    .as_mut()
+                    .filter(|branch| branch.ref_name == branch_ref)
+                    .and_then(|branch| branch.upstream.as_mut())
+                    .filter(|upstream| upstream.ref_name == upstream_ref)?;
+                if upstream.tracking == tracking.into() {
+                    return None;
+                }
+                upstream.tracking = tracking.into();
+                cx.emit(RepositoryEvent::BranchChanged);
+                Some(this.snapshot.clone())
+            })?;
+            if let Some((snapshot, updates_tx)) = snapshot.zip(updates_tx) {
+                updates_tx
+                    .unbounded_send(DownstreamUpdate::UpdateRepository(snapshot))
+                    .ok();
+            }
+            Ok(())
+        });
+        cx.spawn(|_, _: &mut AsyncApp| async move { rx.await? })
+    }
+
     pub fn push(
         &mut self,
         branch: SharedString,
@@ -5585,15 +5927,41 @@ impl Repository {
     }
 
     fn schedule_scan(
+        &mut self,
+        updates_tx: Option<mpsc::UnboundedSender<DownstreamUpdate>>,
+        debounce: Duration,
+        cx: &mut Context<Self>,
+    ) {
+        if debounce.is_zero() {
+            self.send_status_scan(updates_tx, cx);
+            return;
+        }
+
+        // Replacing the task cancels the scan scheduled by a previous change in the burst.
+        let pending_refresh = PendingStatusRefresh::new(&self.pending_status_refreshes);
+        self.debounced_status_scan = Some(cx.spawn(async move |this, cx| {
+            let _pending_refresh = pending_refresh;
+            cx.background_executor().timer(debounce).await;
+            this.update(cx, |this, cx| {
+                this.debounced_status_scan = None;
+                this.send_status_scan(updates_tx, cx);
+            })
+            .ok();
+        }));
+    }
+
+    fn send_status_scan(
         &mut self,
         updates_tx: Option<mpsc::UnboundedSender<DownstreamUpdate>>,
         cx: &mut Context<Self>,
     ) {
         let this = cx.weak_entity();
+        let pending_refresh = PendingStatusRefresh::new(&self.pending_status_refreshes);
         let _ = self.send_keyed_job(
             Some(GitJobKey::ReloadGitState),
             None,
             |state, mut cx| async move {
+                let _pending_refresh = pending_refresh;
                 log::debug!("run scheduled git status scan");
 
                 let Some(this) = this.upgrade() else {
@@ -5602,19 +5970,26 @@ impl Repository {
                 let RepositoryState::Local(LocalRepositoryState { backend, .. }) = state else {
                     bail!("not a local repository")
                 };
-                let (snapshot, events) = this
+                let (snapshot, events, _status_entries_recomputed) = this
                     .update(&mut cx, |this, _| {
-                        this.paths_needing_status_update.clear();
                         compute_snapshot(
                             this.id,
                             this.work_directory_abs_path.clone(),
                             this.snapshot.clone(),
                             backend.clone(),
+                            mem::take(&mut this.paths_needing_status_update),
+                            mem::take(&mut this.config_needs_reload),
+                            mem::take(&mut this.tags_need_reload),
                         )
                     })
                     .await?;
                 this.update(&mut cx, |this, cx| {
                     this.snapshot = snapshot.clone();
+                    #[cfg(any(test, feature = "test-support"))]
+                    {
+                        this.status_entries_recomputed += _status_entries_recomputed;
+                        this.status_scans_completed += 1;
+                    }
                     this.clear_pending_ops(cx);
                     for event in events {
                         cx.emit(event);
@@ -5814,11 +6189,13 @@ impl Repository {
         self.paths_needing_status_update.extend(paths);
 
         let this = cx.weak_entity();
+        let pending_refresh = PendingStatusRefresh::new(&self.pending_status_refreshes);
         let _ = self.send_keyed_job(
             Some(GitJobKey::RefreshStatuses),
             None,
             |state, mut cx| async move {
-                let (prev_snapshot, mut changed_paths) = this.update(&mut cx, |this, _| {
+                let _pending_refresh = pending_refresh;
+                let (prev_snapshot, changed_paths) = this.update(&mut cx, |this, _| {
                     (
                         this.snapshot.clone(),
                         mem::take(&mut this.paths_needing_status_update),
@@ -5829,7 +6206,7 @@ impl Repository {
                 };
 
                 let paths = changed_paths.iter().cloned().collect::<Vec<_>>();
-                if paths.is_empty() {
+                if paths.is_empty() || prev_snapshot.is_bare() {
                     return Ok(());
                 }
                 let statuses = backend.status(&paths).await?;
@@ -5837,35 +6214,20 @@ impl Repository {
 
                 let changed_path_statuses = cx
                     .background_spawn(async move {
-                        let mut changed_path_statuses = Vec::new();
-                        let prev_statuses = prev_snapshot.statuses_by_path.clone();
-                        let mut cursor = prev_statuses.cursor::<PathProgress>(());
-
-                        for (repo_path, status) in &*statuses.entries {
-                            changed_paths.remove(repo_path);
-                            if cursor.seek_forward(&PathTarget::Path(repo_path), Bias::Left)
-                                && cursor.item().is_some_and(|entry| entry.status == *status)
-                            {
-                                continue;
-                            }
-
-                            changed_path_statuses.push(Edit::Insert(StatusEntry {
-                                repo_path: repo_path.clone(),
-                                status: *status,
-                            }));
-                        }
-                        let mut cursor = prev_statuses.cursor::<PathProgress>(());
-                        for path in changed_paths.into_iter() {
-                            if cursor.seek_forward(&PathTarget::Path(&path), Bias::Left) {
-                                changed_path_statuses
-                                    .push(Edit::Remove(PathKey(path.as_ref().clone())));
-                            }
-                        }
-                        changed_path_statuses
+                        path_status_edits(
+                            &prev_snapshot.statuses_by_path,
+                            changed_paths,
+                            &statuses.entries,
+                        )
                     })
                     .await;
 
                 this.update(&mut cx, |this, cx| {
+                    #[cfg(any(test, feature = "test-support"))]
+                    {
+                        this.status_entries_recomputed += paths.len();
+                    }
+
                     if this.snapshot.stash_entries != stash_entries {
                         cx.emit(RepositoryEvent::StashEntriesChanged);
                         this.snapshot.stash_entries = stash_entries;
@@ -5896,6 +6258,27 @@ impl Repository {
         self.active_jobs.values().next().cloned()
     }
 
+    /// How many status entries have been computed by git, counting every entry reported by a
+    /// full scan and every path examined by a partial one.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn status_entries_recomputed_count(&self) -> usize {
+        self.status_entries_recomputed
+    }
+
+    /// How many full status scans have completed for this repository.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn status_scan_count(&self) -> usize {
+        self.status_scans_completed
+    }
+
+    /// Whether a status refresh is queued or in flight, meaning the cached statuses may be
+    /// about to change. The UI can use this to show progress instead of flickering.
+    pub fn is_status_stale(&self) -> bool {
+        self.pending_status_refreshes
+            .load(atomic::Ordering::Acquire)
+            > 0
+    }
+
     pub fn barrier(&mut self) -> oneshot::Receiver<()> {
         self.send_job(None, |_, _| async {})
     }
@@ -6200,31 +6583,93 @@ fn proto_to_commit_details(proto: &proto::GitCommitDetails) -> CommitDetails {
     }
 }
 
+/// Tracks a queued status refresh job. The count is decremented when the job finishes, or
+/// when it is dropped without running because a newer job with the same key superseded it.
+struct PendingStatusRefresh(Arc<AtomicUsize>);
+
+impl PendingStatusRefresh {
+    fn new(pending_status_refreshes: &Arc<AtomicUsize>) -> Self {
+        pending_status_refreshes.fetch_add(1, atomic::Ordering::AcqRel);
+        Self(pending_status_refreshes.clone())
+    }
+}
+
+impl Drop for PendingStatusRefresh {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, atomic::Ordering::AcqRel);
+    }
+}
+
 async fn compute_snapshot(
     id: RepositoryId,
     work_directory_abs_path: Arc<Path>,
     prev_snapshot: RepositorySnapshot,
     backend: Arc<dyn GitRepository>,
-) -> Result<(RepositorySnapshot, Vec<RepositoryEvent>)> {
+    changed_paths: BTreeSet<RepoPath>,
+    reload_config: bool,
+    reload_tags: bool,
+) -> Result<(RepositorySnapshot, Vec<RepositoryEvent>, usize)> {
     let mut events = Vec::new();
     let branches = backend.branches().await?;
     let branch = branches.into_iter().find(|branch| branch.is_head);
-    let statuses = backend
-        .status(&[RepoPath::from_rel_path(
-            &RelPath::new(".".as_ref(), PathStyle::local()).unwrap(),
-        )])
-        .await?;
+    let config_values = if reload_config
+        && let Some(config_values) = backend.config_values().await.log_err()
+        && config_values != *prev_snapshot.config_values
+    {
+        Arc::new(config_values)
+    } else {
+        prev_snapshot.config_values.clone()
+    };
+    let head_sha = backend.head_sha().await;
+    let head_moved = head_sha.as_deref()
+        != prev_snapshot
+            .head_commit
+            .as_ref()
+            .map(|head| head.sha.as_ref());
+    let index_entries = backend.index_entries().await.log_err().map(Arc::new);
+    let (status_edits, status_entries_recomputed) = if config_declares_bare(&config_values) {
+        (status_edits(&prev_snapshot.statuses_by_path, &[]), 0)
+    } else if let Some(prev_index_entries) = &prev_snapshot.index_entries
+        && let Some(index_entries) = &index_entries
+        && !reload_config
+        && !head_moved
+    {
+        // With HEAD and the config unchanged, a path's status can only have changed if its
+        // index entry did, or if the worktree reported a change to it since the last scan.
+        let mut paths = changed_paths;
+        paths.extend(changed_index_paths(prev_index_entries, index_entries));
+        let status_paths = paths.iter().cloned().collect::<Vec<_>>();
+        let edits = if status_paths.is_empty() {
+            Vec::new()
+        } else {
+            let statuses = backend.status(&status_paths).await?;
+            path_status_edits(&prev_snapshot.statuses_by_path, paths, &statuses.entries)
+        };
+        (edits, status_paths.len())
+    } else {
+        let statuses = backend
+            .status(&[RepoPath::from_rel_path(
+                &RelPath::new(".".as_ref(), PathStyle::local()).unwrap(),
+            )])
+            .await?
+            .entries;
+        (
+            status_edits(&prev_snapshot.statuses_by_path, &statuses),
+            statuses.len(),
+        )
+    };
     let stash_entries = backend.stash_entries().await?;
-    let statuses_by_path = SumTree::from_iter(
-        statuses
-            .entries
-            .iter()
-            .map(|(repo_path, status)| StatusEntry {
-                repo_path: repo_path.clone(),
-                status: *status,
-            }),
-        (),
-    );
+    let tags = if reload_tags
+        && let Some(tags) = backend.tags().await.log_err()
+        && tags != *prev_snapshot.tags
+    {
+        Arc::new(tags)
+    } else {
+        prev_snapshot.tags.clone()
+    };
+    let status_edit_count = status_edits.len();
+    let mut statuses_by_path = prev_snapshot.statuses_by_path.clone();
+    statuses_by_path.edit(status_edits, ());
     let (merge_details, merge_heads_changed) =
         MergeDetails::load(&backend, &statuses_by_path, &prev_snapshot).await?;
     log::debug!("new merge details (changed={merge_heads_changed:?}): {merge_details:?}");
@@ -6233,12 +6678,12 @@ async fn compute_snapshot(
         events.push(RepositoryEvent::MergeHeadsChanged);
     }
 
-    if statuses_by_path != prev_snapshot.statuses_by_path {
+    if status_edit_count > 0 {
         events.push(RepositoryEvent::StatusesChanged)
     }
 
     // Useful when branch is None in detached head state
-    let head_commit = match backend.head_sha().await {
+    let head_commit = match head_sha {
         Some(head_sha) => backend.show(head_sha).await.log_err(),
         None => None,
     };
@@ -6261,10 +6706,122 @@ async fn compute_snapshot(
         merge: merge_details,
         remote_origin_url,
         remote_upstream_url,
+        config_values,
         stash_entries,
+        tags,
+        is_submodule: prev_snapshot.is_submodule,
+        index_entries,
+    };
+
+    Ok((snapshot, events, status_entries_recomputed))
+}
+
+fn is_submodule_git_dir(common_dir_abs_path: &Path) -> bool {
+    common_dir_abs_path.ancestors().any(|ancestor| {
+        ancestor.file_name() == Some(OsStr::new("modules"))
+            && ancestor.parent().and_then(Path::file_name) == Some(OsStr::new(DOT_GIT))
+    })
+}
+
+fn config_declares_bare(config_values: &HashMap<String, String>) -> bool {
+    config_values
+        .get("core.bare")
+        .is_some_and(|value| matches!(value.to_lowercase().as_str(), "true" | "yes" | "on" | "1"))
+}
+
+/// Section and variable names in git config keys are case-insensitive, while subsection
+/// names (e.g. the `origin` in `remote.origin.url`) are not.
+fn normalize_config_key(key: &str) -> String {
+    let Some((section, rest)) = key.split_once('.') else {
+        return key.to_lowercase();
     };
+    match rest.rsplit_once('.') {
+        Some((subsection, variable)) => format!(
+            "{}.{subsection}.{}",
+            section.to_lowercase(),
+            variable.to_lowercase()
+        ),
+        None => format!("{}.{}", section.to_lowercase(), rest.to_lowercase()),
+    }
+}
+
+/// Diffs the statuses from a full scan against the previous ones, so that only entries whose
+/// status actually changed are edited into the status tree.
+fn status_edits(
+    prev_statuses: &SumTree<StatusEntry>,
+    statuses: &[(RepoPath, FileStatus)],
+) -> Vec<Edit<StatusEntry>> {
+    let mut edits = Vec::new();
+    let mut prev_entries = prev_statuses.iter().peekable();
+    for (repo_path, status) in statuses {
+        while let Some(prev_entry) = prev_entries.next_if(|entry| entry.repo_path < *repo_path) {
+            edits.push(Edit::Remove(PathKey(prev_entry.repo_path.as_ref().clone())));
+        }
+        if prev_entries
+            .next_if(|entry| entry.repo_path == *repo_path)
+            .is_some_and(|entry| entry.status == *status)
+        {
+            continue;
+        }
+        edits.push(Edit::Insert(StatusEntry {
+            repo_path: repo_path.clone(),
+            status: *status,
+        }));
+    }
+    for prev_entry in prev_entries {
+        edits.push(Edit::Remove(PathKey(prev_entry.repo_path.as_ref().clone())));
+    }
+    edits
+}
 
-    Ok((snapshot, events))
+/// Diffs the statuses freshly computed for `paths` against the previous ones. Paths that no
+/// longer have a status are removed.
+fn path_status_edits(
+    prev_statuses: &SumTree<StatusEntry>,
+    mut paths: BTreeSet<RepoPath>,
+    statuses: &[(RepoPath, FileStatus)],
+) -> Vec<Edit<StatusEntry>> {
+    let mut edits = Vec::new();
+    let mut cursor = prev_statuses.cursor::<PathProgress>(());
+    for (repo_path, status) in statuses {
+        paths.remove(repo_path);
+        if cursor.seek_forward(&PathTarget::Path(repo_path), Bias::Left)
+            && cursor.item().is_some_and(|entry| entry.status == *status)
+        {
+            continue;
+        }
+
+        edits.push(Edit::Insert(StatusEntry {
+            repo_path: repo_path.clone(),
+            status: *status,
+        }));
+    }
+    let mut cursor = prev_statuses.cursor::<PathProgress>(());
+    for path in paths {
+        if cursor.seek_forward(&PathTarget::Path(&path), Bias::Left) {
+            edits.push(Edit::Remove(PathKey(path.as_ref().clone())));
+        }
+    }
+    edits
+}
+
+/// Lists the paths whose index entries differ between two listings of the index.
+fn changed_index_paths(
+    prev_entries: &[(RepoPath, IndexEntry)],
+    entries: &[(RepoPath, IndexEntry)],
+) -> BTreeSet<RepoPath> {
+    let mut entries_by_path = HashMap::<&RepoPath, (Vec<IndexEntry>, Vec<IndexEntry>)>::default();
+    for (path, entry) in prev_entries {
+        entries_by_path.entry(path).or_default().0.push(*entry);
+    }
+    for (path, entry) in entries {
+        entries_by_path.entry(path).or_default().1.push(*entry);
+    }
+    entries_by_path
+        .into_iter()
+        .filter(|(_, (prev_entries, entries))| prev_entries != entries)
+        .map(|(path, _)| path.clone())
+        .collect()
 }
 
 fn status_from_proto(
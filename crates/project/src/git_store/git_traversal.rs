@@ -1,6 +1,13 @@
 use collections::HashMap;
-use git::{repository::RepoPath, status::GitSummary};
-use std::{collections::BTreeMap, ops::Deref, path::Path};
+use git::{
+    repository::RepoPath,
+    status::{GitSummary, TrackedSummary},
+};
+use std::{
+    collections::BTreeMap,
+    ops::{Bound, Deref},
+    path::Path,
+};
 use sum_tree::Cursor;
 use text::Bias;
 use util::rel_path::RelPath;
@@ -8,10 +15,19 @@ use worktree::{Entry, PathProgress, PathTarget, Traversal};
 
 use super::{RepositoryId, RepositorySnapshot, StatusEntry};
 
+/// The summary contributed by a submodule with uncommitted changes to the directories
+/// containing it, when submodule statuses are summarized.
+const DIRTY_SUBMODULE: GitSummary = GitSummary {
+    worktree: TrackedSummary::MODIFIED,
+    count: 1,
+    ..GitSummary::UNCHANGED
+};
+
 /// Walks the worktree entries and their associated git statuses.
 pub struct GitTraversal<'a> {
     traversal: Traversal<'a>,
     current_entry_summary: Option<GitSummary>,
+    summarize_submodules: bool,
     repo_root_to_snapshot: BTreeMap<&'a Path, &'a RepositorySnapshot>,
     repo_location: Option<(
         RepositoryId,
@@ -31,6 +47,7 @@ impl<'a> GitTraversal<'a> {
         let mut this = GitTraversal {
             traversal,
             current_entry_summary: None,
+            summarize_submodules: false,
             repo_location: None,
             repo_root_to_snapshot,
         };
@@ -38,6 +55,14 @@ impl<'a> GitTraversal<'a> {
         this
     }
 
+    /// Folds each submodule with uncommitted changes into the summaries of the directories
+    /// containing it, as a single modified entry.
+    pub fn with_submodule_summaries(mut self, summarize_submodules: bool) -> Self {
+        self.summarize_submodules = summarize_submodules;
+        self.synchronize_statuses(true);
+        self
+    }
+
     fn repo_root_for_path(&self, path: &Path) -> Option<(&'a RepositorySnapshot, RepoPath)> {
         // We might need to perform a range search multiple times, as there may be a nested repository inbetween
         // the target and our path. E.g:
@@ -96,7 +121,10 @@ impl<'a> GitTraversal<'a> {
         if entry.is_dir() {
             let mut statuses = statuses.clone();
             statuses.seek_forward(&PathTarget::Path(&repo_path), Bias::Left);
-            let summary = statuses.summary(&PathTarget::Successor(&repo_path), Bias::Left);
+            let mut summary = statuses.summary(&PathTarget::Successor(&repo_path), Bias::Left);
+            if self.summarize_submodules {
+                summary += self.dirty_submodules_summary(&abs_path);
+            }
 
             self.current_entry_summary = Some(summary);
         } else if entry.is_file() {
@@ -110,6 +138,38 @@ impl<'a> GitTraversal<'a> {
         }
     }
 
+    fn dirty_submodules_summary(&self, dir_abs_path: &Path) -> GitSummary {
+        let mut summary = GitSummary::UNCHANGED;
+        let nested_repos = self
+            .repo_root_to_snapshot
+            .range::<&Path, _>((Bound::Excluded(dir_abs_path), Bound::Unbounded))
+            .take_while(|(work_directory_abs_path, _)| {
+                work_directory_abs_path.starts_with(dir_abs_path)
+            });
+        for (work_directory_abs_path, submodule) in nested_repos {
+            if !submodule.is_submodule || submodule.status_summary().count == 0 {
+                continue;
+            }
+            // Git may already report the submodule as modified in its superproject.
+            let superproject = work_directory_abs_path
+                .parent()
+                .and_then(|parent| self.repo_root_for_path(parent))
+                .and_then(|(superproject, _)| {
+                    Some((
+                        superproject,
+                        superproject.abs_path_to_repo_path(work_directory_abs_path)?,
+                    ))
+                });
+            if let Some((superproject, submodule_repo_path)) = superproject
+                && superproject.status_for_path(&submodule_repo_path).is_some()
+            {
+                continue;
+            }
+            summary += DIRTY_SUBMODULE;
+        }
+        summary
+    }
+
     pub fn advance(&mut self) -> bool {
         let found = self.traversal.advance_by(1);
         self.synchronize_statuses(false);
@@ -193,6 +253,33 @@ impl<'a> Iterator for ChildEntriesGitIter<'a> {
     }
 }
 
+/// Sums the git summaries of the given worktree paths, which may belong to different
+/// repositories. A directory contributes the statuses of everything beneath it, and
+/// paths nested inside another given path are only counted once.
+pub fn git_summary_for_paths<'a>(
+    repo_snapshots: &HashMap<RepositoryId, RepositorySnapshot>,
+    worktree_snapshot: &worktree::Snapshot,
+    paths: impl IntoIterator<Item = &'a RelPath>,
+) -> GitSummary {
+    let mut paths = paths.into_iter().collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup_by(|path, ancestor| path.starts_with(ancestor));
+
+    let mut summary = GitSummary::UNCHANGED;
+    for path in paths {
+        let traversal = GitTraversal::new(
+            repo_snapshots,
+            worktree_snapshot.traverse_from_path(true, true, true, path),
+        );
+        if let Some(entry) = traversal.entry()
+            && entry.path.as_ref() == path
+        {
+            summary += entry.git_summary;
+        }
+    }
+    summary
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct GitEntryRef<'a> {
     pub entry: &'a Entry,
@@ -255,14 +342,17 @@ impl AsRef<Entry> for GitEntry {
 mod tests {
     use std::time::Duration;
 
-    use crate::Project;
+    use crate::{Project, project_settings::ProjectSettings};
 
     use super::*;
     use fs::FakeFs;
-    use git::status::{FileStatus, StatusCode, TrackedSummary, UnmergedStatus, UnmergedStatusCode};
+    use git::{
+        repository::repo_path,
+        status::{FileStatus, StatusCode, TrackedSummary, UnmergedStatus, UnmergedStatusCode},
+    };
     use gpui::TestAppContext;
     use serde_json::json;
-    use settings::SettingsStore;
+    use settings::{Settings as _, SettingsStore};
     use util::{path, rel_path::rel_path};
 
     const CONFLICT: FileStatus = FileStatus::Unmerged(UnmergedStatus {
@@ -479,6 +569,116 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_summarize_submodule_status(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                ".git": {
+                    "modules": {
+                        "dir": {
+                            "sub": {
+                                "HEAD": "",
+                                "config": "",
+                            }
+                        }
+                    }
+                },
+                "a.txt": "",
+                "dir": {
+                    "sub": {
+                        ".git": "gitdir: ../../.git/modules/dir/sub\n",
+                        "c.txt": "",
+                    },
+                    "vendored": {
+                        ".git": {},
+                        "v.txt": "",
+                    },
+                },
+            }),
+        )
+        .await;
+
+        fs.set_status_for_repo(Path::new(path!("/root/.git")), &[]);
+        fs.set_status_for_repo(
+            Path::new(path!("/root/dir/sub/.git")),
+            &[("c.txt", StatusCode::Modified.worktree())],
+        );
+        fs.set_status_for_repo(
+            Path::new(path!("/root/dir/vendored/.git")),
+            &[("v.txt", StatusCode::Modified.worktree())],
+        );
+
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        cx.executor().run_until_parked();
+
+        let (repo_snapshots, worktree_snapshot) = project.read_with(cx, |project, cx| {
+            (
+                project.git_store().read(cx).repo_snapshots(cx),
+                project.worktrees(cx).next().unwrap().read(cx).snapshot(),
+            )
+        });
+        let submodules = repo_snapshots
+            .values()
+            .filter(|repo| repo.is_submodule)
+            .map(|repo| repo.work_directory_abs_path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(submodules, [Path::new(path!("/root/dir/sub")).into()]);
+
+        let summaries = |cx: &mut TestAppContext| {
+            let summarize_submodules = cx.read(|cx| {
+                ProjectSettings::get_global(cx)
+                    .git
+                    .summarize_submodule_status
+            });
+            GitTraversal::new(
+                &repo_snapshots,
+                worktree_snapshot.traverse_from_path(true, true, false, RelPath::empty()),
+            )
+            .with_submodule_summaries(summarize_submodules)
+            .filter(|entry| entry.is_dir())
+            .map(|entry| (entry.path.clone(), entry.git_summary))
+            .collect::<Vec<_>>()
+        };
+
+        let dirty = GitSummary {
+            worktree: TrackedSummary::MODIFIED,
+            count: 1,
+            ..GitSummary::UNCHANGED
+        };
+        pretty_assertions::assert_eq!(
+            summaries(cx),
+            [
+                (rel_path("").into(), GitSummary::UNCHANGED),
+                (rel_path("dir").into(), GitSummary::UNCHANGED),
+                (rel_path("dir/sub").into(), dirty),
+                (rel_path("dir/vendored").into(), dirty),
+            ]
+        );
+
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .git
+                        .get_or_insert_default()
+                        .summarize_submodule_status = Some(true);
+                });
+            });
+        });
+        pretty_assertions::assert_eq!(
+            summaries(cx),
+            [
+                (rel_path("").into(), dirty),
+                (rel_path("dir").into(), dirty),
+                (rel_path("dir/sub").into(), dirty),
+                (rel_path("dir/vendored").into(), dirty),
+            ]
+        );
+    }
+
     #[gpui::test]
     async fn test_git_traversal_simple(cx: &mut TestAppContext) {
         init_test(cx);
@@ -682,6 +882,292 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_git_summary_for_paths_across_repos(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "x": {
+                    ".git": {},
+                    "x1.txt": "foo",
+                    "x2.txt": "bar"
+                },
+                "y": {
+                    ".git": {},
+                    "y1.txt": "baz",
+                    "y2.txt": "qux"
+                },
+                "z": {
+                    ".git": {},
+                    "z1.txt": "quux",
+                    "z2.txt": "quuux"
+                }
+            }),
+        )
+        .await;
+
+        fs.set_status_for_repo(
+            Path::new(path!("/root/x/.git")),
+            &[("x1.txt", StatusCode::Added.index())],
+        );
+        fs.set_status_for_repo(
+            Path::new(path!("/root/y/.git")),
+            &[
+                ("y1.txt", CONFLICT),
+                ("y2.txt", StatusCode::Modified.index()),
+            ],
+        );
+        fs.set_status_for_repo(
+            Path::new(path!("/root/z/.git")),
+            &[("z2.txt", StatusCode::Modified.index())],
+        );
+
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        cx.executor().run_until_parked();
+
+        let (repo_snapshots, worktree_snapshot) = project.read_with(cx, |project, cx| {
+            (
+                project.git_store().read(cx).repo_snapshots(cx),
+                project.worktrees(cx).next().unwrap().read(cx).snapshot(),
+            )
+        });
+
+        assert_eq!(
+            git_summary_for_paths(
+                &repo_snapshots,
+                &worktree_snapshot,
+                [
+                    rel_path("x/x1.txt"),
+                    rel_path("y/y1.txt"),
+                    rel_path("z/z1.txt")
+                ],
+            ),
+            ADDED + GitSummary::CONFLICT
+        );
+        assert_eq!(
+            git_summary_for_paths(
+                &repo_snapshots,
+                &worktree_snapshot,
+                [rel_path("y"), rel_path("y/y2.txt"), rel_path("z/z2.txt")],
+            ),
+            GitSummary::CONFLICT + MODIFIED + MODIFIED
+        );
+        assert_eq!(
+            git_summary_for_paths(&repo_snapshots, &worktree_snapshot, std::iter::empty()),
+            GitSummary::UNCHANGED
+        );
+    }
+
+    #[gpui::test]
+    async fn test_git_status_summary_by_repo(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "x": {
+                    ".git": {},
+                    "x1.txt": "foo",
+                    "x2.txt": "bar"
+                },
+                "y": {
+                    ".git": {},
+                    "y1.txt": "baz",
+                    "y2.txt": "qux"
+                },
+                "z": {
+                    ".git": {},
+                    "z1.txt": "quux",
+                    "z2.txt": "quuux"
+                }
+            }),
+        )
+        .await;
+
+        fs.set_status_for_repo(
+            Path::new(path!("/root/x/.git")),
+            &[("x1.txt", StatusCode::Added.index())],
+        );
+        fs.set_status_for_repo(
+            Path::new(path!("/root/y/.git")),
+            &[
+                ("y1.txt", CONFLICT),
+                ("y2.txt", StatusCode::Modified.index()),
+            ],
+        );
+        fs.set_status_for_repo(
+            Path::new(path!("/root/z/.git")),
+            &[("z2.txt", StatusCode::Modified.index())],
+        );
+
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        cx.executor().run_until_parked();
+
+        let summaries = project.read_with(cx, |project, cx| {
+            project.git_store().read(cx).git_status_summary_by_repo(cx)
+        });
+        assert_eq!(
+            summaries
+                .iter()
+                .map(|(work_directory, summary)| (work_directory.as_ref(), *summary))
+                .collect::<Vec<_>>(),
+            [
+                (Path::new(path!("/root/x")), ADDED),
+                (Path::new(path!("/root/y")), GitSummary::CONFLICT + MODIFIED),
+                (Path::new(path!("/root/z")), MODIFIED),
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_status_summary_for_directory_per_repo(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "x": {
+                    ".git": {},
+                    "a": {
+                        "x1.txt": "foo",
+                    },
+                },
+                "y": {
+                    ".git": {},
+                    "b": {
+                        "y1.txt": "bar",
+                        "y2.txt": "baz",
+                    },
+                },
+            }),
+        )
+        .await;
+        fs.set_status_for_repo(
+            Path::new(path!("/root/x/.git")),
+            &[("a/x1.txt", StatusCode::Added.index())],
+        );
+        fs.set_status_for_repo(
+            Path::new(path!("/root/y/.git")),
+            &[("b/y1.txt", StatusCode::Modified.index())],
+        );
+
+        let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+        cx.executor().run_until_parked();
+
+        let repo_snapshot = |work_directory: &str, cx: &mut TestAppContext| {
+            project.read_with(cx, |project, cx| {
+                project
+                    .git_store()
+                    .read(cx)
+                    .repo_snapshots(cx)
+                    .into_values()
+                    .find(|snapshot| {
+                        snapshot.work_directory_abs_path.as_ref() == Path::new(work_directory)
+                    })
+                    .unwrap()
+            })
+        };
+
+        let x_snapshot = repo_snapshot(path!("/root/x"), cx);
+        let y_snapshot = repo_snapshot(path!("/root/y"), cx);
+        assert_eq!(
+            x_snapshot.status_summary_for_directory(&repo_path("a")),
+            ADDED
+        );
+        assert_eq!(
+            y_snapshot.status_summary_for_directory(&repo_path("b")),
+            MODIFIED
+        );
+
+        fs.set_status_for_repo(
+            Path::new(path!("/root/y/.git")),
+            &[
+                ("b/y1.txt", StatusCode::Modified.index()),
+                ("b/y2.txt", StatusCode::Modified.index()),
+            ],
+        );
+        cx.executor().run_until_parked();
+
+        let new_x_snapshot = repo_snapshot(path!("/root/x"), cx);
+        let new_y_snapshot = repo_snapshot(path!("/root/y"), cx);
+        assert_eq!(
+            new_y_snapshot.status_summary_for_directory(&repo_path("b")),
+            MODIFIED + MODIFIED
+        );
+        assert!(new_y_snapshot.scan_id > y_snapshot.scan_id);
+        // The other repository's statuses were not rescanned.
+        assert_eq!(new_x_snapshot.scan_id, x_snapshot.scan_id);
+        assert_eq!(
+            new_x_snapshot.status_summary_for_directory(&repo_path("a")),
+            ADDED
+        );
+    }
+
+    #[gpui::test]
+    async fn test_status_for_directory(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                ".git": {},
+                "a": {
+                    "b": {
+                        "c1.txt": "",
+                        "c2.txt": "",
+                    },
+                    "d": {
+                        "e1.txt": "",
+                        "e2.txt": "",
+                    },
+                },
+                "f": {
+                    "no-status.txt": "",
+                },
+            }),
+        )
+        .await;
+        fs.set_status_for_repo(
+            Path::new(path!("/root/.git")),
+            &[
+                ("a/b/c1.txt", StatusCode::Added.index()),
+                ("a/d/e2.txt", StatusCode::Modified.index()),
+            ],
+        );
+
+        let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+        cx.executor().run_until_parked();
+
+        let repo_snapshot = project.read_with(cx, |project, cx| {
+            project
+                .git_store()
+                .read(cx)
+                .repo_snapshots(cx)
+                .into_values()
+                .next()
+                .unwrap()
+        });
+
+        assert_eq!(
+            repo_snapshot.status_for_directory(&repo_path("a"), true),
+            ADDED + MODIFIED
+        );
+        assert_eq!(
+            repo_snapshot.status_for_directory(&repo_path("a/b/c1.txt"), false),
+            ADDED
+        );
+        assert_eq!(
+            repo_snapshot.status_for_directory(&repo_path("a"), false),
+            GitSummary::UNCHANGED
+        );
+        assert_eq!(
+            repo_snapshot.status_for_directory(&repo_path("f"), true),
+            GitSummary::UNCHANGED
+        );
+    }
+
     fn init_test(cx: &mut gpui::TestAppContext) {
         zlog::init_test();
 
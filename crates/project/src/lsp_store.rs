@@ -4209,7 +4209,9 @@ impl LspStore {
                         this.update_local_worktree_language_servers(&worktree, changes, cx);
                     }
                     worktree::Event::UpdatedGitRepositories(_)
-                    | worktree::Event::DeletedEntry(_) => {}
+                    | worktree::Event::DeletedEntry(_)
+                    | worktree::Event::RootChanged { .. }
+                    | worktree::Event::GitConfigChanged { .. } => {}
                 })
                 .detach()
             }
@@ -50,7 +50,9 @@ impl WorktreeRoots {
                             }
                         }
                     }
-                    WorktreeEvent::UpdatedGitRepositories(_) => {}
+                    WorktreeEvent::UpdatedGitRepositories(_)
+                    | WorktreeEvent::RootChanged { .. }
+                    | WorktreeEvent::GitConfigChanged { .. } => {}
                     WorktreeEvent::DeletedEntry(entry_id) => {
                         let Some(entry) = this.worktree_store.read(cx).entry_for_id(*entry_id, cx)
                         else {
@@ -46,7 +46,9 @@ use crate::{
 pub use agent_server_store::{AgentServerStore, AgentServersUpdated, ExternalAgentServerName};
 pub use git_store::{
     ConflictRegion, ConflictSet, ConflictSetSnapshot, ConflictSetUpdate,
-    git_traversal::{ChildEntriesGitIter, GitEntry, GitEntryRef, GitTraversal},
+    git_traversal::{
+        ChildEntriesGitIter, GitEntry, GitEntryRef, GitTraversal, git_summary_for_paths,
+    },
 };
 pub use manifest_tree::ManifestTree;
 pub use project_search::{Search, SearchResults};
@@ -2381,7 +2383,7 @@ impl Project {
     pub fn delete_file(
         &mut self,
         path: ProjectPath,
-        trash: bool,
+        trash: Option<bool>,
         cx: &mut Context<Self>,
     ) -> Option<Task<Result<()>>> {
         let entry = self.entry_for_path(&path, cx)?;
@@ -2392,7 +2394,7 @@ impl Project {
     pub fn delete_entry(
         &mut self,
         entry_id: ProjectEntryId,
-        trash: bool,
+        trash: Option<bool>,
         cx: &mut Context<Self>,
     ) -> Option<Task<Result<()>>> {
         let worktree = self.worktree_for_entry(entry_id, cx)?;
@@ -5529,6 +5531,10 @@ impl Project {
         self.git_store.read(cx).repositories()
     }
 
+    pub fn repositories_sorted_by_path(&self, cx: &App) -> Vec<Entity<Repository>> {
+        self.git_store.read(cx).repositories_sorted_by_path(cx)
+    }
+
     pub fn status_for_buffer_id(&self, buffer_id: BufferId, cx: &App) -> Option<FileStatus> {
         self.git_store.read(cx).status_for_buffer_id(buffer_id, cx)
     }
@@ -1091,6 +1091,12 @@ mod tests {
             size: 0,
             char_bag: Default::default(),
             is_fifo: false,
+            is_broken_symlink: false,
+            symlink_target: None,
+            is_git_internal: false,
+            is_binary: None,
+            is_lfs_pointer: None,
+            content_hash: None,
         };
 
         // 1. Test searching for `field`, including ignored files without any
@@ -402,6 +402,15 @@ pub struct GitSettings {
     ///
     /// Default: file_name_first
     pub path_style: GitPathStyle,
+    /// Whether a submodule with uncommitted changes marks the directories containing it as
+    /// modified.
+    ///
+    /// Default: false
+    pub summarize_submodule_status: bool,
+    /// How long, in milliseconds, git status refreshes wait for further repository changes.
+    ///
+    /// Default: 0
+    pub status_refresh_debounce_ms: u64,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -591,6 +600,8 @@ impl Settings for ProjectSettings {
             },
             hunk_style: git.hunk_style.unwrap(),
             path_style: git.path_style.unwrap().into(),
+            summarize_submodule_status: git.summarize_submodule_status.unwrap(),
+            status_refresh_debounce_ms: git.status_refresh_debounce_ms.unwrap(),
         };
         Self {
             context_servers: project
@@ -2,7 +2,7 @@
 
 use crate::{
     Event,
-    git_store::{GitStoreEvent, RepositoryEvent, StatusEntry, pending_op},
+    git_store::{GitStoreEvent, RepositoryEvent, StatusCounts, StatusEntry, pending_op},
     task_inventory::TaskContexts,
     task_store::TaskSettingsLocation,
     *,
@@ -15,7 +15,7 @@ use fs::FakeFs;
 use futures::{StreamExt, future};
 use git::{
     GitHostingProviderRegistry,
-    repository::{RepoPath, repo_path},
+    repository::{RebaseProgress, RepoPath, TagEntry, UpstreamTrackingStatus, repo_path},
     status::{StatusCode, TrackedStatus},
 };
 use git2::RepositoryInitOptions;
@@ -59,7 +59,7 @@ use util::{
     test::{TempTree, marked_text_offsets},
     uri,
 };
-use worktree::WorktreeModelHandle as _;
+use worktree::{WorkDirectory, WorktreeModelHandle as _};
 
 #[gpui::test]
 async fn test_block_via_channel(cx: &mut gpui::TestAppContext) {
@@ -8368,6 +8368,129 @@ async fn test_repository_and_path_for_project_path(
     });
 }
 
+#[gpui::test]
+async fn test_repository_and_status_for_project_path(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            "outer": {
+                ".git": {},
+                "clean.txt": "",
+                "new.txt": "",
+                "inner": {
+                    ".git": {},
+                    "changed.txt": "",
+                },
+            },
+            "outside.txt": "",
+        }),
+    )
+    .await;
+    fs.set_status_for_repo(
+        path!("/root/outer/.git").as_ref(),
+        &[("new.txt", FileStatus::Untracked)],
+    );
+    fs.set_status_for_repo(
+        path!("/root/outer/inner/.git").as_ref(),
+        &[("changed.txt", StatusCode::Modified.worktree())],
+    );
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    let tree_id = project.read_with(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    project.read_with(cx, |project, cx| {
+        let git_store = project.git_store().read(cx);
+        let repository_and_status = |path: &str| {
+            git_store
+                .repository_and_status_for_project_path(&(tree_id, rel_path(path)).into(), cx)
+                .map(|(repo, status)| (repo.read(cx).work_directory_abs_path.clone(), status))
+        };
+
+        assert_eq!(
+            repository_and_status("outer/inner/changed.txt"),
+            Some((
+                Path::new(path!("/root/outer/inner")).into(),
+                Some(StatusCode::Modified.worktree())
+            ))
+        );
+        assert_eq!(
+            repository_and_status("outer/new.txt"),
+            Some((
+                Path::new(path!("/root/outer")).into(),
+                Some(FileStatus::Untracked)
+            ))
+        );
+        assert_eq!(
+            repository_and_status("outer/clean.txt"),
+            Some((Path::new(path!("/root/outer")).into(), None))
+        );
+        assert_eq!(repository_and_status("outside.txt"), None);
+    });
+}
+
+#[gpui::test]
+async fn test_can_blame(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            "repo": {
+                ".git": {},
+                ".gitignore": "ignored.txt\n",
+                "committed.txt": "",
+                "modified.txt": "",
+                "added.txt": "",
+                "untracked.txt": "",
+                "ignored.txt": "",
+                "dir": {},
+            },
+            "outside.txt": "",
+        }),
+    )
+    .await;
+    fs.set_status_for_repo(
+        path!("/root/repo/.git").as_ref(),
+        &[
+            ("modified.txt", StatusCode::Modified.worktree()),
+            ("added.txt", StatusCode::Added.index()),
+            ("untracked.txt", FileStatus::Untracked),
+            ("ignored.txt", FileStatus::Ignored),
+        ],
+    );
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    let tree_id = project.read_with(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    project.read_with(cx, |project, cx| {
+        let git_store = project.git_store().read(cx);
+        let can_blame = |path: &str| git_store.can_blame(&(tree_id, rel_path(path)).into(), cx);
+
+        assert!(can_blame("repo/committed.txt"));
+        assert!(can_blame("repo/modified.txt"));
+        assert!(!can_blame("repo/added.txt"));
+        assert!(!can_blame("repo/untracked.txt"));
+        assert!(!can_blame("repo/ignored.txt"));
+        assert!(!can_blame("repo/dir"));
+        assert!(!can_blame("repo/missing.txt"));
+        assert!(!can_blame("outside.txt"));
+    });
+}
+
 #[gpui::test]
 async fn test_home_dir_as_git_repository(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -8377,181 +8500,1207 @@ async fn test_home_dir_as_git_repository(cx: &mut gpui::TestAppContext) {
         home,
         json!({
             ".git": {},
-            "project": {
-                "a.txt": "A"
-            },
+            "project": {
+                "a.txt": "A"
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [home.join("project").as_ref()], cx).await;
+    let tree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    let tree_id = tree.read_with(cx, |tree, _| tree.id());
+
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    tree.flush_fs_events(cx).await;
+
+    project.read_with(cx, |project, cx| {
+        let containing = project
+            .git_store()
+            .read(cx)
+            .repository_and_path_for_project_path(&(tree_id, rel_path("a.txt")).into(), cx);
+        assert!(containing.is_none());
+    });
+
+    let project = Project::test(fs.clone(), [home.as_ref()], cx).await;
+    let tree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    let tree_id = tree.read_with(cx, |tree, _| tree.id());
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    tree.flush_fs_events(cx).await;
+
+    project.read_with(cx, |project, cx| {
+        let containing = project
+            .git_store()
+            .read(cx)
+            .repository_and_path_for_project_path(&(tree_id, rel_path("project/a.txt")).into(), cx);
+        assert_eq!(
+            containing
+                .unwrap()
+                .0
+                .read(cx)
+                .work_directory_abs_path
+                .as_ref(),
+            home,
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_git_repository_status(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let root = TempTree::new(json!({
+        "project": {
+            "a.txt": "a",    // Modified
+            "b.txt": "bb",   // Added
+            "c.txt": "ccc",  // Unchanged
+            "d.txt": "dddd", // Deleted
+        },
+    }));
+
+    // Set up git repository before creating the project.
+    let work_dir = root.path().join("project");
+    let repo = git_init(work_dir.as_path());
+    git_add("a.txt", &repo);
+    git_add("c.txt", &repo);
+    git_add("d.txt", &repo);
+    git_commit("Initial commit", &repo);
+    std::fs::remove_file(work_dir.join("d.txt")).unwrap();
+    std::fs::write(work_dir.join("a.txt"), "aa").unwrap();
+
+    let project = Project::test(
+        Arc::new(RealFs::new(None, cx.executor())),
+        [root.path()],
+        cx,
+    )
+    .await;
+
+    let tree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    tree.flush_fs_events(cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.executor().run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+
+    // Check that the right git state is observed on startup
+    repository.read_with(cx, |repository, _| {
+        let entries = repository.cached_status().collect::<Vec<_>>();
+        assert_eq!(repository.status_len(), entries.len());
+        assert_eq!(
+            entries,
+            [
+                StatusEntry {
+                    repo_path: repo_path("a.txt"),
+                    status: StatusCode::Modified.worktree(),
+                },
+                StatusEntry {
+                    repo_path: repo_path("b.txt"),
+                    status: FileStatus::Untracked,
+                },
+                StatusEntry {
+                    repo_path: repo_path("d.txt"),
+                    status: StatusCode::Deleted.worktree(),
+                },
+            ]
+        );
+        assert_eq!(
+            repository.file_status_counts(),
+            StatusCounts {
+                modified: 1,
+                untracked: 1,
+                deleted: 1,
+                ..Default::default()
+            }
+        );
+    });
+
+    std::fs::write(work_dir.join("c.txt"), "some changes").unwrap();
+
+    tree.flush_fs_events(cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.executor().run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        let entries = repository.cached_status().collect::<Vec<_>>();
+        assert_eq!(repository.status_len(), entries.len());
+        assert_eq!(
+            entries,
+            [
+                StatusEntry {
+                    repo_path: repo_path("a.txt"),
+                    status: StatusCode::Modified.worktree(),
+                },
+                StatusEntry {
+                    repo_path: repo_path("b.txt"),
+                    status: FileStatus::Untracked,
+                },
+                StatusEntry {
+                    repo_path: repo_path("c.txt"),
+                    status: StatusCode::Modified.worktree(),
+                },
+                StatusEntry {
+                    repo_path: repo_path("d.txt"),
+                    status: StatusCode::Deleted.worktree(),
+                },
+            ]
+        );
+    });
+
+    git_add("a.txt", &repo);
+    git_add("c.txt", &repo);
+    git_remove_index(Path::new("d.txt"), &repo);
+    git_commit("Another commit", &repo);
+    tree.flush_fs_events(cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.executor().run_until_parked();
+
+    std::fs::remove_file(work_dir.join("a.txt")).unwrap();
+    std::fs::remove_file(work_dir.join("b.txt")).unwrap();
+    tree.flush_fs_events(cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.executor().run_until_parked();
+
+    repository.read_with(cx, |repository, _cx| {
+        let entries = repository.cached_status().collect::<Vec<_>>();
+        assert_eq!(repository.status_len(), entries.len());
+
+        // Deleting an untracked entry, b.txt, should leave no status
+        // a.txt was tracked, and so should have a status
+        assert_eq!(
+            entries,
+            [StatusEntry {
+                repo_path: repo_path("a.txt"),
+                status: StatusCode::Deleted.worktree(),
+            }]
+        );
+        assert_eq!(
+            repository.file_status_counts(),
+            StatusCounts {
+                deleted: 1,
+                ..Default::default()
+            }
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_git_status_scan_only_recomputes_changed_entries(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "modified",
+            "b.txt": "modified",
+            "c.txt": "modified",
+            "d.txt": "modified",
+            "e.txt": "modified",
+        }),
+    )
+    .await;
+    let committed_contents =
+        ["a.txt", "b.txt", "c.txt", "d.txt", "e.txt"].map(|path| (path, "original".to_string()));
+    fs.set_head_and_index_for_repo(path!("/root/.git").as_ref(), &committed_contents);
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    let recomputed_before_staging = repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.cached_status().count(), 5);
+        repository.status_entries_recomputed_count()
+    });
+
+    let mut index_contents = committed_contents.clone();
+    index_contents[2].1 = "modified".to_string();
+    fs.set_index_for_repo(path!("/root/.git").as_ref(), &index_contents);
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("c.txt"))
+                .map(|entry| entry.status),
+            Some(StatusCode::Modified.index())
+        );
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("a.txt"))
+                .map(|entry| entry.status),
+            Some(StatusCode::Modified.worktree())
+        );
+        assert_eq!(
+            repository.status_entries_recomputed_count() - recomputed_before_staging,
+            1
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_git_status_refresh_debounce(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "a",
+            "b.txt": "b",
+            "c.txt": "c",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings
+                    .git
+                    .get_or_insert_default()
+                    .status_refresh_debounce_ms = Some(500);
+            });
+        });
+    });
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    let scans_before_burst =
+        repository.read_with(cx, |repository, _| repository.status_scan_count());
+
+    for path in ["a.txt", "b.txt", "c.txt"] {
+        fs.set_status_for_repo(
+            path!("/root/.git").as_ref(),
+            &[(path, StatusCode::Modified.worktree())],
+        );
+        cx.run_until_parked();
+    }
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.status_scan_count(), scans_before_burst);
+        assert!(repository.is_status_stale());
+    });
+
+    cx.executor().advance_clock(Duration::from_millis(500));
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.status_scan_count(), scans_before_burst + 1);
+        assert!(!repository.is_status_stale());
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("c.txt"))
+                .map(|entry| entry.status),
+            Some(StatusCode::Modified.worktree())
+        );
+        assert_eq!(repository.status_for_path(&repo_path("a.txt")), None);
+    });
+}
+
+#[gpui::test]
+async fn test_git_status_is_stale_while_refresh_in_flight(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "original",
+        }),
+    )
+    .await;
+    fs.set_head_and_index_for_repo(
+        path!("/root/.git").as_ref(),
+        &[("a.txt", "original".to_string())],
+    );
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert!(!repository.is_status_stale());
+        assert_eq!(repository.cached_status().count(), 0);
+    });
+
+    fs.save(
+        path!("/root/a.txt").as_ref(),
+        &"modified".into(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut saw_stale = false;
+    for _ in 0..100 {
+        if repository.read_with(cx, |repository, _| repository.is_status_stale()) {
+            saw_stale = true;
+            break;
+        }
+        if !cx.executor().tick() {
+            cx.executor().advance_clock(Duration::from_millis(10));
+        }
+    }
+    assert!(saw_stale, "expected a status refresh to be scheduled");
+
+    cx.run_until_parked();
+    repository.read_with(cx, |repository, _| {
+        assert!(!repository.is_status_stale());
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("a.txt"))
+                .map(|entry| entry.status),
+            Some(StatusCode::Modified.worktree())
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_repository_config_value(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "a",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.config_value("core.autocrlf"), None);
+    });
+
+    fs.set_config_for_repo(path!("/root/.git").as_ref(), "core.autocrlf", "true");
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository.config_value("core.autocrlf").as_deref(),
+            Some("true")
+        );
+        assert_eq!(
+            repository.config_value("core.autoCRLF").as_deref(),
+            Some("true")
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_repository_config_only_reloaded_when_changed(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "a",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    let scans_before = repository.read_with(cx, |repository, _| repository.status_scan_count());
+
+    // Changing the config along with other git state doesn't re-read it, since
+    // the config file itself wasn't reported as changed.
+    fs.with_git_state(path!("/root/.git").as_ref(), true, |state| {
+        state
+            .config
+            .insert("core.autocrlf".to_string(), "true".to_string());
+    })
+    .unwrap();
+    cx.run_until_parked();
+    repository.read_with(cx, |repository, _| {
+        assert!(repository.status_scan_count() > scans_before);
+        assert_eq!(repository.config_value("core.autocrlf"), None);
+    });
+
+    fs.set_config_for_repo(path!("/root/.git").as_ref(), "core.autocrlf", "input");
+    cx.run_until_parked();
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository.config_value("core.autocrlf").as_deref(),
+            Some("input")
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_repository_tags_only_reloaded_when_refs_change(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "a",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    let scans_before = repository.read_with(cx, |repository, _| repository.status_scan_count());
+    let tag = TagEntry {
+        name: "v1".into(),
+        target_oid: "0123456789abcdef0123456789abcdef01234567".parse().unwrap(),
+    };
+
+    // Changing the tags along with other git state doesn't re-list them, since
+    // no ref was reported as changed.
+    fs.with_git_state(path!("/root/.git").as_ref(), true, |state| {
+        state.tags = vec![tag.clone()];
+    })
+    .unwrap();
+    cx.run_until_parked();
+    repository.read_with(cx, |repository, _| {
+        assert!(repository.status_scan_count() > scans_before);
+        assert!(repository.tags().is_empty());
+    });
+
+    fs.set_tags_for_repo(path!("/root/.git").as_ref(), vec![tag.clone()]);
+    cx.run_until_parked();
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.tags(), &[tag]);
+    });
+}
+
+#[gpui::test]
+async fn test_bare_repository_has_no_statuses(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {
+                "HEAD": "ref: refs/heads/main\n",
+                "config": "[core]\n\tbare = true\n",
+                "objects": {},
+                "refs": {},
+            },
+            "a.txt": "modified",
+            "untracked.txt": "",
+        }),
+    )
+    .await;
+    fs.set_config_for_repo(path!("/root/.git").as_ref(), "core.bare", "true");
+    fs.set_head_and_index_for_repo(path!("/root/.git").as_ref(), &[("a.txt", "a".to_string())]);
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert!(repository.is_bare());
+        assert_eq!(repository.status_len(), 0);
+    });
+
+    fs.save(
+        path!("/root/a.txt").as_ref(),
+        &"modified again".into(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert!(repository.is_bare());
+        assert_eq!(repository.status_len(), 0);
+    });
+}
+
+#[gpui::test]
+async fn test_prepared_commit_message(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "a",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.prepared_commit_message(), None);
+    });
+
+    fs.save(
+        path!("/root/.git/MERGE_MSG").as_ref(),
+        &"Merge branch 'feature'\n".into(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository.prepared_commit_message().as_deref(),
+            Some("Merge branch 'feature'\n")
+        );
+    });
+
+    fs.remove_file(path!("/root/.git/MERGE_MSG").as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.prepared_commit_message(), None);
+    });
+}
+
+#[gpui::test]
+async fn test_rebase_progress(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "a",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.rebase_progress(), None);
+    });
+
+    fs.insert_tree(
+        path!("/root/.git/rebase-merge"),
+        json!({
+            "msgnum": "2\n",
+            "end": "5\n",
+        }),
+    )
+    .await;
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository.rebase_progress(),
+            Some(RebaseProgress {
+                current: 2,
+                total: 5
+            })
+        );
+    });
+
+    fs.remove_dir(
+        path!("/root/.git/rebase-merge").as_ref(),
+        RemoveOptions {
+            recursive: true,
+            ignore_if_not_exists: false,
+        },
+    )
+    .await
+    .unwrap();
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.rebase_progress(), None);
+    });
+}
+
+#[gpui::test]
+async fn test_repository_tags(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let root = TempTree::new(json!({
+        "project": {
+            "a.txt": "a",
+        },
+    }));
+    let work_dir = root.path().join("project");
+    let repo = git_init(work_dir.as_path());
+    git_add("a.txt", &repo);
+    git_commit("Initial commit", &repo);
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let head_oid = head.id().to_string().parse().unwrap();
+
+    let project = Project::test(
+        Arc::new(RealFs::new(None, cx.executor())),
+        [root.path()],
+        cx,
+    )
+    .await;
+    let tree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    tree.flush_fs_events(cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.executor().run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert!(repository.tags().is_empty());
+    });
+
+    let signature = git2::Signature::now("test", "test@zed.dev").unwrap();
+    repo.tag_lightweight("v1", head.as_object(), false).unwrap();
+    repo.tag("v2", head.as_object(), &signature, "Second release", false)
+        .unwrap();
+    tree.flush_fs_events(cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.executor().run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository.tags(),
+            &[
+                TagEntry {
+                    name: "v1".into(),
+                    target_oid: head_oid,
+                },
+                TagEntry {
+                    name: "v2".into(),
+                    target_oid: head_oid,
+                },
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_repository_for_work_directory(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            "project": {
+                "dir1": {
+                    ".git": {},
+                    "a.txt": "a",
+                },
+                "b.txt": "b",
+            },
+            "outer": {
+                ".git": {},
+                "sub": {
+                    "c.txt": "c",
+                },
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(
+        fs.clone(),
+        [
+            path!("/root/project").as_ref(),
+            path!("/root/outer/sub").as_ref(),
+        ],
+        cx,
+    )
+    .await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let (project_worktree_id, sub_worktree_id) = project.read_with(cx, |project, cx| {
+        let worktree_ids = project
+            .worktrees(cx)
+            .map(|worktree| worktree.read(cx).id())
+            .collect::<Vec<_>>();
+        (worktree_ids[0], worktree_ids[1])
+    });
+
+    project.read_with(cx, |project, cx| {
+        let git_store = project.git_store().read(cx);
+
+        let repository = git_store
+            .repository_for_work_directory(
+                project_worktree_id,
+                &WorkDirectory::InProject {
+                    relative_path: rel_path("dir1").into(),
+                },
+                cx,
+            )
+            .unwrap();
+        assert_eq!(
+            repository.read(cx).work_directory_abs_path,
+            Path::new(path!("/root/project/dir1")).into()
+        );
+
+        let repository = git_store
+            .repository_for_work_directory(
+                sub_worktree_id,
+                &WorkDirectory::AboveProject {
+                    absolute_path: Path::new(path!("/root/outer")).into(),
+                    location_in_repo: Path::new("sub").into(),
+                },
+                cx,
+            )
+            .unwrap();
+        assert_eq!(
+            repository.read(cx).work_directory_abs_path,
+            Path::new(path!("/root/outer")).into()
+        );
+
+        assert!(
+            git_store
+                .repository_for_work_directory(
+                    project_worktree_id,
+                    &WorkDirectory::InProject {
+                        relative_path: rel_path("b.txt").into(),
+                    },
+                    cx,
+                )
+                .is_none()
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_repositories_sorted_by_path(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            "a": {
+                ".git": {},
+                "nested": {
+                    ".git": {},
+                },
+            },
+            "b": {
+                ".git": {},
+            },
+            "c": {
+                ".git": {},
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(
+        fs.clone(),
+        [
+            path!("/root/c").as_ref(),
+            path!("/root/a").as_ref(),
+            path!("/root/b").as_ref(),
+        ],
+        cx,
+    )
+    .await;
+    cx.executor().run_until_parked();
+
+    let work_directories = project.read_with(cx, |project, cx| {
+        project
+            .repositories_sorted_by_path(cx)
+            .into_iter()
+            .map(|repo| repo.read(cx).work_directory_abs_path.clone())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(
+        work_directories,
+        [
+            Path::new(path!("/root/a")).into(),
+            Path::new(path!("/root/a/nested")).into(),
+            Path::new(path!("/root/b")).into(),
+            Path::new(path!("/root/c")).into(),
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_git_core_worktree_config(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let root = TempTree::new(json!({
+        "gitdir": {},
+        "work": {
+            "a.txt": "a",
+        },
+    }));
+
+    let repo = git_init(&root.path().join("gitdir"));
+    repo.config()
+        .unwrap()
+        .set_str("core.worktree", "../../work")
+        .unwrap();
+    let repo = git2::Repository::open(root.path().join("gitdir")).unwrap();
+    git_add("a.txt", &repo);
+    git_commit("Initial commit", &repo);
+    std::fs::write(root.path().join("work/a.txt"), "aa").unwrap();
+    std::fs::write(root.path().join("work/b.txt"), "b").unwrap();
+
+    let project = Project::test(
+        Arc::new(RealFs::new(None, cx.executor())),
+        [root.path()],
+        cx,
+    )
+    .await;
+
+    let tree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    tree.flush_fs_events(cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.executor().run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository.work_directory_abs_path.as_ref(),
+            root.path().join("work")
+        );
+        assert_eq!(
+            repository.cached_status().collect::<Vec<_>>(),
+            [
+                StatusEntry {
+                    repo_path: repo_path("a.txt"),
+                    status: StatusCode::Modified.worktree(),
+                },
+                StatusEntry {
+                    repo_path: repo_path("b.txt"),
+                    status: FileStatus::Untracked,
+                },
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_rename_entry_updates_git_statuses(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "tracked.txt": "tracked",
+            "untracked.txt": "untracked",
+        }),
+    )
+    .await;
+    fs.set_head_and_index_for_repo(
+        path!("/root/.git").as_ref(),
+        &[("tracked.txt", "tracked".to_string())],
+    );
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.status_for_path(&repo_path("tracked.txt")), None);
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("untracked.txt"))
+                .unwrap()
+                .status,
+            FileStatus::Untracked
+        );
+    });
+
+    let (worktree_id, tracked_id, untracked_id) = project.read_with(cx, |project, cx| {
+        let worktree = project.worktrees(cx).next().unwrap().read(cx);
+        (
+            worktree.id(),
+            worktree.entry_for_path(rel_path("tracked.txt")).unwrap().id,
+            worktree
+                .entry_for_path(rel_path("untracked.txt"))
+                .unwrap()
+                .id,
+        )
+    });
+    for (entry_id, new_path) in [(tracked_id, "renamed.txt"), (untracked_id, "moved.txt")] {
+        project
+            .update(cx, |project, cx| {
+                project.rename_entry(entry_id, (worktree_id, rel_path(new_path)).into(), cx)
+            })
+            .await
+            .unwrap();
+    }
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("tracked.txt"))
+                .unwrap()
+                .status,
+            StatusCode::Deleted.worktree()
+        );
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("renamed.txt"))
+                .unwrap()
+                .status,
+            FileStatus::Untracked
+        );
+        assert_eq!(
+            repository.status_for_path(&repo_path("untracked.txt")),
+            None
+        );
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("moved.txt"))
+                .unwrap()
+                .status,
+            FileStatus::Untracked
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_refresh_git_statuses_without_fs_events(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "a",
         }),
     )
     .await;
+    fs.set_head_and_index_for_repo(path!("/root/.git").as_ref(), &[("a.txt", "a".to_string())]);
 
-    let project = Project::test(fs.clone(), [home.join("project").as_ref()], cx).await;
-    let tree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
-    let tree_id = tree.read_with(cx, |tree, _| tree.id());
-
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
     project
         .update(cx, |project, cx| project.git_scans_complete(cx))
         .await;
-    tree.flush_fs_events(cx).await;
+    cx.run_until_parked();
 
-    project.read_with(cx, |project, cx| {
-        let containing = project
-            .git_store()
-            .read(cx)
-            .repository_and_path_for_project_path(&(tree_id, rel_path("a.txt")).into(), cx);
-        assert!(containing.is_none());
+    let worktree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    let repository = project.read_with(cx, |project, cx| {
+        project.repositories(cx).values().next().unwrap().clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.status_for_path(&repo_path("a.txt")), None);
     });
 
-    let project = Project::test(fs.clone(), [home.as_ref()], cx).await;
-    let tree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
-    let tree_id = tree.read_with(cx, |tree, _| tree.id());
-    project
-        .update(cx, |project, cx| project.git_scans_complete(cx))
-        .await;
-    tree.flush_fs_events(cx).await;
+    fs.with_git_state(path!("/root/.git").as_ref(), false, |state| {
+        state
+            .head_contents
+            .insert(repo_path("a.txt"), "original".to_string());
+    })
+    .unwrap();
+    cx.run_until_parked();
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(repository.status_for_path(&repo_path("a.txt")), None);
+    });
 
-    project.read_with(cx, |project, cx| {
-        let containing = project
-            .git_store()
-            .read(cx)
-            .repository_and_path_for_project_path(&(tree_id, rel_path("project/a.txt")).into(), cx);
+    worktree
+        .update(cx, |worktree, cx| {
+            worktree.as_local().unwrap().refresh_git_statuses(cx)
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+    repository.read_with(cx, |repository, _| {
         assert_eq!(
-            containing
-                .unwrap()
-                .0
-                .read(cx)
-                .work_directory_abs_path
-                .as_ref(),
-            home,
+            repository
+                .status_for_path(&repo_path("a.txt"))
+                .map(|entry| entry.status),
+            Some(StatusCode::Modified.index())
         );
     });
 }
 
 #[gpui::test]
-async fn test_git_repository_status(cx: &mut gpui::TestAppContext) {
+async fn test_recompute_upstream_tracking(cx: &mut gpui::TestAppContext) {
     init_test(cx);
-    cx.executor().allow_parking();
-
-    let root = TempTree::new(json!({
-        "project": {
-            "a.txt": "a",    // Modified
-            "b.txt": "bb",   // Added
-            "c.txt": "ccc",  // Unchanged
-            "d.txt": "dddd", // Deleted
-        },
-    }));
-
-    // Set up git repository before creating the project.
-    let work_dir = root.path().join("project");
-    let repo = git_init(work_dir.as_path());
-    git_add("a.txt", &repo);
-    git_add("c.txt", &repo);
-    git_add("d.txt", &repo);
-    git_commit("Initial commit", &repo);
-    std::fs::remove_file(work_dir.join("d.txt")).unwrap();
-    std::fs::write(work_dir.join("a.txt"), "aa").unwrap();
 
-    let project = Project::test(
-        Arc::new(RealFs::new(None, cx.executor())),
-        [root.path()],
-        cx,
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "a",
+        }),
     )
     .await;
+    fs.insert_branches(path!("/root/.git").as_ref(), &["main"]);
+    fs.set_upstream_tracking_for_repo(
+        path!("/root/.git").as_ref(),
+        "main",
+        UpstreamTrackingStatus {
+            ahead: 0,
+            behind: 2,
+        },
+    );
 
-    let tree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
-    tree.flush_fs_events(cx).await;
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
     project
         .update(cx, |project, cx| project.git_scans_complete(cx))
         .await;
-    cx.executor().run_until_parked();
+    cx.run_until_parked();
 
     let repository = project.read_with(cx, |project, cx| {
         project.repositories(cx).values().next().unwrap().clone()
     });
-
-    // Check that the right git state is observed on startup
     repository.read_with(cx, |repository, _| {
-        let entries = repository.cached_status().collect::<Vec<_>>();
         assert_eq!(
-            entries,
-            [
-                StatusEntry {
-                    repo_path: repo_path("a.txt"),
-                    status: StatusCode::Modified.worktree(),
-                },
-                StatusEntry {
-                    repo_path: repo_path("b.txt"),
-                    status: FileStatus::Untracked,
-                },
-                StatusEntry {
-                    repo_path: repo_path("d.txt"),
-                    status: StatusCode::Deleted.worktree(),
-                },
-            ]
+            repository
+                .branch
+                .as_ref()
+                .and_then(|branch| branch.tracking_status()),
+            Some(UpstreamTrackingStatus {
+                ahead: 0,
+                behind: 2,
+            })
         );
     });
 
-    std::fs::write(work_dir.join("c.txt"), "some changes").unwrap();
-
-    tree.flush_fs_events(cx).await;
-    project
-        .update(cx, |project, cx| project.git_scans_complete(cx))
-        .await;
-    cx.executor().run_until_parked();
-
+    // Simulate a local commit that no file system event reports.
+    fs.with_git_state(path!("/root/.git").as_ref(), false, |state| {
+        state.upstream_tracking.insert(
+            "main".to_string(),
+            UpstreamTrackingStatus {
+                ahead: 1,
+                behind: 2,
+            },
+        );
+    })
+    .unwrap();
+    cx.run_until_parked();
     repository.read_with(cx, |repository, _| {
-        let entries = repository.cached_status().collect::<Vec<_>>();
         assert_eq!(
-            entries,
-            [
-                StatusEntry {
-                    repo_path: repo_path("a.txt"),
-                    status: StatusCode::Modified.worktree(),
-                },
-                StatusEntry {
-                    repo_path: repo_path("b.txt"),
-                    status: FileStatus::Untracked,
-                },
-                StatusEntry {
-                    repo_path: repo_path("c.txt"),
-                    status: StatusCode::Modified.worktree(),
-                },
-                StatusEntry {
-                    repo_path: repo_path("d.txt"),
-                    status: StatusCode::Deleted.worktree(),
-                },
-            ]
+            repository
+                .branch
+                .as_ref()
+                .and_then(|branch| branch.tracking_status()),
+            Some(UpstreamTrackingStatus {
+                ahead: 0,
+                behind: 2,
+            })
         );
     });
 
-    git_add("a.txt", &repo);
-    git_add("c.txt", &repo);
-    git_remove_index(Path::new("d.txt"), &repo);
-    git_commit("Another commit", &repo);
-    tree.flush_fs_events(cx).await;
-    project
-        .update(cx, |project, cx| project.git_scans_complete(cx))
-        .await;
-    cx.executor().run_until_parked();
-
-    std::fs::remove_file(work_dir.join("a.txt")).unwrap();
-    std::fs::remove_file(work_dir.join("b.txt")).unwrap();
-    tree.flush_fs_events(cx).await;
-    project
-        .update(cx, |project, cx| project.git_scans_complete(cx))
-        .await;
-    cx.executor().run_until_parked();
-
-    repository.read_with(cx, |repository, _cx| {
-        let entries = repository.cached_status().collect::<Vec<_>>();
+    repository
+        .update(cx, |repository, cx| repository.recompute_tracking(cx))
+        .await
+        .unwrap();
 
-        // Deleting an untracked entry, b.txt, should leave no status
-        // a.txt was tracked, and so should have a status
+    repository.read_with(cx, |repository, _| {
         assert_eq!(
-            entries,
-            [StatusEntry {
-                repo_path: repo_path("a.txt"),
-                status: StatusCode::Deleted.worktree(),
-            }]
+            repository
+                .branch
+                .as_ref()
+                .and_then(|branch| branch.tracking_status()),
+            Some(UpstreamTrackingStatus {
+                ahead: 1,
+                behind: 2,
+            })
         );
     });
 }
@@ -8616,7 +9765,11 @@ async fn test_git_status_postprocessing(cx: &mut gpui::TestAppContext) {
                 }
                 .into(),
             }]
-        )
+        );
+        assert_eq!(
+            repository.porcelain_status(&repo_path("a.txt")),
+            Some(*b"DA")
+        );
     });
 }
 
@@ -10251,6 +11404,66 @@ async fn test_git_worktrees_and_submodules(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_git_file_pointing_to_external_git_dir(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/git-dirs"),
+        json!({
+            "project": {},
+        }),
+    )
+    .await;
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+            ".git": format!("gitdir: {}\n", path!("/git-dirs/project")),
+            "a.txt": "A",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/project").as_ref()], cx).await;
+    project
+        .update(cx, |project, cx| project.git_scans_complete(cx))
+        .await;
+    cx.run_until_parked();
+
+    let repository = project.read_with(cx, |project, cx| {
+        let repositories = project
+            .repositories(cx)
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(repositories.len(), 1);
+        repositories[0].clone()
+    });
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository.work_directory_abs_path,
+            Path::new(path!("/project")).into()
+        );
+        assert_eq!(repository.status_for_path(&repo_path("a.txt")), None);
+    });
+
+    fs.set_status_for_repo(
+        path!("/project/.git").as_ref(),
+        &[("a.txt", StatusCode::Modified.worktree())],
+    );
+    cx.run_until_parked();
+
+    repository.read_with(cx, |repository, _| {
+        assert_eq!(
+            repository
+                .status_for_path(&repo_path("a.txt"))
+                .map(|entry| entry.status),
+            Some(StatusCode::Modified.worktree())
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_repository_deduplication(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -10940,6 +12153,47 @@ async fn test_git_worktree_remove(cx: &mut gpui::TestAppContext) {
     assert!(active_repo_path.is_none());
 }
 
+#[gpui::test]
+async fn test_is_path_tracked(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "committed.txt": "committed",
+            "staged.txt": "staged",
+            "untracked.txt": "untracked",
+        }),
+    )
+    .await;
+    fs.set_head_for_repo(
+        Path::new(path!("/root/.git")),
+        &[("committed.txt", "committed".into())],
+        "deadbeef",
+    );
+    fs.set_index_for_repo(
+        Path::new(path!("/root/.git")),
+        &[
+            ("committed.txt", "committed".into()),
+            ("staged.txt", "staged".into()),
+        ],
+    );
+
+    let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+    cx.run_until_parked();
+    let repo = project.read_with(cx, |project, cx| project.active_repository(cx).unwrap());
+
+    for (path, expected) in [
+        ("committed.txt", true),
+        ("staged.txt", true),
+        ("untracked.txt", false),
+    ] {
+        let tracked = repo.update(cx, |repo, _| repo.is_path_tracked(repo_path(path)));
+        assert_eq!(tracked.await.unwrap().unwrap(), expected, "{path}");
+    }
+}
+
 #[gpui::test]
 async fn test_optimistic_hunks_in_staged_files(cx: &mut gpui::TestAppContext) {
     use DiffHunkSecondaryStatus::*;
@@ -650,7 +650,7 @@ impl WorktreeStore {
         self.send_project_updates(cx);
 
         let handle_id = worktree.entity_id();
-        cx.subscribe(worktree, |_, worktree, event, cx| {
+        cx.subscribe(worktree, |this, worktree, event, cx| {
             let worktree_id = worktree.read(cx).id();
             match event {
                 worktree::Event::UpdatedEntries(changes) => {
@@ -668,6 +668,8 @@ impl WorktreeStore {
                 worktree::Event::DeletedEntry(id) => {
                     cx.emit(WorktreeStoreEvent::WorktreeDeletedEntry(worktree_id, *id))
                 }
+                worktree::Event::RootChanged { .. } => this.send_project_updates(cx),
+                worktree::Event::GitConfigChanged { .. } => {}
             }
         })
         .detach();
@@ -34,6 +34,12 @@ fn load_linux_repo_snapshot() -> Vec<GitEntry> {
                 is_hidden: false,
                 char_bag: Default::default(),
                 is_fifo: false,
+                is_broken_symlink: false,
+                symlink_target: None,
+                is_git_internal: false,
+                is_binary: None,
+                is_lfs_pointer: None,
+                content_hash: None,
             };
             Some(GitEntry {
                 entry,
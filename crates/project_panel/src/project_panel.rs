@@ -34,7 +34,7 @@ use project::{
     Entry, EntryKind, Fs, GitEntry, GitEntryRef, GitTraversal, Project, ProjectEntryId,
     ProjectPath, Worktree, WorktreeId,
     git_store::{GitStoreEvent, RepositoryEvent, git_traversal::ChildEntriesGitIter},
-    project_settings::GoToDiagnosticSeverityFilter,
+    project_settings::{GoToDiagnosticSeverityFilter, ProjectSettings},
 };
 use project_panel_settings::ProjectPanelSettings;
 use rayon::slice::ParallelSliceMut;
@@ -2071,11 +2071,13 @@ impl ProjectPanel {
     }
 
     fn trash(&mut self, action: &Trash, window: &mut Window, cx: &mut Context<Self>) {
-        self.remove(true, action.skip_prompt, window, cx);
+        self.remove(Some(true), action.skip_prompt, window, cx);
     }
 
     fn delete(&mut self, action: &Delete, window: &mut Window, cx: &mut Context<Self>) {
-        self.remove(false, action.skip_prompt, window, cx);
+        // Remote hosts may not support trashing, so deletions there are always permanent.
+        let trash = self.project.read(cx).is_remote().then_some(false);
+        self.remove(trash, action.skip_prompt, window, cx);
     }
 
     fn restore_file(
@@ -2174,7 +2176,7 @@ impl ProjectPanel {
 
     fn remove(
         &mut self,
-        trash: bool,
+        trash: Option<bool>,
         skip_prompt: bool,
         window: &mut Window,
         cx: &mut Context<ProjectPanel>,
@@ -2203,7 +2205,11 @@ impl ProjectPanel {
                 return None;
             }
             let answer = if !skip_prompt {
-                let operation = if trash { "Trash" } else { "Delete" };
+                let operation = if trash == Some(true) {
+                    "Trash"
+                } else {
+                    "Delete"
+                };
                 let prompt = match file_paths.first() {
                     Some((_, path)) if file_paths.len() == 1 => {
                         let unsaved_warning = if dirty_buffers > 0 {
@@ -3503,6 +3509,9 @@ impl ProjectPanel {
         let auto_collapse_dirs = settings.auto_fold_dirs;
         let hide_gitignore = settings.hide_gitignore;
         let sort_mode = settings.sort_mode;
+        let summarize_submodules = ProjectSettings::get_global(cx)
+            .git
+            .summarize_submodule_status;
         let project = self.project.read(cx);
         let repo_snapshots = project.git_store().read(cx).repo_snapshots(cx);
 
@@ -3557,7 +3566,8 @@ impl ProjectPanel {
 
                         let mut visible_worktree_entries = Vec::new();
                         let mut entry_iter =
-                            GitTraversal::new(&repo_snapshots, worktree_snapshot.entries(true, 0));
+                            GitTraversal::new(&repo_snapshots, worktree_snapshot.entries(true, 0))
+                                .with_submodule_summaries(summarize_submodules);
                         let mut auto_folded_ancestors = vec![];
                         let worktree_abs_path = worktree_snapshot.abs_path();
                         while let Some(entry) = entry_iter.entry() {
@@ -927,6 +927,20 @@ impl VsCodeSettings {
                         .collect::<Vec<_>>()
                 })
                 .filter(|r| !r.is_empty()),
+            treat_git_dir_root_as_repo_internals: None,
+            scan_dot_git: None,
+            sniff_binary_files_during_scan: None,
+            auto_expand_single_root_repo: None,
+            hash_file_contents_during_scan: None,
+            hash_file_contents_max_bytes: None,
+            detect_lfs_pointers_during_scan: None,
+            cache_loaded_files: None,
+            max_loadable_file_bytes: None,
+            exclude_files_larger_than: None,
+            scan_concurrency: None,
+            on_scan_permission_error: None,
+            delete_to_trash: None,
+            git_repository_events_window_ms: None,
         }
     }
 }
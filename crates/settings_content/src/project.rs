@@ -132,6 +132,121 @@ pub struct WorktreeSettingsContent {
     /// external dependencies that should not be modified directly.
     /// Default: []
     pub read_only_files: Option<Vec<String>>,
+
+    /// When a worktree's root is itself a `.git` directory, mark its entries as git
+    /// internals so they can be presented differently from regular project files.
+    /// The entries are still scanned.
+    ///
+    /// Default: false
+    pub treat_git_dir_root_as_repo_internals: Option<bool>,
+
+    /// Whether to scan the contents of `.git` directories. When disabled, their contents
+    /// are excluded even if `file_scan_exclusions` doesn't list them, unless
+    /// `file_scan_inclusions` includes them. When enabled, they're scanned unless
+    /// `file_scan_exclusions` excludes them.
+    ///
+    /// Default: false
+    pub scan_dot_git: Option<bool>,
+
+    /// Whether to read the beginning of every file during worktree scans to detect
+    /// binary content. When disabled, files are only sniffed on demand, and files with
+    /// well-known binary extensions are still flagged.
+    ///
+    /// Default: false
+    pub sniff_binary_files_during_scan: Option<bool>,
+
+    /// Whether to eagerly load every top-level directory of a worktree that contains exactly
+    /// one git repository, rooted at the worktree root, including ignored ones, so they appear
+    /// expanded right away.
+    ///
+    /// Default: false
+    pub auto_expand_single_root_repo: Option<bool>,
+
+    /// Whether to hash the contents of every file during worktree scans. This catches
+    /// rewrites that keep the same size and modification time, at the cost of reading
+    /// each file.
+    ///
+    /// Default: false
+    pub hash_file_contents_during_scan: Option<bool>,
+
+    /// Files larger than this many bytes aren't hashed during worktree scans, even
+    /// when `hash_file_contents_during_scan` is enabled, so that huge artifacts don't
+    /// have to be read in full.
+    ///
+    /// Default: 1048576
+    pub hash_file_contents_max_bytes: Option<u64>,
+
+    /// Whether to read small files during worktree scans to detect Git LFS pointers
+    /// standing in for content that hasn't been downloaded.
+    ///
+    /// Default: false
+    pub detect_lfs_pointers_during_scan: Option<bool>,
+
+    /// Whether to keep the contents of recently opened files in memory, so that
+    /// reopening them skips reading them from disk. Cached contents are dropped
+    /// once the worktree observes a change to the file.
+    ///
+    /// Default: false
+    pub cache_loaded_files: Option<bool>,
+
+    /// The maximum size, in bytes, of files that can be opened from a worktree.
+    /// Larger files fail to load instead of being read into memory.
+    ///
+    /// Default: null
+    pub max_loadable_file_bytes: Option<u64>,
+
+    /// Files larger than this many bytes are still listed in the worktree, but are marked
+    /// as ignored so that huge generated files aren't offered for opening.
+    ///
+    /// Default: null
+    pub exclude_files_larger_than: Option<u64>,
+
+    /// The maximum number of directories the worktree scanner reads concurrently.
+    /// Lower values suit network mounts; `null` uses one per CPU.
+    ///
+    /// Default: null
+    pub scan_concurrency: Option<usize>,
+
+    /// What the worktree scanner does when it is denied permission to read a directory.
+    ///
+    /// Default: skip
+    pub on_scan_permission_error: Option<ScanPermissionErrorSetting>,
+
+    /// Whether deleting files and directories from a worktree moves them to the system
+    /// trash rather than removing them permanently, when the caller doesn't choose.
+    ///
+    /// Default: true
+    pub delete_to_trash: Option<bool>,
+
+    /// How long, in milliseconds, to collect changes to a worktree's git repositories before
+    /// reporting them, so that a burst of writes under `.git` (e.g. during `git gc`) is
+    /// reported once. With 0, changes are reported as soon as they're scanned.
+    ///
+    /// Default: 0
+    pub git_repository_events_window_ms: Option<u64>,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    MergeFrom,
+    strum::VariantArray,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanPermissionErrorSetting {
+    /// Leave the directory's contents unloaded.
+    #[default]
+    Skip,
+    /// Read the directory again a few times before giving up, in case it was locked temporarily.
+    Retry,
 }
 
 #[with_fallible_options]
@@ -370,6 +485,16 @@ pub struct GitSettings {
     ///
     /// Default: file_name_first
     pub path_style: Option<GitPathStyle>,
+    /// Whether a submodule with uncommitted changes marks the directories containing it as
+    /// modified.
+    ///
+    /// Default: false
+    pub summarize_submodule_status: Option<bool>,
+    /// How long, in milliseconds, git status refreshes wait for further repository
+    /// changes before running, so that a burst of git operations triggers one refresh.
+    ///
+    /// Default: 0
+    pub status_refresh_debounce_ms: Option<u64>,
 }
 
 #[with_fallible_options]
@@ -24,6 +24,13 @@ impl<K> Default for MapKey<K> {
     }
 }
 
+/// Summarizes a subtree of a [TreeMap] by its greatest key and its number of entries.
+#[derive(Clone, Debug)]
+pub struct MapSummary<K> {
+    max_key: MapKey<K>,
+    count: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct MapKeyRef<'a, K>(Option<&'a K>);
 
@@ -53,6 +60,10 @@ impl<K: Clone + Ord, V: Clone> TreeMap<K, V> {
         self.0.is_empty()
     }
 
+    pub fn len(&self) -> usize {
+        self.0.summary().count
+    }
+
     pub fn get(&self, key: &K) -> Option<&V> {
         let (.., item) = self
             .0
@@ -213,7 +224,7 @@ where
 #[derive(Debug)]
 struct MapSeekTargetAdaptor<'a, T>(&'a T);
 
-impl<'a, K: Clone + Ord, T: MapSeekTarget<K>> SeekTarget<'a, MapKey<K>, MapKeyRef<'a, K>>
+impl<'a, K: Clone + Ord, T: MapSeekTarget<K>> SeekTarget<'a, MapSummary<K>, MapKeyRef<'a, K>>
     for MapSeekTargetAdaptor<'_, T>
 {
     fn cmp(&self, cursor_location: &MapKeyRef<K>, _: ()) -> Ordering {
@@ -250,10 +261,13 @@ where
     K: Clone + Ord,
     V: Clone,
 {
-    type Summary = MapKey<K>;
+    type Summary = MapSummary<K>;
 
     fn summary(&self, _cx: ()) -> Self::Summary {
-        self.key()
+        MapSummary {
+            max_key: self.key(),
+            count: 1,
+        }
     }
 }
 
@@ -269,20 +283,37 @@ where
     }
 }
 
-impl<K> ContextLessSummary for MapKey<K>
+impl<K> ContextLessSummary for MapSummary<K>
 where
     K: Clone,
 {
     fn zero() -> Self {
-        Default::default()
+        MapSummary {
+            max_key: MapKey::default(),
+            count: 0,
+        }
     }
 
     fn add_summary(&mut self, summary: &Self) {
-        *self = summary.clone()
+        self.max_key = summary.max_key.clone();
+        self.count += summary.count;
+    }
+}
+
+impl<'a, K> Dimension<'a, MapSummary<K>> for MapKey<K>
+where
+    K: Clone + Ord,
+{
+    fn zero(_cx: ()) -> Self {
+        Default::default()
+    }
+
+    fn add_summary(&mut self, summary: &'a MapSummary<K>, _: ()) {
+        *self = summary.max_key.clone();
     }
 }
 
-impl<'a, K> Dimension<'a, MapKey<K>> for MapKeyRef<'a, K>
+impl<'a, K> Dimension<'a, MapSummary<K>> for MapKeyRef<'a, K>
 where
     K: Clone + Ord,
 {
@@ -290,12 +321,12 @@ where
         Default::default()
     }
 
-    fn add_summary(&mut self, summary: &'a MapKey<K>, _: ()) {
-        self.0 = summary.0.as_ref();
+    fn add_summary(&mut self, summary: &'a MapSummary<K>, _: ()) {
+        self.0 = summary.max_key.0.as_ref();
     }
 }
 
-impl<'a, K> SeekTarget<'a, MapKey<K>, MapKeyRef<'a, K>> for MapKeyRef<'_, K>
+impl<'a, K> SeekTarget<'a, MapSummary<K>, MapKeyRef<'a, K>> for MapKeyRef<'_, K>
 where
     K: Clone + Ord,
 {
@@ -377,6 +408,11 @@ mod tests {
             map.iter().collect::<Vec<_>>(),
             vec![(&1, &"a"), (&2, &"b"), (&3, &"c")]
         );
+        assert_eq!(map.len(), 3);
+
+        map.insert(2, "b2");
+        assert_eq!(map.len(), 3);
+        map.insert(2, "b");
 
         assert_eq!(map.closest(&0), None);
         assert_eq!(map.closest(&1), Some((&1, &"a")));
@@ -385,6 +421,7 @@ mod tests {
         map.remove(&2);
         assert_eq!(map.get(&2), None);
         assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&1, &"a"), (&3, &"c")]);
+        assert_eq!(map.len(), 2);
 
         assert_eq!(map.closest(&2), Some((&1, &"a")));
 
@@ -401,6 +438,7 @@ mod tests {
         map.insert(6, "f");
         map.retain(|key, _| *key % 2 == 0);
         assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&4, &"d"), (&6, &"f")]);
+        assert_eq!(map.len(), 2);
     }
 
     #[test]
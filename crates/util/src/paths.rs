@@ -322,6 +322,37 @@ impl AsRef<Path> for SanitizedPath {
     }
 }
 
+/// On Windows, prefixes absolute paths that exceed `MAX_PATH` with `\\?\` (or `\\?\UNC\`
+/// for UNC paths), so that filesystem operations on them don't fail. Other paths are returned
+/// unchanged. This is the inverse of the sanitization that [`SanitizedPath`] performs.
+pub fn extended_length_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(not(target_os = "windows"))]
+    return Cow::Borrowed(path);
+
+    #[cfg(target_os = "windows")]
+    {
+        const MAX_PATH: usize = 260;
+
+        let Some(path_str) = path.to_str() else {
+            return Cow::Borrowed(path);
+        };
+        if path_str.len() < MAX_PATH
+            || !path.is_absolute()
+            || path_str.starts_with(r"\\?\")
+            || path_str.starts_with(r"\\.\")
+        {
+            return Cow::Borrowed(path);
+        }
+        // Verbatim paths are passed to the filesystem as-is, so they must only use backslashes.
+        let path_str = path_str.replace('/', "\\");
+        let extended = match path_str.strip_prefix(r"\\") {
+            Some(unc_path) => format!(r"\\?\UNC\{unc_path}"),
+            None => format!(r"\\?\{path_str}"),
+        };
+        Cow::Owned(PathBuf::from(extended))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PathStyle {
     Posix,
@@ -17,6 +17,10 @@ pub enum IgnoreStackEntry {
         ignore: Arc<Gitignore>,
         parent: Arc<IgnoreStackEntry>,
     },
+    Additional {
+        ignore: Arc<Gitignore>,
+        parent: Arc<IgnoreStackEntry>,
+    },
     Some {
         abs_base_path: Arc<Path>,
         ignore: Arc<Gitignore>,
@@ -29,6 +33,7 @@ pub enum IgnoreStackEntry {
 pub enum IgnoreKind {
     Gitignore(Arc<Path>),
     RepoExclude,
+    Additional,
 }
 
 impl IgnoreStack {
@@ -66,6 +71,10 @@ impl IgnoreStack {
                     ignore,
                     parent: self.top.clone(),
                 },
+                IgnoreKind::Additional => IgnoreStackEntry::Additional {
+                    ignore,
+                    parent: self.top.clone(),
+                },
             }),
         };
         Self {
@@ -100,7 +109,8 @@ impl IgnoreStack {
                     ignore::Match::Whitelist(_) => false,
                 }
             }
-            IgnoreStackEntry::RepoExclude { ignore, parent } => {
+            IgnoreStackEntry::RepoExclude { ignore, parent }
+            | IgnoreStackEntry::Additional { ignore, parent } => {
                 match ignore.matched(abs_path, is_dir) {
                     ignore::Match::None => IgnoreStack {
                         repo_root: self.repo_root.clone(),
@@ -7,7 +7,7 @@ use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
 use anyhow::{Context as _, Result, anyhow};
 use chardetng::EncodingDetector;
 use clock::ReplicaId;
-use collections::{HashMap, HashSet, VecDeque};
+use collections::{FxHasher, HashMap, HashSet, VecDeque};
 use encoding_rs::Encoding;
 use fs::{Fs, MTime, PathEvent, RemoveOptions, Watcher, copy_recursive, read_dir_items};
 use futures::{
@@ -21,8 +21,8 @@ use futures::{
 };
 use fuzzy::CharBag;
 use git::{
-    COMMIT_MESSAGE, DOT_GIT, FSMONITOR_DAEMON, GITIGNORE, INDEX_LOCK, LFS_DIR, REPO_EXCLUDE,
-    status::GitSummary,
+    COMMIT_MESSAGE, DOT_GIT, FSMONITOR_DAEMON, GITIGNORE, HEAD_LOG, INDEX_LOCK, LFS_DIR,
+    PACKED_REFS, REFS_DIR, REPO_CONFIG, REPO_EXCLUDE, repository::RepoPath, status::GitSummary,
 };
 use gpui::{
     App, AppContext as _, AsyncApp, BackgroundExecutor, Context, Entity, EventEmitter, Priority,
@@ -32,29 +32,34 @@ use ignore::IgnoreStack;
 use language::DiskState;
 
 use parking_lot::Mutex;
-use paths::{local_settings_folder_name, local_vscode_folder_name};
+use paths::{
+    local_settings_folder_name, local_vscode_folder_name, local_worktree_file_relative_path,
+};
 use postage::{
     barrier,
     prelude::{Sink as _, Stream as _},
     watch,
 };
+use rand::{Rng, seq::IteratorRandom as _};
 use rpc::{
     AnyProtoClient,
     proto::{self, split_worktree_update},
 };
 pub use settings::WorktreeId;
-use settings::{Settings, SettingsLocation, SettingsStore};
+use settings::{ScanPermissionErrorSetting, Settings, SettingsLocation, SettingsStore};
 use smallvec::{SmallVec, smallvec};
 use smol::channel::{self, Sender};
 use std::{
     any::Any,
     borrow::Borrow as _,
-    cmp::Ordering,
-    collections::hash_map,
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, hash_map},
     convert::TryFrom,
     ffi::OsStr,
     fmt,
     future::Future,
+    hash::Hasher as _,
+    io,
     mem::{self},
     ops::{Deref, DerefMut, Range},
     path::{Path, PathBuf},
@@ -63,21 +68,27 @@ use std::{
         Arc,
         atomic::{AtomicUsize, Ordering::SeqCst},
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 use sum_tree::{Bias, Dimensions, Edit, KeyedItem, SeekTarget, SumTree, Summary, TreeMap, TreeSet};
 use text::{LineEnding, Rope};
 use util::{
     ResultExt, debug_panic, maybe,
-    paths::{PathMatcher, PathStyle, SanitizedPath, home_dir},
+    paths::{
+        PathMatcher, PathStyle, SanitizedPath, compare_rel_paths, home_dir, normalize_lexically,
+    },
     rel_path::RelPath,
 };
-pub use worktree_settings::WorktreeSettings;
+pub use worktree_settings::{WorktreeScanOverrides, WorktreeSettings};
 
 use crate::ignore::IgnoreKind;
 
 pub const FS_WATCH_LATENCY: Duration = Duration::from_millis(100);
 
+/// How many more times a directory is read after a permission error, when retrying is enabled.
+const SCAN_PERMISSION_ERROR_RETRIES: usize = 3;
+const SCAN_PERMISSION_ERROR_RETRY_DELAY: Duration = Duration::from_millis(100);
+
 /// A set of local or remote files that are being opened as part of a project.
 /// Responsible for tracking related FS (for local)/collab (for remote) events and corresponding updates.
 /// Stores git repositories data and the diagnostics for the file(s).
@@ -103,6 +114,33 @@ pub enum CreatedEntry {
     Excluded { abs_path: PathBuf },
 }
 
+/// A file whose entry no longer matches the file system, as found by
+/// [`LocalWorktree::sample_verify`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Discrepancy {
+    /// The file no longer exists on disk.
+    Missing { path: Arc<RelPath> },
+    /// The file's mtime or size on disk differs from its entry.
+    Changed { path: Arc<RelPath> },
+}
+
+/// Records an operation performed on a local worktree, so that it can be reversed with
+/// [`LocalWorktree::undo`].
+///
+/// Only renames produce a token. Deletions can't be undone: [`Fs`] can move files to the
+/// trash but has no way to restore them from it.
+#[derive(Clone, Debug)]
+pub struct UndoToken(UndoOperation);
+
+#[derive(Clone, Debug)]
+enum UndoOperation {
+    Rename {
+        entry_id: ProjectEntryId,
+        old_path: Arc<RelPath>,
+        new_path: Arc<RelPath>,
+    },
+}
+
 #[derive(Debug)]
 pub struct LoadedFile {
     pub file: Arc<File>,
@@ -125,6 +163,171 @@ impl fmt::Debug for LoadedBinaryFile {
     }
 }
 
+/// The contents of recently loaded files, so that loading a file again skips reading it from
+/// disk. Only used when `cache_loaded_files` is enabled.
+///
+/// Entries are dropped by the background scanner whenever it reloads their path, so a rewrite
+/// that keeps the size and mtime is still picked up once its fs event has been processed. The
+/// mtime and size are only checked as an additional guard.
+#[derive(Default)]
+struct LoadedFileCache {
+    /// Ordered from most to least recently used.
+    entries: VecDeque<CachedFileText>,
+}
+
+struct CachedFileText {
+    path: Arc<RelPath>,
+    mtime: MTime,
+    len: u64,
+    text: String,
+    encoding: &'static Encoding,
+    has_bom: bool,
+}
+
+impl LoadedFileCache {
+    const CAPACITY: usize = 16;
+    const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+    fn get(
+        &mut self,
+        path: &RelPath,
+        mtime: MTime,
+        len: u64,
+    ) -> Option<(String, &'static Encoding, bool)> {
+        let ix = self
+            .entries
+            .iter()
+            .position(|cached| cached.path.as_ref() == path)?;
+        let cached = self.entries.remove(ix)?;
+        if cached.mtime != mtime || cached.len != len {
+            return None;
+        }
+        let result = (cached.text.clone(), cached.encoding, cached.has_bom);
+        self.entries.push_front(cached);
+        Some(result)
+    }
+
+    fn insert(
+        &mut self,
+        path: Arc<RelPath>,
+        mtime: MTime,
+        len: u64,
+        text: &str,
+        encoding: &'static Encoding,
+        has_bom: bool,
+    ) {
+        if len > Self::MAX_FILE_BYTES {
+            return;
+        }
+        self.invalidate(&path);
+        self.entries.truncate(Self::CAPACITY - 1);
+        self.entries.push_front(CachedFileText {
+            path,
+            mtime,
+            len,
+            text: text.to_string(),
+            encoding,
+            has_bom,
+        });
+    }
+
+    /// Drops the cached contents of `path` and of any file beneath it.
+    fn invalidate(&mut self, path: &RelPath) {
+        self.entries.retain(|cached| !cached.path.starts_with(path));
+    }
+}
+
+/// The working copy of a file together with its contents in the repository's HEAD.
+#[derive(Debug)]
+pub struct LoadedFileAtHead {
+    pub working_copy: LoadedFile,
+    /// `None` if the file is not in a repository, or is untracked at HEAD.
+    pub head_text: Option<String>,
+}
+
+/// The parts of an [`Entry`] that are stable across independent scans of the same file system.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EntrySansId<'a> {
+    pub path: &'a RelPath,
+    pub inode: u64,
+    pub is_ignored: bool,
+}
+
+/// An error returned by [`LocalWorktree::load_file`] that callers may want to handle specifically.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoadFileError {
+    /// The file exceeds the configured `max_loadable_file_bytes` limit.
+    TooLarge { size: u64 },
+}
+
+impl std::error::Error for LoadFileError {}
+
+impl fmt::Display for LoadFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge { size } => write!(f, "File is too large to load ({size} bytes)"),
+        }
+    }
+}
+
+/// An error returned by [`LocalWorktree::expand_entry`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExpandEntryError {
+    NotFound,
+    NotAnUnloadedDir,
+}
+
+impl std::error::Error for ExpandEntryError {}
+
+impl fmt::Display for ExpandEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Entry not found"),
+            Self::NotAnUnloadedDir => write!(f, "Entry is not an unloaded directory"),
+        }
+    }
+}
+
+/// Returned when deleting the worktree's root entry, which would remove the whole worktree.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RootEntryError;
+
+impl std::error::Error for RootEntryError {}
+
+impl fmt::Display for RootEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot delete the worktree root")
+    }
+}
+
+/// Returned when creating an entry at a path that already holds an entry that can't be reused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CreateEntryError {
+    /// A file was requested, but the path is a directory.
+    ExistsAsDirectory,
+    /// A directory was requested, but the path is a file.
+    ExistsAsFile,
+    /// A file with content was requested, but a file already exists at the path.
+    AlreadyExists,
+}
+
+impl std::error::Error for CreateEntryError {}
+
+impl fmt::Display for CreateEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExistsAsDirectory => write!(f, "A directory already exists at this path"),
+            Self::ExistsAsFile => write!(f, "A file already exists at this path"),
+            Self::AlreadyExists => {
+                write!(
+                    f,
+                    "A file already exists at this path and was not overwritten"
+                )
+            }
+        }
+    }
+}
+
 pub struct LocalWorktree {
     snapshot: LocalSnapshot,
     scan_requests_tx: channel::Sender<ScanRequest>,
@@ -137,8 +340,63 @@ pub struct LocalWorktree {
     visible: bool,
     next_entry_id: Arc<AtomicUsize>,
     settings: WorktreeSettings,
+    /// Exclusions and inclusions from the worktree's `.zed/worktree.json`, merged into `settings`.
+    scan_overrides: WorktreeScanOverrides,
     share_private_files: bool,
     scanning_enabled: bool,
+    /// Whether restarts of the background scanner (e.g. due to settings changes) are deferred.
+    scan_paused: bool,
+    /// Whether a background scanner restart was requested while scanning was paused.
+    restart_pending: bool,
+    loaded_file_cache: Arc<Mutex<LoadedFileCache>>,
+    rename_progress_trackers: Arc<Mutex<Vec<RenameProgressTracker>>>,
+    /// The paths created by in-progress [`LocalWorktree::batch`] calls. Changes to these paths
+    /// and their ancestors are collected in `batched_entry_changes` instead of being emitted.
+    batched_paths: Arc<Mutex<Vec<Arc<RelPath>>>>,
+    batched_entry_changes: Vec<(Arc<RelPath>, ProjectEntryId, PathChange)>,
+    /// The snapshot taken when the initial scan completed.
+    open_snapshot: Option<Snapshot>,
+}
+
+/// Operations performed inside a [`LocalWorktree::batch`], whose entry changes are reported
+/// in a single [`Event::UpdatedEntries`].
+pub struct BatchOps<'a> {
+    worktree: &'a LocalWorktree,
+    cx: &'a Context<'a, Worktree>,
+    paths: Vec<Arc<RelPath>>,
+    created_entries: Vec<Task<Result<CreatedEntry>>>,
+}
+
+impl BatchOps<'_> {
+    pub fn create_entry(&mut self, path: Arc<RelPath>, is_dir: bool, content: Option<Vec<u8>>) {
+        self.paths.push(path.clone());
+        self.created_entries
+            .push(self.worktree.create_entry(path, is_dir, content, self.cx));
+    }
+}
+
+/// Removes a batch's paths from `LocalWorktree::batched_paths` when dropped, so that they stop
+/// being collected even if the batch's task is dropped before completing.
+struct BatchGuard {
+    batched_paths: Arc<Mutex<Vec<Arc<RelPath>>>>,
+    paths: Vec<Arc<RelPath>>,
+}
+
+impl Drop for BatchGuard {
+    fn drop(&mut self) {
+        let mut batched_paths = self.batched_paths.lock();
+        for path in &self.paths {
+            if let Some(ix) = batched_paths.iter().position(|batched| batched == path) {
+                batched_paths.swap_remove(ix);
+            }
+        }
+    }
+}
+
+/// Receives the number of entries loaded by each directory scan beneath a renamed directory.
+struct RenameProgressTracker {
+    path: Arc<RelPath>,
+    loaded_count_tx: mpsc::UnboundedSender<usize>,
 }
 
 pub struct PathPrefixScanRequest {
@@ -148,6 +406,18 @@ pub struct PathPrefixScanRequest {
 
 struct ScanRequest {
     relative_paths: Vec<Arc<RelPath>>,
+    /// Whether the contents of the requested files should be sniffed for binary data.
+    sniff_binary: bool,
+    /// Whether the requested files should be checked for being Git LFS pointers.
+    detect_lfs_pointers: bool,
+    /// Whether all git repositories should be reloaded, as if their `.git` directories changed.
+    refresh_git_repositories: bool,
+    /// Whether the ignore statuses of all entries should be recomputed after reloading the
+    /// requested paths.
+    reload_gitignores: bool,
+    /// Whether the file system events that were already received should be applied before
+    /// the request is reported as done.
+    process_pending_fs_events: bool,
     done: SmallVec<[barrier::Sender; 1]>,
 }
 
@@ -226,6 +496,23 @@ impl WorkDirectory {
             WorkDirectory::AboveProject { .. } => true,
         }
     }
+
+    /// Converts a path relative to the repository's working directory into one relative to
+    /// the worktree root. Returns `None` for paths in a repository above the project that
+    /// lie outside of the worktree.
+    pub fn try_unrelativize(&self, repo_path: &RepoPath) -> Option<Arc<RelPath>> {
+        match self {
+            WorkDirectory::InProject { relative_path } => Some(relative_path.join(repo_path)),
+            WorkDirectory::AboveProject {
+                location_in_repo, ..
+            } => {
+                let location_in_repo =
+                    RelPath::new(location_in_repo, PathStyle::local()).log_err()?;
+                let path = repo_path.strip_prefix(&location_in_repo).ok()?;
+                Some(Arc::from(path))
+            }
+        }
+    }
 }
 
 impl Default for WorkDirectory {
@@ -240,6 +527,8 @@ impl Default for WorkDirectory {
 pub struct LocalSnapshot {
     snapshot: Snapshot,
     global_gitignore: Option<Arc<Gitignore>>,
+    /// Patterns set through [`LocalWorktree::set_additional_ignores`], applied beneath all gitignores.
+    additional_ignores: Option<Arc<Gitignore>>,
     /// Exclude files for all git repositories in the worktree, indexed by their absolute path.
     /// The boolean indicates whether the gitignore needs to be updated.
     repo_exclude_by_work_dir_abs_path: HashMap<Arc<Path>, (Arc<Gitignore>, bool)>,
@@ -275,6 +564,14 @@ struct LocalRepositoryEntry {
     work_directory: WorkDirectory,
     work_directory_abs_path: Arc<Path>,
     git_dir_scan_id: usize,
+    /// The scan id at which the repository's `logs/HEAD` was last observed to change.
+    head_log_scan_id: usize,
+    /// The scan id at which the repository's config, exclude file, or one of its gitignores was
+    /// last observed to change.
+    config_scan_id: usize,
+    /// The scan id at which one of the repository's refs, or its `packed-refs` file, was last
+    /// observed to change.
+    refs_scan_id: usize,
     /// Absolute path to the original .git entry that caused us to create this repository.
     ///
     /// This is normally a directory, but may be a "gitfile" that points to a directory elsewhere
@@ -359,6 +656,15 @@ pub enum Event {
     UpdatedEntries(UpdatedEntriesSet),
     UpdatedGitRepositories(UpdatedGitRepositoriesSet),
     DeletedEntry(ProjectEntryId),
+    /// The worktree's root was renamed or moved on disk.
+    RootChanged {
+        old_abs_path: Arc<Path>,
+        new_abs_path: Arc<Path>,
+    },
+    /// A repository's `.git/config`, `.git/info/exclude`, or one of its `.gitignore` files changed.
+    GitConfigChanged {
+        work_directory: WorkDirectory,
+    },
 }
 
 impl EventEmitter<Event> for Worktree {}
@@ -385,6 +691,8 @@ impl Worktree {
             true
         });
 
+        let scan_overrides = load_scan_overrides(fs.as_ref(), &abs_path).await;
+
         let root_file_handle = if metadata.as_ref().is_some() {
             fs.open_handle(&abs_path)
                 .await
@@ -403,6 +711,7 @@ impl Worktree {
             let mut snapshot = LocalSnapshot {
                 ignores_by_parent_abs_path: Default::default(),
                 global_gitignore: Default::default(),
+                additional_ignores: Default::default(),
                 repo_exclude_by_work_dir_abs_path: Default::default(),
                 git_repositories: Default::default(),
                 snapshot: Snapshot::new(
@@ -425,13 +734,17 @@ impl Worktree {
                 path: RelPath::empty(),
             });
 
-            let settings = WorktreeSettings::get(settings_location, cx).clone();
+            let settings = WorktreeSettings::get(settings_location, cx)
+                .clone()
+                .with_scan_overrides(&scan_overrides);
             cx.observe_global::<SettingsStore>(move |this, cx| {
                 if let Self::Local(this) = this {
-                    let settings = WorktreeSettings::get(settings_location, cx).clone();
+                    let settings = WorktreeSettings::get(settings_location, cx)
+                        .clone()
+                        .with_scan_overrides(&this.scan_overrides);
                     if this.settings != settings {
                         this.settings = settings;
-                        this.restart_background_scanners(cx);
+                        this.rescan_unless_paused(cx);
                     }
                 }
             })
@@ -446,6 +759,8 @@ impl Worktree {
                     snapshot.root_char_bag,
                     None,
                 );
+                entry.is_git_internal = settings.treat_git_dir_root_as_repo_internals
+                    && abs_path.file_name() == Some(OsStr::new(DOT_GIT));
                 if metadata.is_dir {
                     if !scanning_enabled {
                         entry.kind = EntryKind::UnloadedDir;
@@ -478,13 +793,104 @@ impl Worktree {
                 fs_case_sensitive,
                 visible,
                 settings,
+                scan_overrides,
                 scanning_enabled,
+                scan_paused: false,
+                restart_pending: false,
+                loaded_file_cache: Default::default(),
+                rename_progress_trackers: Default::default(),
+                batched_paths: Default::default(),
+                batched_entry_changes: Vec::new(),
+                open_snapshot: None,
             };
             worktree.start_background_scanner(scan_requests_rx, path_prefixes_to_scan_rx, cx);
             Worktree::Local(worktree)
         }))
     }
 
+    /// Walks the directory at `root` without building a snapshot, counting the files a scan
+    /// would find and how many of them the gitignore files beneath `root` would hide. Like a
+    /// scan, it skips paths excluded by the settings and directories it can't read.
+    pub fn estimate(root: Arc<Path>, fs: Arc<dyn Fs>, cx: &App) -> Task<ScanEstimate> {
+        let settings = WorktreeSettings::get_global(cx).clone();
+        cx.background_spawn(async move {
+            let mut estimate = ScanEstimate::default();
+            let mut files_by_dir = HashMap::<PathBuf, usize>::default();
+            let mut dirs_to_scan = vec![(root.to_path_buf(), IgnoreStack::none())];
+            while let Some((dir_abs_path, mut ignore_stack)) = dirs_to_scan.pop() {
+                if let Ok(ignore) =
+                    build_gitignore(&dir_abs_path.join(GITIGNORE), fs.as_ref()).await
+                {
+                    ignore_stack = ignore_stack.append(
+                        IgnoreKind::Gitignore(dir_abs_path.as_path().into()),
+                        Arc::new(ignore),
+                    );
+                }
+
+                let Some(mut child_abs_paths) = fs
+                    .read_dir(&dir_abs_path)
+                    .await
+                    .with_context(|| format!("reading directory {dir_abs_path:?}"))
+                    .log_err()
+                else {
+                    continue;
+                };
+                while let Some(child_abs_path) = child_abs_paths.next().await {
+                    let Some(child_abs_path) = child_abs_path.log_err() else {
+                        continue;
+                    };
+                    let is_excluded = child_abs_path
+                        .strip_prefix(&root)
+                        .ok()
+                        .and_then(|path| RelPath::new(path, PathStyle::local()).ok())
+                        .is_some_and(|path| settings.is_path_excluded(&path));
+                    if is_excluded {
+                        continue;
+                    }
+                    let Some(metadata) = fs.metadata(&child_abs_path).await.log_err().flatten()
+                    else {
+                        continue;
+                    };
+                    let is_ignored =
+                        ignore_stack.is_abs_path_ignored(&child_abs_path, metadata.is_dir);
+                    if metadata.is_dir {
+                        if !metadata.is_symlink {
+                            let child_ignore_stack = if is_ignored {
+                                IgnoreStack::all()
+                            } else {
+                                ignore_stack.clone()
+                            };
+                            dirs_to_scan.push((child_abs_path, child_ignore_stack));
+                        }
+                        continue;
+                    }
+
+                    estimate.total += 1;
+                    if is_ignored {
+                        estimate.ignored += 1;
+                    }
+                    for ancestor in child_abs_path.ancestors().skip(1) {
+                        let Ok(ancestor) = ancestor.strip_prefix(&root) else {
+                            break;
+                        };
+                        if ancestor.as_os_str().is_empty() {
+                            break;
+                        }
+                        *files_by_dir.entry(ancestor.to_path_buf()).or_default() += 1;
+                    }
+                }
+            }
+
+            let mut largest_dirs = files_by_dir.into_iter().collect::<Vec<_>>();
+            largest_dirs.sort_by(|(path_a, count_a), (path_b, count_b)| {
+                count_b.cmp(count_a).then_with(|| path_a.cmp(path_b))
+            });
+            largest_dirs.truncate(ScanEstimate::LARGEST_DIRS_COUNT);
+            estimate.largest_dirs = largest_dirs;
+            estimate
+        })
+    }
+
     pub fn remote(
         project_id: u64,
         replica_id: ReplicaId,
@@ -687,6 +1093,15 @@ impl Worktree {
         }
     }
 
+    /// Describes the settings globs that failed to compile and are ignored while scanning.
+    /// Remote worktrees report no errors, as their settings are validated by the host.
+    pub fn settings_errors(&self) -> Vec<String> {
+        match self {
+            Worktree::Local(worktree) => worktree.settings.settings_errors.clone(),
+            Worktree::Remote(_) => Vec::new(),
+        }
+    }
+
     pub fn root_file(&self, cx: &Context<Self>) -> Option<Arc<File>> {
         let entry = self.root_entry()?;
         Some(File::for_entry(entry.clone(), cx.entity()))
@@ -703,6 +1118,28 @@ impl Worktree {
         }
     }
 
+    /// Streams the paths of entries that became known because an unloaded directory was loaded,
+    /// leaving out entries that were created on disk. The stream ends when the worktree is dropped.
+    pub fn observe_loaded_entries(
+        &self,
+        cx: &mut Context<Self>,
+    ) -> impl Stream<Item = Arc<RelPath>> + Unpin + use<> {
+        let (tx, rx) = mpsc::unbounded();
+        let subscription = cx.subscribe_self(move |_, event, _| {
+            if let Event::UpdatedEntries(changes) = event {
+                for (path, _, change) in changes.iter() {
+                    if *change == PathChange::Loaded {
+                        tx.unbounded_send(path.clone()).ok();
+                    }
+                }
+            }
+        });
+        rx.map(move |path| {
+            let _subscription = &subscription;
+            path
+        })
+    }
+
     pub fn stop_observing_updates(&mut self) {
         match self {
             Worktree::Local(this) => {
@@ -806,12 +1243,23 @@ impl Worktree {
         }
     }
 
+    /// Deletes the entry, moving it to the trash when `trash` is `Some(true)`, or when it's
+    /// `None` and the `delete_to_trash` setting is enabled. Unlike renames, deletions return
+    /// no [`UndoToken`], since there is no way to restore an entry from the trash.
     pub fn delete_entry(
         &mut self,
         entry_id: ProjectEntryId,
-        trash: bool,
+        trash: Option<bool>,
         cx: &mut Context<Worktree>,
     ) -> Option<Task<Result<()>>> {
+        if self.entry_for_id(entry_id)?.is_root() {
+            return Some(Task::ready(Err(RootEntryError.into())));
+        }
+
+        let trash = trash.unwrap_or_else(|| {
+            WorktreeSettings::get(Some(self.settings_location(cx)), cx).delete_to_trash
+        });
+
         let task = match self {
             Worktree::Local(this) => this.delete_entry(entry_id, trash, cx),
             Worktree::Remote(this) => this.delete_entry(entry_id, trash, cx),
@@ -872,7 +1320,13 @@ impl Worktree {
         cx: &Context<Worktree>,
     ) -> Option<Task<Result<()>>> {
         match self {
-            Worktree::Local(this) => this.expand_entry(entry_id, cx),
+            Worktree::Local(this) => {
+                // Directories that are already loaded have nothing left to expand.
+                if this.entry_for_id(entry_id)?.kind != EntryKind::UnloadedDir {
+                    return Some(Task::ready(Ok(())));
+                }
+                Some(this.expand_entry(entry_id, cx))
+            }
             Worktree::Remote(this) => {
                 let response = this.client.request(proto::ExpandProjectEntry {
                     project_id: this.project_id,
@@ -955,7 +1409,7 @@ impl Worktree {
                 this.scan_id(),
                 this.delete_entry(
                     ProjectEntryId::from_proto(request.entry_id),
-                    request.use_trash,
+                    Some(request.use_trash),
                     cx,
                 ),
             )
@@ -1048,7 +1502,139 @@ impl LocalWorktree {
         self.fs_case_sensitive
     }
 
+    /// Explains which of the exclusion, inclusion and ignore rules decided how the path was
+    /// scanned. Meant for debugging the precedence between these rules.
+    pub fn explain_path(&self, path: &RelPath) -> PathExplanation {
+        let entry = self.entry_for_path(path).cloned();
+        let decision = if let Some(pattern) = self.settings.excluding_pattern(path) {
+            PathDecision::Excluded { pattern }
+        } else {
+            match &entry {
+                None => PathDecision::NotFound,
+                Some(entry) if entry.is_always_included => self
+                    .settings
+                    .including_pattern(path, entry.is_dir())
+                    .map_or(PathDecision::Included, |pattern| {
+                        PathDecision::AlwaysIncluded { pattern }
+                    }),
+                Some(entry) if entry.is_ignored => PathDecision::Ignored,
+                Some(_) => PathDecision::Included,
+            }
+        };
+        PathExplanation { decision, entry }
+    }
+
+    /// Whether reindexing is paused. See [`Self::set_scan_paused`].
+    pub fn scan_paused(&self) -> bool {
+        self.scan_paused
+    }
+
+    /// Pauses or resumes reindexing of the worktree.
+    ///
+    /// While paused, changes that would normally trigger a full rescan (such as editing
+    /// `file_scan_exclusions`) are deferred, and a single rescan happens upon resuming.
+    /// The pause state is kept across settings reloads.
+    pub fn set_scan_paused(&mut self, paused: bool, cx: &Context<Worktree>) {
+        self.scan_paused = paused;
+        if !paused && self.restart_pending {
+            self.restart_background_scanners(cx);
+        }
+    }
+
+    /// Returns the paths that changed on disk since the initial scan of the worktree completed.
+    /// Empty while the initial scan is still in progress.
+    pub fn changed_since_open(&self) -> Vec<(Arc<RelPath>, PathChange)> {
+        self.open_snapshot
+            .as_ref()
+            .map(|open_snapshot| self.snapshot.diff(open_snapshot))
+            .unwrap_or_default()
+    }
+
+    /// Runs the operations queued by `f`, and emits the entry changes they cause as a single
+    /// [`Event::UpdatedEntries`] once all of them have completed. Resolves to the created entries.
+    pub fn batch(
+        &mut self,
+        cx: &Context<Worktree>,
+        f: impl FnOnce(&mut BatchOps<'_>),
+    ) -> Task<Result<Vec<CreatedEntry>>> {
+        let mut ops = BatchOps {
+            worktree: self,
+            cx,
+            paths: Vec::new(),
+            created_entries: Vec::new(),
+        };
+        f(&mut ops);
+        let BatchOps {
+            paths,
+            created_entries,
+            ..
+        } = ops;
+        self.batched_paths.lock().extend(paths.iter().cloned());
+        let guard = BatchGuard {
+            batched_paths: self.batched_paths.clone(),
+            paths,
+        };
+        cx.spawn(async move |this, cx| {
+            let created_entries = futures::future::join_all(created_entries).await;
+            drop(guard);
+            this.update(cx, |this, cx| {
+                if let Some(this) = this.as_local_mut() {
+                    this.flush_batched_entry_changes(cx);
+                }
+            })?;
+            created_entries.into_iter().collect()
+        })
+    }
+
+    /// Emits the collected entry changes that no in-progress batch is waiting for.
+    fn flush_batched_entry_changes(&mut self, cx: &mut Context<Worktree>) {
+        let batched_paths = self.batched_paths.lock();
+        let (held, ready): (Vec<_>, Vec<_>) = mem::take(&mut self.batched_entry_changes)
+            .into_iter()
+            .partition(|(path, _, _)| {
+                batched_paths
+                    .iter()
+                    .any(|batched_path| batched_path.starts_with(path))
+            });
+        drop(batched_paths);
+        self.batched_entry_changes = held;
+        let entry_changes = coalesce_entry_changes(ready);
+        if !entry_changes.is_empty() {
+            cx.emit(Event::UpdatedEntries(entry_changes));
+        }
+    }
+
+    /// Treats paths matching the given gitignore-style patterns as ignored, in addition to the
+    /// worktree's gitignore files, and rescans the worktree to apply them. Patterns are relative
+    /// to the worktree root, and replace any previously set additional patterns.
+    pub fn set_additional_ignores(
+        &mut self,
+        patterns: &[&str],
+        cx: &Context<Worktree>,
+    ) -> Result<()> {
+        self.snapshot.additional_ignores = if patterns.is_empty() {
+            None
+        } else {
+            let mut builder = GitignoreBuilder::new(self.snapshot.abs_path.as_path());
+            for pattern in patterns {
+                builder.add_line(None, pattern)?;
+            }
+            Some(Arc::new(builder.build()?))
+        };
+        self.rescan_unless_paused(cx);
+        Ok(())
+    }
+
+    fn rescan_unless_paused(&mut self, cx: &Context<Worktree>) {
+        if self.scan_paused {
+            self.restart_pending = true;
+        } else {
+            self.restart_background_scanners(cx);
+        }
+    }
+
     fn restart_background_scanners(&mut self, cx: &Context<Worktree>) {
+        self.restart_pending = false;
         let (scan_requests_tx, scan_requests_rx) = channel::unbounded();
         let (path_prefixes_to_scan_tx, path_prefixes_to_scan_rx) = channel::unbounded();
         self.scan_requests_tx = scan_requests_tx;
@@ -1074,7 +1660,9 @@ impl LocalWorktree {
     ) {
         let snapshot = self.snapshot();
         let share_private_files = self.share_private_files;
+        let loaded_file_cache = self.loaded_file_cache.clone();
         let next_entry_id = self.next_entry_id.clone();
+        let rename_progress_trackers = self.rename_progress_trackers.clone();
         let fs = self.fs.clone();
         let scanning_enabled = self.scanning_enabled;
         let settings = self.settings.clone();
@@ -1101,6 +1689,8 @@ impl LocalWorktree {
                     scan_requests_rx,
                     path_prefixes_to_scan_rx,
                     next_entry_id,
+                    rename_progress_trackers,
+                    loaded_file_cache,
                     state: async_lock::Mutex::new(BackgroundScannerState {
                         prev_snapshot: snapshot.snapshot.clone(),
                         snapshot,
@@ -1123,27 +1713,87 @@ impl LocalWorktree {
             }
         });
         let scan_state_updater = cx.spawn(async move |this, cx| {
-            while let Some((state, this)) = scan_states_rx.next().await.zip(this.upgrade()) {
+            // While changes to the repositories are being collected, holds the repositories
+            // as they were when collection started, and the timer that ends it.
+            let mut repository_events_window =
+                None::<(TreeMap<ProjectEntryId, LocalRepositoryEntry>, Task<()>)>;
+            loop {
+                let next_state = if let Some((_, window_closed)) = &mut repository_events_window {
+                    select_biased! {
+                        state = scan_states_rx.next() => Some(state),
+                        _ = window_closed.fuse() => None,
+                    }
+                } else {
+                    Some(scan_states_rx.next().await)
+                };
+                let Some(this) = this.upgrade() else {
+                    break;
+                };
+                let Some(state) = next_state else {
+                    let (old_git_repositories, _) = repository_events_window.take().unwrap();
+                    this.update(cx, |this, cx| {
+                        this.as_local()
+                            .unwrap()
+                            .emit_repository_events(&old_git_repositories, cx);
+                    });
+                    continue;
+                };
+                let Some(state) = state else {
+                    break;
+                };
+
+                // Updates that queued up while we were busy, e.g. during a `git gc`, are
+                // applied together so that they're reported in a single repository event.
+                // As for a single update, that event follows the entry events and precedes
+                // the config events.
+                let mut states = vec![state];
+                while let Ok(Some(next_state)) = scan_states_rx.try_next() {
+                    states.push(next_state);
+                }
                 this.update(cx, |this, cx| {
                     let this = this.as_local_mut().unwrap();
-                    match state {
-                        ScanState::Started => {
-                            *this.is_scanning.0.borrow_mut() = true;
-                        }
-                        ScanState::Updated {
-                            snapshot,
-                            changes,
-                            barrier,
-                            scanning,
-                        } => {
-                            *this.is_scanning.0.borrow_mut() = scanning;
-                            this.set_snapshot(snapshot, changes, cx);
-                            drop(barrier);
-                        }
-                        ScanState::RootUpdated { new_path } => {
-                            this.update_abs_path_and_refresh(new_path, cx);
+                    let old_git_repositories = this.snapshot.git_repositories.clone();
+                    for state in states {
+                        match state {
+                            ScanState::Started => {
+                                *this.is_scanning.0.borrow_mut() = true;
+                            }
+                            ScanState::Updated {
+                                snapshot,
+                                changes,
+                                barrier,
+                                scanning,
+                            } => {
+                                *this.is_scanning.0.borrow_mut() = scanning;
+                                this.set_snapshot(snapshot, changes, cx);
+                                if !scanning && this.open_snapshot.is_none() {
+                                    this.open_snapshot = Some(this.snapshot.snapshot.clone());
+                                }
+                                drop(barrier);
+                            }
+                            ScanState::RootUpdated { new_path } => {
+                                this.update_abs_path_and_refresh(new_path, cx);
+                            }
                         }
                     }
+
+                    // Changes made while a window is open are reported when it closes.
+                    if repository_events_window.is_some() {
+                        return;
+                    }
+                    let window =
+                        Duration::from_millis(this.settings.git_repository_events_window_ms);
+                    if window.is_zero() {
+                        this.emit_repository_events(&old_git_repositories, cx);
+                    } else if !Self::changed_repos(
+                        &old_git_repositories,
+                        &this.snapshot.git_repositories,
+                    )
+                    .is_empty()
+                    {
+                        repository_events_window =
+                            Some((old_git_repositories, cx.background_executor().timer(window)));
+                    }
                 });
             }
         });
@@ -1153,11 +1803,10 @@ impl LocalWorktree {
 
     fn set_snapshot(
         &mut self,
-        mut new_snapshot: LocalSnapshot,
+        new_snapshot: LocalSnapshot,
         entry_changes: UpdatedEntriesSet,
         cx: &mut Context<Worktree>,
     ) {
-        let repo_changes = self.changed_repos(&self.snapshot, &mut new_snapshot);
         self.snapshot = new_snapshot;
 
         if let Some(share) = self.update_observer.as_mut() {
@@ -1167,22 +1816,84 @@ impl LocalWorktree {
                 .ok();
         }
 
-        if !entry_changes.is_empty() {
-            cx.emit(Event::UpdatedEntries(entry_changes));
+        if entry_changes
+            .iter()
+            .any(|(path, _, _)| path.as_ref() == local_worktree_file_relative_path())
+        {
+            self.reload_scan_overrides(cx);
         }
+
+        if self.batched_paths.lock().is_empty() && self.batched_entry_changes.is_empty() {
+            if !entry_changes.is_empty() {
+                cx.emit(Event::UpdatedEntries(entry_changes));
+            }
+        } else {
+            self.batched_entry_changes
+                .extend(entry_changes.iter().cloned());
+            self.flush_batched_entry_changes(cx);
+        }
+    }
+
+    fn reload_scan_overrides(&self, cx: &Context<Worktree>) {
+        let fs = self.fs.clone();
+        let abs_path = self.abs_path().clone();
+        cx.spawn(async move |this, cx| {
+            let scan_overrides = load_scan_overrides(fs.as_ref(), &abs_path).await;
+            this.update(cx, |this, cx| {
+                let Some(this) = this.as_local_mut() else {
+                    return;
+                };
+                if this.scan_overrides == scan_overrides {
+                    return;
+                }
+                let settings_location = Some(SettingsLocation {
+                    worktree_id: this.id(),
+                    path: RelPath::empty(),
+                });
+                this.settings = WorktreeSettings::get(settings_location, cx)
+                    .clone()
+                    .with_scan_overrides(&scan_overrides);
+                this.scan_overrides = scan_overrides;
+                this.rescan_unless_paused(cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Reports the repositories that changed since `old_git_repositories` was taken, followed
+    /// by the ones whose config changed.
+    fn emit_repository_events(
+        &self,
+        old_git_repositories: &TreeMap<ProjectEntryId, LocalRepositoryEntry>,
+        cx: &mut Context<Worktree>,
+    ) {
+        let repo_changes =
+            Self::changed_repos(old_git_repositories, &self.snapshot.git_repositories);
         if !repo_changes.is_empty() {
             cx.emit(Event::UpdatedGitRepositories(repo_changes));
         }
+        let config_changes = self
+            .snapshot
+            .git_repositories
+            .iter()
+            .filter(|(work_directory_id, new_repo)| {
+                old_git_repositories
+                    .get(work_directory_id)
+                    .is_some_and(|old_repo| old_repo.config_scan_id != new_repo.config_scan_id)
+            })
+            .map(|(_, repo)| repo.work_directory.clone())
+            .collect::<Vec<_>>();
+        for work_directory in config_changes {
+            cx.emit(Event::GitConfigChanged { work_directory });
+        }
     }
 
     fn changed_repos(
-        &self,
-        old_snapshot: &LocalSnapshot,
-        new_snapshot: &mut LocalSnapshot,
+        old_repos: &TreeMap<ProjectEntryId, LocalRepositoryEntry>,
+        new_repos: &TreeMap<ProjectEntryId, LocalRepositoryEntry>,
     ) -> UpdatedGitRepositoriesSet {
         let mut changes = Vec::new();
-        let mut old_repos = old_snapshot.git_repositories.iter().peekable();
-        let new_repos = new_snapshot.git_repositories.clone();
+        let mut old_repos = old_repos.iter().peekable();
         let mut new_repos = new_repos.iter().peekable();
 
         loop {
@@ -1201,6 +1912,9 @@ impl LocalWorktree {
                                     new_repo.repository_dir_abs_path.clone(),
                                 ),
                                 common_dir_abs_path: Some(new_repo.common_dir_abs_path.clone()),
+                                head_moved: false,
+                                config_changed: false,
+                                refs_changed: false,
                             });
                             new_repos.next();
                         }
@@ -1222,6 +1936,11 @@ impl LocalWorktree {
                                         new_repo.repository_dir_abs_path.clone(),
                                     ),
                                     common_dir_abs_path: Some(new_repo.common_dir_abs_path.clone()),
+                                    head_moved: new_repo.head_log_scan_id
+                                        != old_repo.head_log_scan_id,
+                                    config_changed: new_repo.config_scan_id
+                                        != old_repo.config_scan_id,
+                                    refs_changed: new_repo.refs_scan_id != old_repo.refs_scan_id,
                                 });
                             }
                             new_repos.next();
@@ -1237,6 +1956,9 @@ impl LocalWorktree {
                                 dot_git_abs_path: None,
                                 repository_dir_abs_path: None,
                                 common_dir_abs_path: None,
+                                head_moved: false,
+                                config_changed: false,
+                                refs_changed: false,
                             });
                             old_repos.next();
                         }
@@ -1250,6 +1972,9 @@ impl LocalWorktree {
                         dot_git_abs_path: Some(repo.dot_git_abs_path.clone()),
                         repository_dir_abs_path: Some(repo.repository_dir_abs_path.clone()),
                         common_dir_abs_path: Some(repo.common_dir_abs_path.clone()),
+                        head_moved: false,
+                        config_changed: false,
+                        refs_changed: false,
                     });
                     new_repos.next();
                 }
@@ -1261,6 +1986,9 @@ impl LocalWorktree {
                         dot_git_abs_path: Some(repo.dot_git_abs_path.clone()),
                         repository_dir_abs_path: Some(repo.repository_dir_abs_path.clone()),
                         common_dir_abs_path: Some(repo.common_dir_abs_path.clone()),
+                        head_moved: false,
+                        config_changed: false,
+                        refs_changed: false,
                     });
                     old_repos.next();
                 }
@@ -1348,9 +2076,15 @@ impl LocalWorktree {
         let fs = self.fs.clone();
         let entry = self.refresh_entry(path.clone(), None, cx);
         let is_private = self.is_path_private(path.as_ref());
+        let max_loadable_file_bytes = self.settings.max_loadable_file_bytes;
+        let loaded_file_cache = self
+            .settings
+            .cache_loaded_files
+            .then(|| self.loaded_file_cache.clone());
 
         let this = cx.weak_entity();
         cx.background_spawn(async move {
+            let metadata = fs.metadata(&abs_path).await.ok().flatten();
             // WARN: Temporary workaround for #27283.
             //       We are not efficient with our memory usage per file, and use in excess of 64GB for a 10GB file
             //       Therefore, as a temporary workaround to prevent system freezes, we just bail before opening a file
@@ -1359,13 +2093,42 @@ impl LocalWorktree {
             //       reasonable limit
             {
                 const FILE_SIZE_MAX: u64 = 6 * 1024 * 1024 * 1024; // 6GB
-                if let Ok(Some(metadata)) = fs.metadata(&abs_path).await
-                    && metadata.len >= FILE_SIZE_MAX
-                {
-                    anyhow::bail!("File is too large to load");
+                if let Some(metadata) = &metadata {
+                    if max_loadable_file_bytes.is_some_and(|max_bytes| metadata.len > max_bytes) {
+                        return Err(LoadFileError::TooLarge { size: metadata.len }.into());
+                    }
+                    if metadata.len >= FILE_SIZE_MAX {
+                        anyhow::bail!("File is too large to load");
+                    }
                 }
             }
-            let (text, encoding, has_bom) = decode_file_text(fs.as_ref(), &abs_path).await?;
+            let cached_text = metadata.as_ref().zip(loaded_file_cache.as_ref()).and_then(
+                |(metadata, loaded_file_cache)| {
+                    loaded_file_cache
+                        .lock()
+                        .get(&path, metadata.mtime, metadata.len)
+                },
+            );
+            let (text, encoding, has_bom) = match cached_text {
+                Some(cached_text) => cached_text,
+                None => {
+                    let (text, encoding, has_bom) =
+                        decode_file_text(fs.as_ref(), &abs_path).await?;
+                    if let Some(metadata) = &metadata
+                        && let Some(loaded_file_cache) = &loaded_file_cache
+                    {
+                        loaded_file_cache.lock().insert(
+                            path.clone(),
+                            metadata.mtime,
+                            metadata.len,
+                            &text,
+                            encoding,
+                            has_bom,
+                        );
+                    }
+                    (text, encoding, has_bom)
+                }
+            };
 
             let worktree = this.upgrade().context("worktree was dropped")?;
             let file = match entry.await? {
@@ -1402,6 +2165,130 @@ impl LocalWorktree {
         })
     }
 
+    /// Loads the contents of the file at `path` as of the HEAD commit of its repository.
+    pub fn load_committed_file(&self, path: &RelPath, cx: &App) -> Task<Result<Option<String>>> {
+        let Some(repository) = self
+            .git_repositories
+            .iter()
+            .map(|(_, repository)| repository)
+            .filter(|repository| repository.work_directory.directory_contains(path))
+            .max_by_key(|repository| repository.work_directory.path_key())
+        else {
+            return Task::ready(Ok(None));
+        };
+        let repo_path = match &repository.work_directory {
+            WorkDirectory::InProject { relative_path } => path
+                .strip_prefix(relative_path)
+                .map(RepoPath::from_rel_path)
+                .map_err(|_| anyhow!("{path:?} is not inside {relative_path:?}")),
+            WorkDirectory::AboveProject {
+                location_in_repo, ..
+            } => RepoPath::from_std_path(
+                &location_in_repo.join(path.as_std_path()),
+                PathStyle::local(),
+            ),
+        };
+        let repo_path = match repo_path {
+            Ok(repo_path) => repo_path,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let fs = self.fs.clone();
+        let dot_git_abs_path = repository.dot_git_abs_path.clone();
+        cx.background_spawn(async move {
+            let git_repository = fs
+                .open_repo(&dot_git_abs_path, None)
+                .with_context(|| format!("opening repository at {dot_git_abs_path:?}"))?;
+            Ok(git_repository.load_committed_text(repo_path).await)
+        })
+    }
+
+    /// Compares `count` randomly chosen files against the file system, to detect changes that
+    /// the file system watcher failed to report.
+    pub fn sample_verify(
+        &self,
+        count: usize,
+        rng: &mut impl Rng,
+        cx: &Context<Worktree>,
+    ) -> Task<Vec<Discrepancy>> {
+        let sampled_files = self
+            .files(true, 0)
+            .map(|entry| {
+                (
+                    entry.path.clone(),
+                    self.absolutize(&entry.path),
+                    entry.mtime,
+                    entry.size,
+                )
+            })
+            .choose_multiple(rng, count);
+        let fs = self.fs.clone();
+        cx.background_spawn(async move {
+            let mut discrepancies = Vec::new();
+            for (path, abs_path, mtime, size) in sampled_files {
+                match fs.metadata(&abs_path).await {
+                    Ok(Some(metadata)) => {
+                        if mtime != Some(metadata.mtime) || size != metadata.len {
+                            discrepancies.push(Discrepancy::Changed { path });
+                        }
+                    }
+                    Ok(None) => discrepancies.push(Discrepancy::Missing { path }),
+                    Err(error) => log::debug!("failed to verify {abs_path:?}: {error:#}"),
+                }
+            }
+            discrepancies
+        })
+    }
+
+    /// Yields the entries whose nearest repository is the one at `work_directory`, in path
+    /// order. Entries inside a repository nested within it are left out.
+    pub fn entries_under_repo<'a>(
+        &'a self,
+        work_directory: &'a WorkDirectory,
+    ) -> impl Iterator<Item = &'a Entry> + 'a {
+        let nested_work_directories = self
+            .git_repositories
+            .iter()
+            .filter_map(|(_, repository)| match &repository.work_directory {
+                WorkDirectory::InProject { relative_path }
+                    if repository.work_directory != *work_directory
+                        && work_directory.directory_contains(relative_path) =>
+                {
+                    Some(relative_path.clone())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let root_path = match work_directory {
+            WorkDirectory::InProject { relative_path } => relative_path.as_ref(),
+            WorkDirectory::AboveProject { .. } => RelPath::empty(),
+        };
+        self.traverse_from_path(true, true, true, root_path)
+            .take_while(move |entry| entry.path.starts_with(root_path))
+            .filter(move |entry| {
+                !nested_work_directories
+                    .iter()
+                    .any(|nested_path| entry.path.starts_with(nested_path))
+            })
+    }
+
+    /// Loads both the working copy and the HEAD contents of the file at `path`, running the
+    /// two reads concurrently.
+    pub fn open_buffer_at_head(
+        &self,
+        path: &RelPath,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<LoadedFileAtHead>> {
+        let working_copy = self.load_file(path, cx);
+        let head_text = self.load_committed_file(path, cx);
+        cx.background_spawn(async move {
+            let (working_copy, head_text) = futures::join!(working_copy, head_text);
+            Ok(LoadedFileAtHead {
+                working_copy: working_copy?,
+                head_text: head_text?,
+            })
+        })
+    }
+
     /// Find the lowest path in the worktree's datastructures that is an ancestor
     fn lowest_ancestor(&self, path: &RelPath) -> Arc<RelPath> {
         let mut lowest_ancestor = None;
@@ -1415,13 +2302,26 @@ impl LocalWorktree {
         lowest_ancestor.unwrap_or_else(|| RelPath::empty().into())
     }
 
-    fn create_entry(
+    /// Creates a file or directory at `path`. If the snapshot already has an entry of the
+    /// requested kind there, it is returned as-is without touching the file system, unless
+    /// `content` was given for a file. That case, like an entry of the other kind, yields a
+    /// [`CreateEntryError`] rather than overwriting the file.
+    pub fn create_entry(
         &self,
         path: Arc<RelPath>,
         is_dir: bool,
         content: Option<Vec<u8>>,
         cx: &Context<Worktree>,
     ) -> Task<Result<CreatedEntry>> {
+        if let Some(entry) = self.entry_for_path(&path) {
+            return Task::ready(match (entry.is_dir(), is_dir) {
+                (true, false) => Err(CreateEntryError::ExistsAsDirectory.into()),
+                (false, true) => Err(CreateEntryError::ExistsAsFile.into()),
+                (false, false) if content.is_some() => Err(CreateEntryError::AlreadyExists.into()),
+                _ => Ok(CreatedEntry::Included(entry.clone())),
+            });
+        }
+
         let abs_path = self.absolutize(&path);
         let path_excluded = self.settings.is_path_excluded(&path);
         let fs = self.fs.clone();
@@ -1476,6 +2376,67 @@ impl LocalWorktree {
         })
     }
 
+    /// Like [`Self::create_entry`], but resolves to every entry the call created: the missing
+    /// ancestor directories from the outermost one down, followed by the entry at `path`.
+    /// Resolves to no entries when `path` already exists or is excluded.
+    pub fn create_entry_with_ancestors(
+        &self,
+        path: Arc<RelPath>,
+        is_dir: bool,
+        content: Option<Vec<u8>>,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<Vec<Entry>>> {
+        let lowest_ancestor = self.lowest_ancestor(&path);
+        let create = self.create_entry(path.clone(), is_dir, content, cx);
+        cx.spawn(async move |this, cx| {
+            if let CreatedEntry::Excluded { .. } = create.await? {
+                return Ok(Vec::new());
+            }
+            this.read_with(cx, |this, _| {
+                let mut created_entries = path
+                    .ancestors()
+                    .take_while(|ancestor| *ancestor != lowest_ancestor.as_ref())
+                    .filter_map(|ancestor| this.entry_for_path(ancestor).cloned())
+                    .collect::<Vec<_>>();
+                created_entries.reverse();
+                created_entries
+            })
+        })
+    }
+
+    /// Creates a symlink at `path` pointing to `target`, and returns its entry once
+    /// it has been inserted into the snapshot. The entry's `canonical_path` holds
+    /// the resolved target.
+    pub fn create_symlink(
+        &self,
+        path: Arc<RelPath>,
+        target: PathBuf,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<Entry>> {
+        let abs_path = self.absolutize(&path);
+        if self.settings.is_path_excluded(&path) {
+            return Task::ready(Err(anyhow!("symlink {abs_path:?} is excluded")));
+        }
+        let fs = self.fs.clone();
+        let create = cx.background_spawn({
+            let abs_path = abs_path.clone();
+            async move {
+                fs.create_symlink(&abs_path, target)
+                    .await
+                    .with_context(|| format!("creating symlink {abs_path:?}"))
+            }
+        });
+
+        cx.spawn(async move |this, cx| {
+            create.await?;
+            this.update(cx, |this, cx| {
+                this.as_local_mut().unwrap().refresh_entry(path, None, cx)
+            })?
+            .await?
+            .with_context(|| format!("symlink {abs_path:?} was not inserted into the worktree"))
+        })
+    }
+
     fn write_file(
         &self,
         path: Arc<RelPath>,
@@ -1592,6 +2553,87 @@ impl LocalWorktree {
         })
     }
 
+    /// Like [`Self::write_file`], but also adds entries for any parent directories of `path` that
+    /// did not exist yet, returning them outermost first along with the written file.
+    pub fn write_file_creating_parents(
+        &self,
+        path: Arc<RelPath>,
+        text: Rope,
+        line_ending: LineEnding,
+        encoding: &'static Encoding,
+        has_bom: bool,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<(Arc<File>, Vec<Entry>)>> {
+        let Some(parent) = path.parent() else {
+            return Task::ready(Err(anyhow!("cannot write to the worktree root")));
+        };
+        let lowest_ancestor = self.lowest_ancestor(parent);
+        let mut missing_parents = parent
+            .ancestors()
+            .take_while(|ancestor| *ancestor != lowest_ancestor.as_ref())
+            .map(Arc::from)
+            .collect::<Vec<Arc<RelPath>>>();
+        missing_parents.reverse();
+        let parent_abs_path = self.absolutize(parent);
+        let fs = self.fs.clone();
+
+        cx.spawn(async move |this, cx| {
+            if !missing_parents.is_empty() {
+                fs.create_dir(&parent_abs_path)
+                    .await
+                    .with_context(|| format!("creating directory {parent_abs_path:?}"))?;
+            }
+            let refreshes = this.update(cx, |this, cx| {
+                let this = this.as_local().unwrap();
+                missing_parents
+                    .into_iter()
+                    .map(|parent| this.refresh_entry(parent, None, cx))
+                    .collect::<Vec<_>>()
+            })?;
+            let mut created_parents = Vec::new();
+            for refresh in refreshes {
+                created_parents.extend(refresh.await?);
+            }
+
+            let file = this
+                .update(cx, |this, cx| {
+                    this.as_local().unwrap().write_file(
+                        path,
+                        text,
+                        line_ending,
+                        encoding,
+                        has_bom,
+                        cx,
+                    )
+                })?
+                .await?;
+            Ok((file, created_parents))
+        })
+    }
+
+    /// Bumps the modification time of the entry's file without changing its content, e.g. to
+    /// force a rebuild, and refreshes the entry so that the snapshot sees the new mtime.
+    pub fn touch_entry(
+        &self,
+        entry_id: ProjectEntryId,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<()>> {
+        let Some(entry) = self.entry_for_id(entry_id) else {
+            return Task::ready(Err(anyhow!("no entry found for id {entry_id:?}")));
+        };
+        let path = entry.path.clone();
+        let abs_path = self.absolutize(&path);
+        let fs = self.fs.clone();
+        cx.spawn(async move |this, cx| {
+            fs.touch(&abs_path).await?;
+            this.update(cx, |this, cx| {
+                this.as_local_mut().unwrap().refresh_entry(path, None, cx)
+            })?
+            .await?;
+            Ok(())
+        })
+    }
+
     fn delete_entry(
         &self,
         entry_id: ProjectEntryId,
@@ -1644,6 +2686,120 @@ impl LocalWorktree {
         }))
     }
 
+    /// Renames the entry within this worktree, returning the renamed entry along with a token
+    /// that moves it back to its original path when passed to [`Self::undo`].
+    pub fn rename_entry(
+        &self,
+        entry_id: ProjectEntryId,
+        new_path: Arc<RelPath>,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<(Entry, UndoToken)>> {
+        let Some(entry) = self.entry_for_id(entry_id) else {
+            return Task::ready(Err(anyhow!("no such entry")));
+        };
+        if entry.is_root() {
+            return Task::ready(Err(RootEntryError.into()));
+        }
+        let old_path = entry.path.clone();
+        let rename = self.move_entry(old_path.clone(), new_path.clone(), cx);
+        cx.background_spawn(async move {
+            let entry = rename.await?;
+            let token = UndoToken(UndoOperation::Rename {
+                entry_id,
+                old_path,
+                new_path,
+            });
+            Ok((entry, token))
+        })
+    }
+
+    /// Like [`Self::rename_entry`], but also calls `on_progress` with the number of descendants
+    /// of the renamed directory that have been loaded at their new path so far, so that large
+    /// renames can show their progress.
+    pub fn rename_entry_with_progress(
+        &self,
+        entry_id: ProjectEntryId,
+        new_path: Arc<RelPath>,
+        on_progress: impl Fn(usize) + 'static,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<(Entry, UndoToken)>> {
+        let Some(entry) = self.entry_for_id(entry_id) else {
+            return Task::ready(Err(anyhow!("no such entry")));
+        };
+        let descendant_count = self
+            .traverse_from_path(true, true, true, &entry.path)
+            .take_while(|descendant| descendant.path.starts_with(&entry.path))
+            .count()
+            .saturating_sub(1);
+        if descendant_count > 0 {
+            let (loaded_count_tx, mut loaded_count_rx) = mpsc::unbounded();
+            self.rename_progress_trackers
+                .lock()
+                .push(RenameProgressTracker {
+                    path: new_path.clone(),
+                    loaded_count_tx,
+                });
+            cx.spawn(async move |_, _| {
+                let mut loaded_count = 0;
+                while let Some(count) = loaded_count_rx.next().await {
+                    loaded_count = (loaded_count + count).min(descendant_count);
+                    on_progress(loaded_count);
+                    if loaded_count == descendant_count {
+                        break;
+                    }
+                }
+            })
+            .detach();
+        }
+        self.rename_entry(entry_id, new_path, cx)
+    }
+
+    /// Reverses the operation recorded by the given token.
+    pub fn undo(&self, token: UndoToken, cx: &Context<Worktree>) -> Task<Result<()>> {
+        match token.0 {
+            UndoOperation::Rename {
+                entry_id,
+                old_path,
+                new_path,
+            } => {
+                let current_path = self
+                    .entry_for_id(entry_id)
+                    .map_or(new_path, |entry| entry.path.clone());
+                let rename = self.move_entry(current_path, old_path, cx);
+                cx.background_spawn(async move {
+                    rename.await?;
+                    Ok(())
+                })
+            }
+        }
+    }
+
+    fn move_entry(
+        &self,
+        old_path: Arc<RelPath>,
+        new_path: Arc<RelPath>,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<Entry>> {
+        let abs_old_path = self.absolutize(&old_path);
+        let abs_new_path = self.absolutize(&new_path);
+        let fs = self.fs.clone();
+        let rename = cx.background_spawn(async move {
+            fs.rename(&abs_old_path, &abs_new_path, Default::default())
+                .await
+                .with_context(|| format!("renaming {abs_old_path:?} into {abs_new_path:?}"))
+        });
+        cx.spawn(async move |this, cx| {
+            rename.await?;
+            this.update(cx, |this, cx| {
+                this.as_local()
+                    .unwrap()
+                    .refresh_entry(new_path.clone(), Some(old_path), cx)
+            })?
+            .await?
+            .with_context(|| format!("{new_path:?} is excluded from the worktree"))
+        })
+    }
+
     pub fn copy_external_entries(
         &self,
         target_directory: Arc<RelPath>,
@@ -1730,17 +2886,23 @@ impl LocalWorktree {
         })
     }
 
-    fn expand_entry(
+    /// Loads the children of an [`EntryKind::UnloadedDir`], such as an ignored or external
+    /// directory. The returned task resolves once the children are in the snapshot.
+    pub fn expand_entry(
         &self,
         entry_id: ProjectEntryId,
         cx: &Context<Worktree>,
-    ) -> Option<Task<Result<()>>> {
-        let path = self.entry_for_id(entry_id)?.path.clone();
+    ) -> Task<Result<()>> {
+        let path = match self.entry_for_id(entry_id) {
+            Some(entry) if entry.kind == EntryKind::UnloadedDir => entry.path.clone(),
+            Some(_) => return Task::ready(Err(ExpandEntryError::NotAnUnloadedDir.into())),
+            None => return Task::ready(Err(ExpandEntryError::NotFound.into())),
+        };
         let mut refresh = self.refresh_entries_for_paths(vec![path]);
-        Some(cx.background_spawn(async move {
+        cx.background_spawn(async move {
             refresh.next().await;
             Ok(())
-        }))
+        })
     }
 
     fn expand_all_for_entry(
@@ -1761,6 +2923,11 @@ impl LocalWorktree {
         self.scan_requests_tx
             .try_send(ScanRequest {
                 relative_paths: paths,
+                sniff_binary: false,
+                detect_lfs_pointers: false,
+                refresh_git_repositories: false,
+                reload_gitignores: false,
+                process_pending_fs_events: false,
                 done: smallvec![tx],
             })
             .ok();
@@ -1815,6 +2982,162 @@ impl LocalWorktree {
         })
     }
 
+    /// Returns the entry for `path`, scanning only the ancestors needed to reach it, even when
+    /// they are gitignored or not yet loaded. Unlike loading the file, its contents are not read.
+    pub fn ensure_entry(&self, path: &RelPath, cx: &Context<Worktree>) -> Task<Result<Entry>> {
+        let path: Arc<RelPath> = Arc::from(path);
+        let entry = self.refresh_entry(path.clone(), None, cx);
+        cx.background_spawn(async move {
+            entry
+                .await?
+                .with_context(|| format!("{path:?} is excluded from the worktree"))
+        })
+    }
+
+    /// Reads the beginning of the file at `path` to determine whether it holds binary
+    /// content, recording the result in the entry's `is_binary` field.
+    pub fn sniff_binary(&self, path: Arc<RelPath>, cx: &Context<Worktree>) -> Task<Result<bool>> {
+        if let Some(is_binary) = self.entry_for_path(&path).and_then(|entry| entry.is_binary) {
+            return Task::ready(Ok(is_binary));
+        }
+        let (tx, mut rx) = barrier::channel();
+        self.scan_requests_tx
+            .try_send(ScanRequest {
+                relative_paths: vec![path.clone()],
+                sniff_binary: true,
+                detect_lfs_pointers: false,
+                refresh_git_repositories: false,
+                reload_gitignores: false,
+                process_pending_fs_events: false,
+                done: smallvec![tx],
+            })
+            .ok();
+        cx.spawn(async move |this, cx| {
+            rx.recv().await;
+            this.read_with(cx, |this, _| {
+                let entry = this
+                    .entry_for_path(&path)
+                    .with_context(|| format!("Could not find entry in worktree for {path:?}"))?;
+                entry
+                    .is_binary
+                    .with_context(|| format!("Could not determine whether {path:?} is binary"))
+            })?
+        })
+    }
+
+    /// Reads the file at `path` to determine whether it's a Git LFS pointer, recording the
+    /// result in the entry's `is_lfs_pointer` field.
+    pub fn check_lfs_pointer(
+        &self,
+        path: Arc<RelPath>,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<bool>> {
+        if let Some(is_lfs_pointer) = self
+            .entry_for_path(&path)
+            .and_then(|entry| entry.is_lfs_pointer)
+        {
+            return Task::ready(Ok(is_lfs_pointer));
+        }
+        let (tx, mut rx) = barrier::channel();
+        self.scan_requests_tx
+            .try_send(ScanRequest {
+                relative_paths: vec![path.clone()],
+                sniff_binary: false,
+                detect_lfs_pointers: true,
+                refresh_git_repositories: false,
+                reload_gitignores: false,
+                process_pending_fs_events: false,
+                done: smallvec![tx],
+            })
+            .ok();
+        cx.spawn(async move |this, cx| {
+            rx.recv().await;
+            this.read_with(cx, |this, _| {
+                let entry = this
+                    .entry_for_path(&path)
+                    .with_context(|| format!("Could not find entry in worktree for {path:?}"))?;
+                entry.is_lfs_pointer.with_context(|| {
+                    format!("Could not determine whether {path:?} is an LFS pointer")
+                })
+            })?
+        })
+    }
+
+    /// Reloads every git repository in the worktree without waiting for file system events,
+    /// which can be dropped on network file systems. The task completes once the worktree has
+    /// reported the repositories as updated, causing their statuses to be recomputed.
+    pub fn refresh_git_statuses(&self, cx: &Context<Worktree>) -> Task<Result<()>> {
+        let (tx, mut rx) = barrier::channel();
+        let sent = self.scan_requests_tx.try_send(ScanRequest {
+            relative_paths: Vec::new(),
+            sniff_binary: false,
+            detect_lfs_pointers: false,
+            refresh_git_repositories: true,
+            reload_gitignores: false,
+            process_pending_fs_events: false,
+            done: smallvec![tx],
+        });
+        if sent.is_err() {
+            return Task::ready(Err(anyhow!("worktree scanner has stopped")));
+        }
+        cx.background_spawn(async move {
+            rx.recv().await;
+            Ok(())
+        })
+    }
+
+    /// Resolves once the initial scan has completed and the file system events received so far
+    /// have been applied to the snapshot, e.g. to observe the changes an external tool made.
+    /// Events the file system has yet to deliver are not waited for.
+    pub fn settle(&self, cx: &Context<Worktree>) -> Task<()> {
+        let scan_complete = self.scan_complete();
+        let scan_requests_tx = self.scan_requests_tx.clone();
+        cx.background_spawn(async move {
+            scan_complete.await;
+            let (tx, mut rx) = barrier::channel();
+            let sent = scan_requests_tx.try_send(ScanRequest {
+                relative_paths: Vec::new(),
+                sniff_binary: false,
+                detect_lfs_pointers: false,
+                refresh_git_repositories: false,
+                reload_gitignores: false,
+                process_pending_fs_events: true,
+                done: smallvec![tx],
+            });
+            if sent.is_ok() {
+                rx.recv().await;
+            }
+        })
+    }
+
+    /// Re-reads every `.gitignore` file in the worktree and recomputes the ignore status of
+    /// its entries, without waiting for file system events or rescanning the whole worktree.
+    pub fn reload_gitignores(&self, cx: &Context<Worktree>) -> Task<Result<()>> {
+        let gitignore_paths = self
+            .snapshot
+            .entries(true, 0)
+            .filter(|entry| entry.is_file() && entry.path.file_name() == Some(GITIGNORE))
+            .map(|entry| entry.path.clone())
+            .collect();
+        let (tx, mut rx) = barrier::channel();
+        let sent = self.scan_requests_tx.try_send(ScanRequest {
+            relative_paths: gitignore_paths,
+            sniff_binary: false,
+            detect_lfs_pointers: false,
+            refresh_git_repositories: false,
+            reload_gitignores: true,
+            process_pending_fs_events: false,
+            done: smallvec![tx],
+        });
+        if sent.is_err() {
+            return Task::ready(Err(anyhow!("worktree scanner has stopped")));
+        }
+        cx.background_spawn(async move {
+            rx.recv().await;
+            Ok(())
+        })
+    }
+
     fn observe_updates<F, Fut>(&mut self, project_id: u64, cx: &Context<Worktree>, callback: F)
     where
         F: 'static + Send + Fn(proto::UpdateWorktree) -> Fut,
@@ -1870,14 +3193,15 @@ impl LocalWorktree {
 
     pub fn share_private_files(&mut self, cx: &Context<Worktree>) {
         self.share_private_files = true;
-        self.restart_background_scanners(cx);
+        self.rescan_unless_paused(cx);
     }
 
     pub fn update_abs_path_and_refresh(
         &mut self,
         new_path: Arc<SanitizedPath>,
-        cx: &Context<Worktree>,
+        cx: &mut Context<Worktree>,
     ) {
+        let old_abs_path = self.abs_path().clone();
         self.snapshot.git_repositories = Default::default();
         self.snapshot.ignores_by_parent_abs_path = Default::default();
         let root_name = new_path
@@ -1889,6 +3213,14 @@ impl LocalWorktree {
             });
         self.snapshot.update_abs_path(new_path, root_name);
         self.restart_background_scanners(cx);
+
+        let new_abs_path = self.abs_path().clone();
+        if old_abs_path != new_abs_path {
+            cx.emit(Event::RootChanged {
+                old_abs_path,
+                new_abs_path,
+            });
+        }
     }
 }
 
@@ -2219,6 +3551,17 @@ impl Snapshot {
         }
     }
 
+    /// The inverse of [`Self::absolutize`]. Returns `None` if `abs_path` isn't inside this worktree.
+    pub fn relativize(&self, abs_path: &Path) -> Option<Arc<RelPath>> {
+        let relative_path = SanitizedPath::new(abs_path)
+            .as_path()
+            .strip_prefix(self.abs_path.as_path())
+            .ok()?;
+        RelPath::new(relative_path, self.path_style)
+            .ok()
+            .map(|relative_path| relative_path.into_arc())
+    }
+
     pub fn contains_entry(&self, entry_id: ProjectEntryId) -> bool {
         self.entries_by_id.get(&entry_id, ()).is_some()
     }
@@ -2338,6 +3681,61 @@ impl Snapshot {
         }
     }
 
+    /// Replays a batch of changes from [`Event::UpdatedEntries`] onto this snapshot,
+    /// reading the new state of added or updated entries from `new_snapshot`.
+    ///
+    /// This lets consumers that maintain their own copy of a worktree's entries keep it
+    /// in sync without rebuilding it on every event.
+    pub fn apply_entries_change(
+        &mut self,
+        changes: &[(Arc<RelPath>, ProjectEntryId, PathChange)],
+        new_snapshot: &Snapshot,
+    ) {
+        for (path, _, change) in changes {
+            match change {
+                PathChange::Removed => {
+                    if let Some(removed_entry) =
+                        self.entries_by_path.remove(&PathKey(path.clone()), ())
+                    {
+                        self.entries_by_id.remove(&removed_entry.id, ());
+                    }
+                }
+                PathChange::Added
+                | PathChange::Updated
+                | PathChange::AddedOrUpdated
+                | PathChange::Loaded => {
+                    let Some(entry) = new_snapshot.entry_for_path(path) else {
+                        continue;
+                    };
+                    let old_path_entry = self.entries_by_id.insert_or_replace(
+                        PathEntry {
+                            id: entry.id,
+                            path: entry.path.clone(),
+                            is_ignored: entry.is_ignored,
+                            scan_id: new_snapshot.scan_id,
+                        },
+                        (),
+                    );
+                    if let Some(old_path_entry) = old_path_entry
+                        && old_path_entry.path != entry.path
+                    {
+                        self.entries_by_path
+                            .remove(&PathKey(old_path_entry.path), ());
+                    }
+                    let old_entry = self.entries_by_path.insert_or_replace(entry.clone(), ());
+                    if let Some(old_entry) = old_entry
+                        && old_entry.id != entry.id
+                    {
+                        self.entries_by_id.remove(&old_entry.id, ());
+                    }
+                }
+            }
+        }
+
+        self.scan_id = new_snapshot.scan_id;
+        self.completed_scan_id = new_snapshot.completed_scan_id;
+    }
+
     pub fn entry_count(&self) -> usize {
         self.entries_by_path.summary().count
     }
@@ -2364,6 +3762,30 @@ impl Snapshot {
         self.entries_by_path.summary().non_ignored_file_count
     }
 
+    /// Returns whether the worktree root is a directory with nothing beneath it.
+    pub fn is_empty(&self) -> bool {
+        self.entry_count() <= 1 && self.root_entry().is_none_or(Entry::is_dir)
+    }
+
+    /// Returns the deepest directory containing every file in the worktree, which is the
+    /// root itself when the worktree has no files.
+    pub fn deepest_common_directory(&self) -> Arc<RelPath> {
+        let mut files = self.files(true, 0);
+        let Some(first_file) = files.next() else {
+            return RelPath::empty().into();
+        };
+        let mut common_directory = first_file.path.parent().unwrap_or(RelPath::empty());
+        for file in files {
+            if common_directory.is_empty() {
+                break;
+            }
+            while !file.path.starts_with(common_directory) {
+                common_directory = common_directory.parent().unwrap_or(RelPath::empty());
+            }
+        }
+        common_directory.into()
+    }
+
     fn traverse_from_offset(
         &self,
         include_files: bool,
@@ -2412,6 +3834,100 @@ impl Snapshot {
         self.traverse_from_offset(true, true, include_ignored, start)
     }
 
+    /// Yields entries in chunks of at most `chunk_size`, so that views can page through a
+    /// large worktree without collecting every entry up front.
+    pub fn entries_batched(
+        &self,
+        include_ignored: bool,
+        chunk_size: usize,
+    ) -> impl Iterator<Item = Vec<Entry>> + '_ {
+        let chunk_size = chunk_size.max(1);
+        let mut traversal = self.entries(include_ignored, 0);
+        std::iter::from_fn(move || {
+            let chunk = traversal
+                .by_ref()
+                .take(chunk_size)
+                .cloned()
+                .collect::<Vec<_>>();
+            (!chunk.is_empty()).then_some(chunk)
+        })
+    }
+
+    /// Resumes a traversal from a cursor saved with [`Traversal::save_cursor`].
+    ///
+    /// The cursor may come from an older snapshot. If the entry it points at has since been
+    /// removed, the traversal resumes at the next entry in path order.
+    pub fn resume_traversal(&self, cursor: &SnapshotCursor) -> Traversal<'_> {
+        match &cursor.next_path {
+            Some(path) => self.traverse_from_path(
+                cursor.include_files,
+                cursor.include_dirs,
+                cursor.include_ignored,
+                path,
+            ),
+            None => self.traverse_from_offset(
+                cursor.include_files,
+                cursor.include_dirs,
+                cursor.include_ignored,
+                usize::MAX,
+            ),
+        }
+    }
+
+    /// Yields entries in the given order. Only [`EntrySortOrder::PathAsc`] is provided
+    /// natively by the entries tree; other orders buffer all entries before sorting them.
+    pub fn sorted_entries(
+        &self,
+        include_ignored: bool,
+        order: EntrySortOrder,
+    ) -> Box<dyn Iterator<Item = &Entry> + '_> {
+        let entries = self.entries(include_ignored, 0);
+        if order == EntrySortOrder::PathAsc {
+            return Box::new(entries);
+        }
+        let mut sorted_entries = entries.collect::<Vec<_>>();
+        // Sorts are stable, so ties keep their path order.
+        match order {
+            EntrySortOrder::PathAsc => {}
+            EntrySortOrder::DirsFirst => sorted_entries
+                .sort_by(|a, b| compare_rel_paths((&a.path, a.is_file()), (&b.path, b.is_file()))),
+            EntrySortOrder::MtimeDesc => sorted_entries
+                .sort_by_key(|entry| Reverse(entry.mtime.map(MTime::timestamp_for_user))),
+            EntrySortOrder::SizeDesc => sorted_entries.sort_by_key(|entry| Reverse(entry.size)),
+        }
+        Box::new(sorted_entries.into_iter())
+    }
+
+    /// Returns the entries matching `predicate`, e.g. large or recently modified files.
+    ///
+    /// Ignored entries are skipped by the underlying traversal unless `include_ignored` is set.
+    pub fn find_entries<'a>(
+        &'a self,
+        include_ignored: bool,
+        predicate: impl Fn(&Entry) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Entry> + 'a {
+        self.entries(include_ignored, 0)
+            .filter(move |entry| predicate(entry))
+    }
+
+    /// Counts the direct children of the directory at `path`, e.g. for folder rows showing
+    /// "(12 files, 3 folders)". Returns `None` if there's no directory at `path`.
+    pub fn dir_child_counts(&self, path: &RelPath) -> Option<DirCounts> {
+        self.entry_for_path(path).filter(|entry| entry.is_dir())?;
+        let mut counts = DirCounts::default();
+        for child in self.child_entries(path) {
+            if child.is_dir() {
+                counts.dirs += 1;
+            } else {
+                counts.files += 1;
+            }
+            if child.is_ignored {
+                counts.ignored += 1;
+            }
+        }
+        Some(counts)
+    }
+
     pub fn paths(&self) -> impl Iterator<Item = &RelPath> {
         self.entries_by_path
             .cursor::<()>(())
@@ -2472,6 +3988,13 @@ impl Snapshot {
         self.scan_id
     }
 
+    /// Whether every scan that has started on this snapshot has also completed. Together
+    /// with [`Self::scan_id`], this tells a consumer caching the snapshot whether its copy
+    /// is still authoritative.
+    pub fn is_scan_complete(&self) -> bool {
+        self.completed_scan_id >= self.scan_id
+    }
+
     pub fn entry_for_path(&self, path: &RelPath) -> Option<&Entry> {
         self.traverse_from_path(true, true, true, path)
             .entry()
@@ -2484,6 +4007,194 @@ impl Snapshot {
             })
     }
 
+    /// The entry for a path in the repository with the given working directory, or `None` if
+    /// the path lies outside of this worktree.
+    pub fn entry_for_repo_path(
+        &self,
+        work_directory: &WorkDirectory,
+        repo_path: &RepoPath,
+    ) -> Option<&Entry> {
+        self.entry_for_path(&work_directory.try_unrelativize(repo_path)?)
+    }
+
+    /// Whether there's an entry at `path`, optionally disregarding ignored entries.
+    ///
+    /// Unlike `entry_for_path(path).is_some()`, this seeks the entries tree directly instead
+    /// of setting up a traversal.
+    pub fn contains_path(&self, path: &RelPath, include_ignored: bool) -> bool {
+        let mut cursor = self.entries_by_path.cursor::<TraversalProgress>(());
+        cursor.seek(&TraversalTarget::path(path), Bias::Left);
+        cursor.item().is_some_and(|entry| {
+            entry.path.as_ref() == path && (include_ignored || !entry.is_ignored)
+        })
+    }
+
+    /// Returns the snapshot's entries without their [`ProjectEntryId`]s, which differ between
+    /// independently scanned worktrees even when the file system is the same.
+    pub fn entries_without_ids(&self, include_ignored: bool) -> Vec<EntrySansId<'_>> {
+        let mut entries = Vec::new();
+        for entry in self.entries_by_path.cursor::<()>(()) {
+            if include_ignored || !entry.is_ignored {
+                entries.push(EntrySansId {
+                    path: entry.path.as_ref(),
+                    inode: entry.inode,
+                    is_ignored: entry.is_ignored,
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.path.cmp(b.path));
+        entries
+    }
+
+    /// Whether both snapshots describe the same entries, ignoring their ids.
+    pub fn semantic_eq(&self, other: &Snapshot) -> bool {
+        self.entries_without_ids(true) == other.entries_without_ids(true)
+    }
+
+    /// Returns the `.gitignore` entry in the closest ancestor directory of `path`, i.e. the
+    /// innermost gitignore within the worktree whose rules apply to `path`.
+    pub fn nearest_gitignore(&self, path: &RelPath) -> Option<&Entry> {
+        let gitignore_name = RelPath::unix(GITIGNORE).unwrap();
+        path.ancestors()
+            .skip(1)
+            .find_map(|ancestor| self.entry_for_path(&ancestor.join(gitignore_name)))
+    }
+
+    /// Returns the deepest directory in the worktree that contains all of the given paths,
+    /// or `None` if `paths` is empty.
+    pub fn common_ancestor(&self, paths: &[&RelPath]) -> Option<Arc<RelPath>> {
+        let (first_path, other_paths) = paths.split_first()?;
+        first_path
+            .ancestors()
+            .filter(|ancestor| other_paths.iter().all(|path| path.starts_with(ancestor)))
+            .find_map(|ancestor| {
+                let entry = self.entry_for_path(ancestor)?;
+                entry.is_dir().then(|| entry.path.clone())
+            })
+    }
+
+    /// Yields the files whose name ends in `.{extension}`, in path order.
+    pub fn entries_with_extension<'a>(
+        &'a self,
+        extension: &'a str,
+        include_ignored: bool,
+    ) -> impl Iterator<Item = &'a Entry> + 'a {
+        self.files(include_ignored, 0).filter(move |entry| {
+            entry
+                .path
+                .file_name()
+                .and_then(|file_name| file_name.rsplit_once('.'))
+                .is_some_and(|(stem, entry_extension)| {
+                    !stem.is_empty() && entry_extension == extension
+                })
+        })
+    }
+
+    /// Yields the files and directories whose final path component is `file_name`, in path order.
+    pub fn entries_named<'a>(
+        &'a self,
+        file_name: &'a str,
+        include_ignored: bool,
+    ) -> impl Iterator<Item = &'a Entry> + 'a {
+        self.entries(include_ignored, 0)
+            .filter(move |entry| entry.path.file_name() == Some(file_name))
+    }
+
+    /// Visits entries in path order, letting `visit` skip a directory's descendants or stop
+    /// the walk early.
+    pub fn walk(&self, include_ignored: bool, mut visit: impl FnMut(&Entry) -> WalkAction) {
+        let mut traversal = self.entries(include_ignored, 0);
+        while let Some(entry) = traversal.entry() {
+            match visit(entry) {
+                WalkAction::Continue => traversal.advance(),
+                WalkAction::SkipSubtree => traversal.advance_to_sibling(),
+                WalkAction::Stop => break,
+            };
+        }
+    }
+
+    /// Returns the paths that were added, removed or updated in this snapshot relative to
+    /// `old_snapshot`, in path order.
+    pub fn diff(&self, old_snapshot: &Snapshot) -> Vec<(Arc<RelPath>, PathChange)> {
+        let mut changes = Vec::new();
+        let mut old_entries = old_snapshot.entries_by_path.iter().peekable();
+        let mut new_entries = self.entries_by_path.iter().peekable();
+        loop {
+            match (old_entries.peek(), new_entries.peek()) {
+                (Some(old_entry), Some(new_entry)) => {
+                    match Ord::cmp(&old_entry.path, &new_entry.path) {
+                        Ordering::Less => {
+                            changes.push((old_entry.path.clone(), PathChange::Removed));
+                            old_entries.next();
+                        }
+                        Ordering::Equal => {
+                            if old_entry != new_entry {
+                                changes.push((new_entry.path.clone(), PathChange::Updated));
+                            }
+                            old_entries.next();
+                            new_entries.next();
+                        }
+                        Ordering::Greater => {
+                            changes.push((new_entry.path.clone(), PathChange::Added));
+                            new_entries.next();
+                        }
+                    }
+                }
+                (Some(old_entry), None) => {
+                    changes.push((old_entry.path.clone(), PathChange::Removed));
+                    old_entries.next();
+                }
+                (None, Some(new_entry)) => {
+                    changes.push((new_entry.path.clone(), PathChange::Added));
+                    new_entries.next();
+                }
+                (None, None) => break,
+            }
+        }
+        changes
+    }
+
+    /// Yields the files whose modification time is later than `time`, in path order.
+    pub fn entries_modified_after(
+        &self,
+        time: SystemTime,
+        include_ignored: bool,
+    ) -> impl Iterator<Item = &Entry> {
+        self.files(include_ignored, 0).filter(move |entry| {
+            entry
+                .mtime
+                .is_some_and(|mtime| mtime.timestamp_for_user() > time)
+        })
+    }
+
+    /// Returns the `n` most recently modified files, newest first.
+    pub fn newest_entries(&self, n: usize, include_ignored: bool) -> Vec<&Entry> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let file_count = if include_ignored {
+            self.file_count()
+        } else {
+            self.visible_file_count()
+        };
+        // Keep only the newest `n` files seen so far, with the oldest of them on top.
+        let mut newest = BinaryHeap::with_capacity(n.min(file_count) + 1);
+        for entry in self.files(include_ignored, 0) {
+            let Some(mtime) = entry.mtime else {
+                continue;
+            };
+            newest.push(Reverse((mtime.timestamp_for_user(), entry.path.as_ref())));
+            if newest.len() > n {
+                newest.pop();
+            }
+        }
+        newest
+            .into_sorted_vec()
+            .into_iter()
+            .filter_map(|Reverse((_, path))| self.entry_for_path(path))
+            .collect()
+    }
+
     /// Resolves a path to an executable using the following heuristics:
     ///
     /// 1. If the path starts with `~`, it is expanded to the user's home directory.
@@ -2525,6 +4236,12 @@ impl Snapshot {
 }
 
 impl LocalSnapshot {
+    /// The number of git repositories found in this worktree, including a repository that
+    /// contains the worktree root. Changes to it are reported via [`Event::UpdatedGitRepositories`].
+    pub fn git_repositories_count(&self) -> usize {
+        self.git_repositories.len()
+    }
+
     fn local_repo_for_work_directory_path(&self, path: &RelPath) -> Option<&LocalRepositoryEntry> {
         self.git_repositories
             .iter()
@@ -2654,6 +4371,9 @@ impl LocalSnapshot {
         } else {
             IgnoreStack::none()
         };
+        if let Some(additional_ignores) = self.additional_ignores.clone() {
+            ignore_stack = ignore_stack.append(IgnoreKind::Additional, additional_ignores);
+        }
 
         if let Some((repo_exclude, _)) = repo_root
             .as_ref()
@@ -2765,18 +4485,6 @@ impl LocalSnapshot {
             }
         }
     }
-
-    #[cfg(test)]
-    pub fn entries_without_ids(&self, include_ignored: bool) -> Vec<(&RelPath, u64, bool)> {
-        let mut paths = Vec::new();
-        for entry in self.entries_by_path.cursor::<()>(()) {
-            if include_ignored || !entry.is_ignored {
-                paths.push((entry.path.as_ref(), entry.inode, entry.is_ignored));
-            }
-        }
-        paths.sort_by(|a, b| a.0.cmp(b.0));
-        paths
-    }
 }
 
 impl BackgroundScannerState {
@@ -2834,11 +4542,17 @@ impl BackgroundScannerState {
             // Conditionally reuse the old entry's id:
             // * if the mtime is the same, the file was probably been renamed.
             // * if the path is the same, the file may just have been updated
+            //
+            // A file replaced by a directory, or vice versa, is a different entry.
             if let Some(removed_entry) = self.removed_entries.remove(&entry.inode) {
-                if removed_entry.mtime == Some(mtime) || removed_entry.path == entry.path {
+                if removed_entry.is_dir() == entry.is_dir()
+                    && (removed_entry.mtime == Some(mtime) || removed_entry.path == entry.path)
+                {
                     entry.id = removed_entry.id;
                 }
-            } else if let Some(existing_entry) = self.snapshot.entry_for_path(&entry.path) {
+            } else if let Some(existing_entry) = self.snapshot.entry_for_path(&entry.path)
+                && existing_entry.is_dir() == entry.is_dir()
+            {
                 entry.id = existing_entry.id;
             }
         }
@@ -2857,11 +4571,17 @@ impl BackgroundScannerState {
         // Conditionally reuse the old entry's id:
         // * if the mtime is the same, the file was probably been renamed.
         // * if the path is the same, the file may just have been updated
+        //
+        // A file replaced by a directory, or vice versa, is a different entry.
         if let Some(removed_entry) = self.removed_entries.remove(&metadata.inode) {
-            if removed_entry.mtime == Some(metadata.mtime) || *removed_entry.path == *path {
+            if removed_entry.is_dir() == metadata.is_dir
+                && (removed_entry.mtime == Some(metadata.mtime) || *removed_entry.path == *path)
+            {
                 return removed_entry.id;
             }
-        } else if let Some(existing_entry) = self.snapshot.entry_for_path(path) {
+        } else if let Some(existing_entry) = self.snapshot.entry_for_path(path)
+            && existing_entry.is_dir() == metadata.is_dir
+        {
             return existing_entry.id;
         }
         ProjectEntryId::new(next_entry_id)
@@ -3035,6 +4755,23 @@ impl BackgroundScannerState {
 
         let dot_git_abs_path = Arc::from(self.snapshot.absolutize(&dot_git_path).as_ref());
 
+        let work_dir_path = match configured_work_tree(&dot_git_abs_path, fs).await {
+            Some(work_tree_abs_path) => match work_tree_abs_path
+                .strip_prefix(self.snapshot.abs_path())
+                .ok()
+                .and_then(|path| RelPath::new(path, PathStyle::local()).ok())
+            {
+                Some(relative_path) => relative_path.into_arc(),
+                None => {
+                    log::debug!(
+                        "ignoring core.worktree {work_tree_abs_path:?} outside of the worktree, `.git` path in the worktree: {dot_git_path:?}"
+                    );
+                    work_dir_path
+                }
+            },
+            None => work_dir_path,
+        };
+
         self.insert_git_repository_for_path(
             WorkDirectory::InProject {
                 relative_path: work_dir_path,
@@ -3080,6 +4817,15 @@ impl BackgroundScannerState {
                 .context("failed to add repository directory to watcher")
                 .log_err();
         }
+        let head_log_abs_path = repository_dir_abs_path.join(HEAD_LOG);
+        if let Some(head_log_dir_abs_path) = head_log_abs_path.parent()
+            && fs.is_dir(head_log_dir_abs_path).await
+        {
+            watcher
+                .add(head_log_dir_abs_path)
+                .context("failed to add HEAD reflog directory to watcher")
+                .log_err();
+        }
 
         let work_directory_id = work_dir_entry.id;
 
@@ -3088,6 +4834,9 @@ impl BackgroundScannerState {
             work_directory,
             work_directory_abs_path: work_directory_abs_path.as_path().into(),
             git_dir_scan_id: 0,
+            head_log_scan_id: 0,
+            config_scan_id: 0,
+            refs_scan_id: 0,
             dot_git_abs_path,
             common_dir_abs_path,
             repository_dir_abs_path,
@@ -3388,6 +5137,46 @@ pub struct Entry {
     pub size: u64,
     pub char_bag: CharBag,
     pub is_fifo: bool,
+
+    // The fields below are only known to the local worktree. They aren't sent to remote
+    // peers, whose entries leave them at their defaults.
+    /// Whether this entry is a symlink whose target doesn't exist.
+    ///
+    /// Broken symlinks are kept in the snapshot as files, with their target
+    /// recorded in `symlink_target`.
+    pub is_broken_symlink: bool,
+
+    /// The path a broken symlink points to, as read from the link itself.
+    ///
+    /// Symlinks that resolve record their target in `canonical_path` instead.
+    pub symlink_target: Option<Arc<Path>>,
+
+    /// Whether this entry belongs to a worktree whose root is itself a `.git`
+    /// directory, and `treat_git_dir_root_as_repo_internals` is enabled.
+    ///
+    /// Such entries are still scanned, but consumers may choose to present them
+    /// differently from regular project files.
+    pub is_git_internal: bool,
+
+    /// Whether this file holds binary content, or `None` if that is not known yet.
+    ///
+    /// Files with well-known binary extensions are flagged during the scan. Other files
+    /// are only sniffed when `sniff_binary_files_during_scan` is enabled, or on demand via
+    /// [`LocalWorktree::sniff_binary`].
+    pub is_binary: Option<bool>,
+
+    /// Whether this file is a Git LFS pointer, standing in for content that hasn't been
+    /// downloaded, or `None` if that is not known yet.
+    ///
+    /// Files too large or too small to be pointers are flagged during the scan. Other files
+    /// are only read when `detect_lfs_pointers_during_scan` is enabled, or on demand via
+    /// [`LocalWorktree::check_lfs_pointer`].
+    pub is_lfs_pointer: Option<bool>,
+
+    /// A hash of this file's content, or `None` if `hash_file_contents_during_scan` is
+    /// disabled. It makes rewrites that keep the file's size and mtime show up as
+    /// [`PathChange::Updated`].
+    pub content_hash: Option<u64>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -3428,9 +5217,56 @@ pub struct UpdatedGitRepository {
     pub dot_git_abs_path: Option<Arc<Path>>,
     pub repository_dir_abs_path: Option<Arc<Path>>,
     pub common_dir_abs_path: Option<Arc<Path>>,
+    /// Whether the repository's `HEAD` moved, as observed through its `logs/HEAD` reflog.
+    ///
+    /// This is set when a commit, checkout or reset happened, and is `false` for changes
+    /// that only touched other git state such as the index. It is always `false` for
+    /// repositories that were added or removed.
+    pub head_moved: bool,
+    /// Whether the repository's config, exclude file, or one of its gitignores changed.
+    ///
+    /// It is always `false` for repositories that were added or removed.
+    pub config_changed: bool,
+    /// Whether one of the repository's refs, or its `packed-refs` file, changed.
+    ///
+    /// It is always `false` for repositories that were added or removed.
+    pub refs_changed: bool,
 }
 
 pub type UpdatedEntriesSet = Arc<[(Arc<RelPath>, ProjectEntryId, PathChange)]>;
+
+/// Merges the changes reported for the same entry over several snapshot updates, and sorts
+/// them by path like the changes of a single update. Changes to different entries at the same
+/// path, such as the removal of a replaced entry, are kept separately.
+fn coalesce_entry_changes(
+    mut changes: Vec<(Arc<RelPath>, ProjectEntryId, PathChange)>,
+) -> UpdatedEntriesSet {
+    changes.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+    let mut coalesced: Vec<(Arc<RelPath>, ProjectEntryId, PathChange)> = Vec::new();
+    for (path, entry_id, change) in changes {
+        let same_path_start = coalesced.len()
+            - coalesced
+                .iter()
+                .rev()
+                .take_while(|(last_path, _, _)| *last_path == path)
+                .count();
+        let same_entry_ix = coalesced[same_path_start..]
+            .iter()
+            .position(|(_, last_entry_id, _)| *last_entry_id == entry_id)
+            .map(|ix| same_path_start + ix);
+        match same_entry_ix {
+            Some(ix) => match (coalesced[ix].2, change) {
+                (PathChange::Added, PathChange::Removed) => {
+                    coalesced.remove(ix);
+                }
+                (PathChange::Added, _) => {}
+                (_, change) => coalesced[ix].2 = change,
+            },
+            None => coalesced.push((path, entry_id, change)),
+        }
+    }
+    coalesced.into()
+}
 pub type UpdatedGitRepositoriesSet = Arc<[UpdatedGitRepository]>;
 
 #[derive(Clone, Debug)]
@@ -3547,6 +5383,12 @@ impl Entry {
             is_private: false,
             char_bag,
             is_fifo: metadata.is_fifo,
+            is_broken_symlink: false,
+            symlink_target: None,
+            is_git_internal: false,
+            is_binary: None,
+            is_lfs_pointer: None,
+            content_hash: None,
         }
     }
 
@@ -3561,6 +5403,17 @@ impl Entry {
     pub fn is_file(&self) -> bool {
         self.kind.is_file()
     }
+
+    /// Whether this is the worktree's root entry, whose path is empty.
+    pub fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Returns the entry's path relative to the worktree root, separated by `/` on every
+    /// platform, as git and language servers expect.
+    pub fn relative_path_str(&self) -> String {
+        self.path.as_unix_str().to_string()
+    }
 }
 
 impl EntryKind {
@@ -3730,6 +5583,8 @@ struct BackgroundScanner {
     scan_requests_rx: channel::Receiver<ScanRequest>,
     path_prefixes_to_scan_rx: channel::Receiver<PathPrefixScanRequest>,
     next_entry_id: Arc<AtomicUsize>,
+    rename_progress_trackers: Arc<Mutex<Vec<RenameProgressTracker>>>,
+    loaded_file_cache: Arc<Mutex<LoadedFileCache>>,
     phase: BackgroundScannerPhase,
     watcher: Arc<dyn Watcher>,
     settings: WorktreeSettings,
@@ -3864,6 +5719,9 @@ impl BackgroundScanner {
         // Perform an initial scan of the directory.
         drop(scan_job_tx);
         self.scan_dirs(true, scan_job_rx).await;
+        if self.settings.auto_expand_single_root_repo {
+            self.expand_single_root_repo().await;
+        }
         {
             let mut state = self.state.lock().await;
             state.snapshot.completed_scan_id = state.snapshot.scan_id;
@@ -3876,9 +5734,7 @@ impl BackgroundScanner {
         // have the previous state loaded yet.
         self.phase = BackgroundScannerPhase::EventsReceivedDuringInitialScan;
         if let Poll::Ready(Some(mut paths)) = futures::poll!(fs_events_rx.next()) {
-            while let Poll::Ready(Some(more_paths)) = futures::poll!(fs_events_rx.next()) {
-                paths.extend(more_paths);
-            }
+            drain_pending_fs_events(&mut fs_events_rx, &mut paths).await;
             self.process_events(
                 paths
                     .into_iter()
@@ -3901,6 +5757,13 @@ impl BackgroundScanner {
                 // these before handling changes reported by the filesystem.
                 request = self.next_scan_request().fuse() => {
                     let Ok(request) = request else { break };
+                    if request.process_pending_fs_events {
+                        let mut paths = Vec::new();
+                        drain_pending_fs_events(&mut fs_events_rx, &mut paths).await;
+                        if !paths.is_empty() {
+                            self.process_events(paths.into_iter().filter(|e| e.kind.is_some()).map(Into::into).collect()).await;
+                        }
+                    }
                     if !self.process_scan_request(request, false).await {
                         return;
                     }
@@ -3928,9 +5791,7 @@ impl BackgroundScanner {
 
                 paths = fs_events_rx.next().fuse() => {
                     let Some(mut paths) = paths else { break };
-                    while let Poll::Ready(Some(more_paths)) = futures::poll!(fs_events_rx.next()) {
-                        paths.extend(more_paths);
-                    }
+                    drain_pending_fs_events(&mut fs_events_rx, &mut paths).await;
                     self.process_events(paths.into_iter().filter(|e| e.kind.is_some()).map(Into::into).collect()).await;
                 }
 
@@ -3973,13 +5834,27 @@ impl BackgroundScanner {
             })
             .collect::<Vec<_>>();
 
-        {
+        let dot_git_abs_paths = {
             let mut state = self.state.lock().await;
             let is_idle = state.snapshot.completed_scan_id == state.snapshot.scan_id;
             state.snapshot.scan_id += 1;
             if is_idle {
                 state.snapshot.completed_scan_id = state.snapshot.scan_id;
             }
+            if request.refresh_git_repositories {
+                state
+                    .snapshot
+                    .git_repositories
+                    .values()
+                    .map(|repository| repository.repository_dir_abs_path.to_path_buf())
+                    .collect()
+            } else {
+                Vec::new()
+            }
+        };
+        if !dot_git_abs_paths.is_empty() {
+            self.update_git_repositories(dot_git_abs_paths, &[], &[])
+                .await;
         }
 
         self.reload_entries_for_paths(
@@ -3988,12 +5863,49 @@ impl BackgroundScanner {
             &request.relative_paths,
             abs_paths,
             None,
+            request.sniff_binary || self.settings.sniff_binary_files_during_scan,
+            request.detect_lfs_pointers || self.settings.detect_lfs_pointers_during_scan,
         )
         .await;
 
+        if request.reload_gitignores {
+            self.reload_ignore_statuses().await;
+        }
+
         self.send_status_update(scanning, request.done).await
     }
 
+    /// Applies any gitignores that were rebuilt while reloading entries. Unlike
+    /// [`Self::update_ignore_statuses_for_paths`], this runs on the current task, as it is
+    /// invoked while handling a scan request.
+    async fn reload_ignore_statuses(&self) {
+        let ignores_to_update = self.ignores_needing_update().await;
+        let ignores_to_update = self.order_ignores(ignores_to_update).await;
+        let snapshot = self.state.lock().await.snapshot.clone();
+        let (scan_job_tx, scan_job_rx) = channel::unbounded();
+        let (ignore_queue_tx, ignore_queue_rx) = channel::unbounded();
+        for (parent_abs_path, ignore_stack) in ignores_to_update {
+            ignore_queue_tx
+                .send(UpdateIgnoreStatusJob {
+                    abs_path: parent_abs_path,
+                    ignore_stack,
+                    ignore_queue: ignore_queue_tx.clone(),
+                    scan_queue: scan_job_tx.clone(),
+                })
+                .await
+                .log_err();
+        }
+        drop(ignore_queue_tx);
+        drop(scan_job_tx);
+
+        while let Ok(job) = ignore_queue_rx.recv().await {
+            self.update_ignore_status(job, &snapshot).await;
+        }
+        while let Ok(job) = scan_job_rx.recv().await {
+            self.scan_dir(&job).await.log_err();
+        }
+    }
+
     async fn process_events(&self, mut abs_paths: Vec<PathBuf>) {
         log::trace!("process events: {abs_paths:?}");
         let root_path = self.state.lock().await.snapshot.abs_path.clone();
@@ -4041,6 +5953,9 @@ impl BackgroundScanner {
 
         let mut relative_paths = Vec::with_capacity(abs_paths.len());
         let mut dot_git_abs_paths = Vec::new();
+        let mut head_moved_dot_git_abs_paths = Vec::new();
+        let mut config_changed_dot_git_abs_paths = Vec::new();
+        let mut refs_changed_dot_git_abs_paths = Vec::new();
         let mut work_dirs_needing_exclude_update = Vec::new();
         abs_paths.sort_unstable();
         abs_paths.dedup_by(|a, b| a.starts_with(b));
@@ -4076,6 +5991,20 @@ impl BackgroundScanner {
                     }
                 }
 
+                // A `.git` file may point to a git directory anywhere on disk, which doesn't
+                // have to look like a git directory on its own.
+                if dot_git_paths.is_none() {
+                    dot_git_paths = snapshot.git_repositories.values().find_map(|repo| {
+                        [&repo.repository_dir_abs_path, &repo.common_dir_abs_path]
+                            .into_iter()
+                            .find_map(|git_dir_abs_path| {
+                                let path_in_git_dir =
+                                    abs_path.as_path().strip_prefix(git_dir_abs_path).ok()?;
+                                Some((git_dir_abs_path.to_path_buf(), path_in_git_dir.to_owned()))
+                            })
+                    });
+                }
+
                 if let Some((dot_git_abs_path, path_in_git_dir)) = dot_git_paths {
                     if skipped_files_in_dot_git
                         .iter()
@@ -4092,6 +6021,23 @@ impl BackgroundScanner {
                     }
 
                     is_git_related = true;
+                    if path_in_git_dir.as_path() == Path::new(HEAD_LOG)
+                        && !head_moved_dot_git_abs_paths.contains(&dot_git_abs_path)
+                    {
+                        head_moved_dot_git_abs_paths.push(dot_git_abs_path.clone());
+                    }
+                    if (path_in_git_dir.as_path() == Path::new(REPO_CONFIG)
+                        || path_in_git_dir.as_path() == Path::new(REPO_EXCLUDE))
+                        && !config_changed_dot_git_abs_paths.contains(&dot_git_abs_path)
+                    {
+                        config_changed_dot_git_abs_paths.push(dot_git_abs_path.clone());
+                    }
+                    if (path_in_git_dir.starts_with(REFS_DIR)
+                        || path_in_git_dir.as_path() == Path::new(PACKED_REFS))
+                        && !refs_changed_dot_git_abs_paths.contains(&dot_git_abs_path)
+                    {
+                        refs_changed_dot_git_abs_paths.push(dot_git_abs_path.clone());
+                    }
                     if !dot_git_abs_paths.contains(&dot_git_abs_path) {
                         dot_git_abs_paths.push(dot_git_abs_path);
                     }
@@ -4138,6 +6084,15 @@ impl BackgroundScanner {
                         }) {
                             dot_git_abs_paths.push(repo.common_dir_abs_path.to_path_buf());
                         }
+                        if !config_changed_dot_git_abs_paths
+                            .iter()
+                            .any(|dot_git_abs_path| {
+                                dot_git_abs_path == repo.common_dir_abs_path.as_ref()
+                            })
+                        {
+                            config_changed_dot_git_abs_paths
+                                .push(repo.common_dir_abs_path.to_path_buf());
+                        }
                     }
                 }
 
@@ -4195,11 +6150,19 @@ impl BackgroundScanner {
             &relative_paths,
             abs_paths,
             Some(scan_job_tx.clone()),
+            self.settings.sniff_binary_files_during_scan,
+            self.settings.detect_lfs_pointers_during_scan,
         )
         .await;
 
         let affected_repo_roots = if !dot_git_abs_paths.is_empty() {
-            self.update_git_repositories(dot_git_abs_paths).await
+            self.update_git_repositories(
+                dot_git_abs_paths,
+                &head_moved_dot_git_abs_paths,
+                &config_changed_dot_git_abs_paths,
+                &refs_changed_dot_git_abs_paths,
+            )
+            .await
         } else {
             Vec::new()
         };
@@ -4220,6 +6183,14 @@ impl BackgroundScanner {
             for (_, entry) in mem::take(&mut state.removed_entries) {
                 state.scanned_dirs.remove(&entry.id);
             }
+            // Renamed directories that have been scanned by now are fully loaded.
+            self.rename_progress_trackers.lock().retain(|tracker| {
+                !tracker.loaded_count_tx.is_closed()
+                    && state
+                        .snapshot
+                        .entry_for_path(&tracker.path)
+                        .is_none_or(|entry| entry.kind == EntryKind::PendingDir)
+            });
         }
         self.send_status_update(false, SmallVec::new()).await;
     }
@@ -4250,6 +6221,31 @@ impl BackgroundScanner {
         self.send_status_update(false, SmallVec::new()).await;
     }
 
+    /// Loads the top-level directories that the initial scan deferred, if the worktree contains
+    /// exactly one git repository and that repository is rooted at the worktree root.
+    async fn expand_single_root_repo(&self) {
+        let top_level_dirs = {
+            let state = self.state.lock().await;
+            let snapshot = &state.snapshot;
+            let is_single_root_repo = snapshot.git_repositories_count() == 1
+                && snapshot.git_repositories.values().all(|repo| {
+                    matches!(
+                        &repo.work_directory,
+                        WorkDirectory::InProject { relative_path } if relative_path.is_empty()
+                    )
+                });
+            if !is_single_root_repo {
+                return;
+            }
+            snapshot
+                .child_entries(RelPath::empty())
+                .filter(|entry| entry.kind == EntryKind::UnloadedDir && !entry.is_external)
+                .map(|entry| entry.path.clone())
+                .collect::<Vec<_>>()
+        };
+        self.forcibly_load_paths(&top_level_dirs).await;
+    }
+
     async fn forcibly_load_paths(&self, paths: &[Arc<RelPath>]) -> bool {
         let (scan_job_tx, scan_job_rx) = channel::unbounded();
         {
@@ -4299,7 +6295,7 @@ impl BackgroundScanner {
         let progress_update_count = AtomicUsize::new(0);
         self.executor
             .scoped_priority(Priority::Low, |scope| {
-                for _ in 0..self.executor.num_cpus() {
+                for _ in 0..self.scan_concurrency() {
                     scope.spawn(async {
                         let mut last_progress_update_count = 0;
                         let progress_update_timer = self.progress_timer(enable_progress_updates).fuse();
@@ -4353,6 +6349,14 @@ impl BackgroundScanner {
             .await;
     }
 
+    /// The number of workers reading directories, each of which issues one `read_dir` at a time.
+    fn scan_concurrency(&self) -> usize {
+        self.settings
+            .scan_concurrency
+            .unwrap_or_else(|| self.executor.num_cpus())
+            .max(1)
+    }
+
     async fn send_status_update(
         &self,
         scanning: bool,
@@ -4383,6 +6387,32 @@ impl BackgroundScanner {
             .is_ok()
     }
 
+    async fn read_dir(
+        &self,
+        abs_path: &Path,
+    ) -> Result<Pin<Box<dyn Send + Stream<Item = Result<PathBuf>>>>> {
+        let mut retries = 0;
+        loop {
+            match self.fs.read_dir(abs_path).await {
+                Err(error)
+                    if self.settings.on_scan_permission_error
+                        == ScanPermissionErrorSetting::Retry
+                        && is_permission_error(&error) =>
+                {
+                    if retries == SCAN_PERMISSION_ERROR_RETRIES {
+                        log::error!(
+                            "giving up reading directory {abs_path:?} after {retries} retries: {error:#}"
+                        );
+                        return Err(error);
+                    }
+                    retries += 1;
+                    self.executor.timer(SCAN_PERMISSION_ERROR_RETRY_DELAY).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
     async fn scan_dir(&self, job: &ScanJob) -> Result<()> {
         let root_abs_path;
         let root_char_bag;
@@ -4404,7 +6434,6 @@ impl BackgroundScanner {
         let mut new_entries: Vec<Entry> = Vec::new();
         let mut new_jobs: Vec<Option<ScanJob>> = Vec::new();
         let mut child_paths = self
-            .fs
             .read_dir(&job.abs_path)
             .await?
             .filter_map(|entry| async {
@@ -4493,11 +6522,21 @@ impl BackgroundScanner {
                 child_entry.is_external = true;
             } else if child_metadata.is_symlink {
                 let canonical_path = match self.fs.canonicalize(&child_abs_path).await {
-                    Ok(path) => path,
-                    Err(err) => {
-                        log::error!("error reading target of symlink {child_abs_path:?}: {err:#}",);
-                        continue;
-                    }
+                    Ok(path) => Some(path),
+                    Err(err) => match self.fs.read_link(&child_abs_path).await {
+                        Ok(target) => {
+                            log::debug!("symlink {child_abs_path:?} points to missing {target:?}");
+                            child_entry.is_broken_symlink = true;
+                            child_entry.symlink_target = Some(target.into());
+                            None
+                        }
+                        Err(_) => {
+                            log::error!(
+                                "error reading target of symlink {child_abs_path:?}: {err:#}",
+                            );
+                            continue;
+                        }
+                    },
                 };
 
                 // lazily canonicalize the root path in order to determine if
@@ -4513,11 +6552,13 @@ impl BackgroundScanner {
                     },
                 };
 
-                if !canonical_path.starts_with(root_canonical_path) {
-                    child_entry.is_external = true;
-                }
+                if let Some(canonical_path) = canonical_path {
+                    if !canonical_path.starts_with(root_canonical_path) {
+                        child_entry.is_external = true;
+                    }
 
-                child_entry.canonical_path = Some(canonical_path.into());
+                    child_entry.canonical_path = Some(canonical_path.into());
+                }
             }
 
             if child_entry.is_dir() {
@@ -4546,9 +6587,28 @@ impl BackgroundScanner {
                     }));
                 }
             } else {
-                child_entry.is_ignored = ignore_stack.is_abs_path_ignored(&child_abs_path, false);
+                child_entry.is_ignored = ignore_stack.is_abs_path_ignored(&child_abs_path, false)
+                    || self.settings.is_file_too_large_to_scan(child_entry.size);
                 child_entry.is_always_included =
                     self.settings.is_path_always_included(&child_path, false);
+                if !child_entry.is_broken_symlink {
+                    child_entry.is_binary = self
+                        .detect_binary(
+                            &child_abs_path,
+                            self.settings.sniff_binary_files_during_scan,
+                        )
+                        .await;
+                    child_entry.is_lfs_pointer = self
+                        .detect_lfs_pointer(
+                            &child_abs_path,
+                            child_entry.size,
+                            self.settings.detect_lfs_pointers_during_scan,
+                        )
+                        .await;
+                    child_entry.content_hash = self
+                        .hash_file_content(&child_abs_path, child_entry.size)
+                        .await;
+                }
             }
 
             {
@@ -4563,6 +6623,7 @@ impl BackgroundScanner {
                     log::debug!("detected hidden file: {relative_path:?}");
                     child_entry.is_hidden = true;
                 }
+                child_entry.is_git_internal = self.is_git_internal_root(&root_abs_path);
             }
 
             new_entries.push(child_entry);
@@ -4591,8 +6652,16 @@ impl BackgroundScanner {
             }
         }
 
+        let new_entry_count = new_entries.len();
         state.populate_dir(job.path.clone(), new_entries, new_ignore);
         self.watcher.add(job.abs_path.as_ref()).log_err();
+        self.rename_progress_trackers.lock().retain(|tracker| {
+            !job.path.starts_with(&tracker.path)
+                || tracker
+                    .loaded_count_tx
+                    .unbounded_send(new_entry_count)
+                    .is_ok()
+        });
 
         for new_job in new_jobs.into_iter().flatten() {
             job.scan_queue
@@ -4611,7 +6680,18 @@ impl BackgroundScanner {
         relative_paths: &[Arc<RelPath>],
         abs_paths: Vec<PathBuf>,
         scan_queue_tx: Option<Sender<ScanJob>>,
+        sniff_binary: bool,
+        detect_lfs_pointers: bool,
     ) {
+        // Whatever changed on disk, don't serve these paths from the loaded file cache anymore,
+        // even if their mtime and size look unchanged.
+        {
+            let mut loaded_file_cache = self.loaded_file_cache.lock();
+            for path in relative_paths {
+                loaded_file_cache.invalidate(path);
+            }
+        }
+
         // grab metadata for all requested paths
         let metadata = futures::future::join_all(
             abs_paths
@@ -4619,7 +6699,14 @@ impl BackgroundScanner {
                 .map(|abs_path| async move {
                     let metadata = self.fs.metadata(abs_path).await?;
                     if let Some(metadata) = metadata {
-                        let canonical_path = self.fs.canonicalize(abs_path).await?;
+                        let (canonical_path, symlink_target) =
+                            match self.fs.canonicalize(abs_path).await {
+                                Ok(canonical_path) => (Some(canonical_path), None),
+                                Err(_) if metadata.is_symlink => {
+                                    (None, Some(self.fs.read_link(abs_path).await?))
+                                }
+                                Err(err) => return Err(err),
+                            };
 
                         // If we're on a case-insensitive filesystem (default on macOS), we want
                         // to only ignore metadata for non-symlink files if their absolute-path matches
@@ -4627,7 +6714,10 @@ impl BackgroundScanner {
                         // Because if not, this might be a case-only-renaming (`mv test.txt TEST.TXT`)
                         // and we want to ignore the metadata for the old path (`test.txt`) so it's
                         // treated as removed.
-                        if !self.fs_case_sensitive && !metadata.is_symlink {
+                        if let Some(canonical_path) = &canonical_path
+                            && !self.fs_case_sensitive
+                            && !metadata.is_symlink
+                        {
                             let canonical_file_name = canonical_path.file_name();
                             let file_name = abs_path.file_name();
                             if canonical_file_name != file_name {
@@ -4635,7 +6725,31 @@ impl BackgroundScanner {
                             }
                         }
 
-                        anyhow::Ok(Some((metadata, SanitizedPath::new_arc(&canonical_path))))
+                        let (is_binary, is_lfs_pointer, content_hash) =
+                            if metadata.is_dir || symlink_target.is_some() {
+                                (None, None, None)
+                            } else {
+                                (
+                                    self.detect_binary(abs_path, sniff_binary).await,
+                                    self.detect_lfs_pointer(
+                                        abs_path,
+                                        metadata.len,
+                                        detect_lfs_pointers,
+                                    )
+                                    .await,
+                                    self.hash_file_content(abs_path, metadata.len).await,
+                                )
+                            };
+
+                        anyhow::Ok(Some((
+                            metadata,
+                            canonical_path
+                                .map(|canonical_path| SanitizedPath::new_arc(&canonical_path)),
+                            symlink_target,
+                            is_binary,
+                            is_lfs_pointer,
+                            content_hash,
+                        )))
                     } else {
                         Ok(None)
                     }
@@ -4665,32 +6779,59 @@ impl BackgroundScanner {
         for (path, metadata) in relative_paths.iter().zip(metadata.into_iter()) {
             let abs_path: Arc<Path> = root_abs_path.join(path.as_std_path()).into();
             match metadata {
-                Ok(Some((metadata, canonical_path))) => {
+                Ok(Some((
+                    metadata,
+                    canonical_path,
+                    symlink_target,
+                    is_binary,
+                    is_lfs_pointer,
+                    content_hash,
+                ))) => {
                     let ignore_stack = state
                         .snapshot
                         .ignore_stack_for_abs_path(&abs_path, metadata.is_dir, self.fs.as_ref())
                         .await;
-                    let is_external = !canonical_path.starts_with(&root_canonical_path);
+                    let is_external = canonical_path.as_ref().is_some_and(|canonical_path| {
+                        !canonical_path.starts_with(&root_canonical_path)
+                    });
                     let entry_id = state.entry_id_for(self.next_entry_id.as_ref(), path, &metadata);
                     let mut fs_entry = Entry::new(
                         path.clone(),
                         &metadata,
                         entry_id,
                         state.snapshot.root_char_bag,
-                        if metadata.is_symlink {
-                            Some(canonical_path.as_path().to_path_buf().into())
-                        } else {
-                            None
-                        },
+                        canonical_path
+                            .as_ref()
+                            .filter(|_| metadata.is_symlink)
+                            .map(|canonical_path| canonical_path.as_path().to_path_buf().into()),
                     );
 
                     let is_dir = fs_entry.is_dir();
-                    fs_entry.is_ignored = ignore_stack.is_abs_path_ignored(&abs_path, is_dir);
+                    fs_entry.is_ignored = ignore_stack.is_abs_path_ignored(&abs_path, is_dir)
+                        || (!is_dir && self.settings.is_file_too_large_to_scan(fs_entry.size));
                     fs_entry.is_external = is_external;
+                    fs_entry.is_broken_symlink = symlink_target.is_some();
+                    fs_entry.symlink_target = symlink_target.map(Into::into);
                     fs_entry.is_private = self.is_path_private(path);
                     fs_entry.is_always_included =
                         self.settings.is_path_always_included(path, is_dir);
                     fs_entry.is_hidden = self.settings.is_path_hidden(path);
+                    fs_entry.is_git_internal = self.is_git_internal_root(&root_abs_path);
+                    fs_entry.is_binary = is_binary.or_else(|| {
+                        state
+                            .snapshot
+                            .entry_for_path(path)
+                            .filter(|existing_entry| existing_entry.mtime == fs_entry.mtime)
+                            .and_then(|existing_entry| existing_entry.is_binary)
+                    });
+                    fs_entry.is_lfs_pointer = is_lfs_pointer.or_else(|| {
+                        state
+                            .snapshot
+                            .entry_for_path(path)
+                            .filter(|existing_entry| existing_entry.mtime == fs_entry.mtime)
+                            .and_then(|existing_entry| existing_entry.is_lfs_pointer)
+                    });
+                    fs_entry.content_hash = content_hash;
 
                     if let (Some(scan_queue_tx), true) = (&scan_queue_tx, is_dir) {
                         if state.should_scan_directory(&fs_entry)
@@ -4972,7 +7113,8 @@ impl BackgroundScanner {
         for mut entry in snapshot.child_entries(&path).cloned() {
             let was_ignored = entry.is_ignored;
             let abs_path: Arc<Path> = snapshot.absolutize(&entry.path).into();
-            entry.is_ignored = ignore_stack.is_abs_path_ignored(&abs_path, entry.is_dir());
+            entry.is_ignored = ignore_stack.is_abs_path_ignored(&abs_path, entry.is_dir())
+                || (!entry.is_dir() && self.settings.is_file_too_large_to_scan(entry.size));
 
             if entry.is_dir() {
                 let child_ignore_stack = if entry.is_ignored {
@@ -5032,7 +7174,13 @@ impl BackgroundScanner {
         state.snapshot.entries_by_id.edit(entries_by_id_edits, ());
     }
 
-    async fn update_git_repositories(&self, dot_git_paths: Vec<PathBuf>) -> Vec<Arc<Path>> {
+    async fn update_git_repositories(
+        &self,
+        dot_git_paths: Vec<PathBuf>,
+        head_moved_dot_git_paths: &[PathBuf],
+        config_changed_dot_git_paths: &[PathBuf],
+        refs_changed_dot_git_paths: &[PathBuf],
+    ) -> Vec<Arc<Path>> {
         log::trace!("reloading repositories: {dot_git_paths:?}");
         let mut state = self.state.lock().await;
         let scan_id = state.snapshot.scan_id;
@@ -5075,10 +7223,22 @@ impl BackgroundScanner {
                         .await;
                 }
                 Some(local_repository) => {
+                    let head_moved = head_moved_dot_git_paths.contains(&dot_git_dir);
+                    let config_changed = config_changed_dot_git_paths.contains(&dot_git_dir);
+                    let refs_changed = refs_changed_dot_git_paths.contains(&dot_git_dir);
                     state.snapshot.git_repositories.update(
                         &local_repository.work_directory_id,
                         |entry| {
                             entry.git_dir_scan_id = scan_id;
+                            if head_moved {
+                                entry.head_log_scan_id = scan_id;
+                            }
+                            if config_changed {
+                                entry.config_scan_id = scan_id;
+                            }
+                            if refs_changed {
+                                entry.refs_scan_id = scan_id;
+                            }
                         },
                     );
                 }
@@ -5141,16 +7301,82 @@ impl BackgroundScanner {
         !self.share_private_files && self.settings.is_path_private(path)
     }
 
+    fn is_git_internal_root(&self, root_abs_path: &SanitizedPath) -> bool {
+        self.settings.treat_git_dir_root_as_repo_internals
+            && root_abs_path.as_path().file_name() == Some(OsStr::new(DOT_GIT))
+    }
+
+    async fn detect_binary(&self, abs_path: &Path, sniff_content: bool) -> Option<bool> {
+        if has_binary_extension(abs_path) {
+            Some(true)
+        } else if sniff_content {
+            sniff_binary_content(self.fs.as_ref(), abs_path)
+                .await
+                .log_err()
+        } else {
+            None
+        }
+    }
+
+    async fn detect_lfs_pointer(
+        &self,
+        abs_path: &Path,
+        size: u64,
+        read_content: bool,
+    ) -> Option<bool> {
+        if !may_be_lfs_pointer(size) {
+            Some(false)
+        } else if read_content {
+            is_lfs_pointer_file(self.fs.as_ref(), abs_path)
+                .await
+                .log_err()
+        } else {
+            None
+        }
+    }
+
+    async fn hash_file_content(&self, abs_path: &Path, size: u64) -> Option<u64> {
+        if !self.settings.hash_file_contents_during_scan
+            || size > self.settings.hash_file_contents_max_bytes
+        {
+            return None;
+        }
+        let mut file = self.fs.open_sync(abs_path).await.log_err()?;
+        hash_file_chunks(file.as_mut(), abs_path).log_err()
+    }
+
     async fn next_scan_request(&self) -> Result<ScanRequest> {
         let mut request = self.scan_requests_rx.recv().await?;
         while let Ok(next_request) = self.scan_requests_rx.try_recv() {
             request.relative_paths.extend(next_request.relative_paths);
+            request.sniff_binary |= next_request.sniff_binary;
+            request.detect_lfs_pointers |= next_request.detect_lfs_pointers;
+            request.refresh_git_repositories |= next_request.refresh_git_repositories;
+            request.reload_gitignores |= next_request.reload_gitignores;
+            request.process_pending_fs_events |= next_request.process_pending_fs_events;
             request.done.extend(next_request.done);
         }
         Ok(request)
     }
 }
 
+/// Appends the file system events that have already been received to `paths`, without
+/// waiting for more.
+async fn drain_pending_fs_events(
+    fs_events_rx: &mut (impl Stream<Item = Vec<PathEvent>> + Unpin),
+    paths: &mut Vec<PathEvent>,
+) {
+    while let Poll::Ready(Some(more_paths)) = futures::poll!(fs_events_rx.next()) {
+        paths.extend(more_paths);
+    }
+}
+
+fn is_permission_error(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<io::Error>()
+        .is_some_and(|error| error.kind() == io::ErrorKind::PermissionDenied)
+}
+
 async fn discover_ancestor_git_repo(
     fs: Arc<dyn Fs>,
     root_abs_path: &SanitizedPath,
@@ -5574,6 +7800,22 @@ impl Default for TraversalProgress<'_> {
     }
 }
 
+/// A bookmark into a [`Traversal`], holding the path of the next entry to yield.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotCursor {
+    next_path: Option<Arc<RelPath>>,
+    include_files: bool,
+    include_dirs: bool,
+    include_ignored: bool,
+}
+
+impl SnapshotCursor {
+    /// Whether the traversal this cursor was saved from had yielded every entry.
+    pub fn is_done(&self) -> bool {
+        self.next_path.is_none()
+    }
+}
+
 #[derive(Debug)]
 pub struct Traversal<'a> {
     snapshot: &'a Snapshot,
@@ -5649,6 +7891,17 @@ impl<'a> Traversal<'a> {
         self.cursor.item()
     }
 
+    /// Saves the traversal's position, so it can be resumed later with
+    /// [`Snapshot::resume_traversal`] without borrowing the snapshot in between.
+    pub fn save_cursor(&self) -> SnapshotCursor {
+        SnapshotCursor {
+            next_path: self.entry().map(|entry| entry.path.clone()),
+            include_files: self.include_files,
+            include_dirs: self.include_dirs,
+            include_ignored: self.include_ignored,
+        }
+    }
+
     pub fn snapshot(&self) -> &'a Snapshot {
         self.snapshot
     }
@@ -5762,6 +8015,78 @@ impl<'a> SeekTarget<'a, PathSummary<sum_tree::NoSummary>, TraversalProgress<'a>>
     }
 }
 
+/// What [`Snapshot::walk`] does after visiting an entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WalkAction {
+    /// Visit the next entry, descending into the visited entry if it's a directory.
+    Continue,
+    /// Skip the visited entry's descendants.
+    SkipSubtree,
+    /// End the walk.
+    Stop,
+}
+
+/// The order in which [`Snapshot::sorted_entries`] yields entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntrySortOrder {
+    /// By path, the order in which entries are stored.
+    PathAsc,
+    /// Directories before files within each directory, then by name.
+    DirsFirst,
+    /// Most recently modified first.
+    MtimeDesc,
+    /// Largest first.
+    SizeDesc,
+}
+
+/// The number of direct children of a directory, as returned by [`Snapshot::dir_child_counts`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DirCounts {
+    pub files: usize,
+    pub dirs: usize,
+    /// How many of the files and directories are ignored.
+    pub ignored: usize,
+}
+
+/// A rough count of a directory's contents, as returned by [`Worktree::estimate`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScanEstimate {
+    /// The number of files beneath the root, including ignored ones.
+    pub total: usize,
+    /// How many of those files are ignored.
+    pub ignored: usize,
+    /// The directories holding the most files, relative to the root, with their file counts.
+    pub largest_dirs: Vec<(PathBuf, usize)>,
+}
+
+impl ScanEstimate {
+    const LARGEST_DIRS_COUNT: usize = 10;
+}
+
+/// How the scanner classified a path, as returned by [`LocalWorktree::explain_path`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathExplanation {
+    pub decision: PathDecision,
+    /// The path's entry, holding its flags, if the path is in the snapshot.
+    pub entry: Option<Entry>,
+}
+
+/// The rule that decided a path's classification, listed in order of precedence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathDecision {
+    /// The path or one of its ancestors matches this `file_scan_exclusions` glob, so it's
+    /// never scanned, even if it also matches `file_scan_inclusions`.
+    Excluded { pattern: String },
+    /// The path matches this `file_scan_inclusions` glob, so it's scanned even if ignored.
+    AlwaysIncluded { pattern: String },
+    /// The path is ignored by a gitignore, an exclude file, or additional ignores.
+    Ignored,
+    /// The path is scanned like any other.
+    Included,
+    /// The path isn't in the snapshot, e.g. because it doesn't exist or its directory is unloaded.
+    NotFound,
+}
+
 pub struct ChildEntriesOptions {
     pub include_files: bool,
     pub include_dirs: bool,
@@ -5841,6 +8166,12 @@ impl TryFrom<(&CharBag, &PathMatcher, proto::Entry)> for Entry {
             is_private: false,
             char_bag,
             is_fifo: entry.is_fifo,
+            is_broken_symlink: false,
+            symlink_target: None,
+            is_git_internal: false,
+            is_binary: None,
+            is_lfs_pointer: None,
+            content_hash: None,
         })
     }
 }
@@ -5890,6 +8221,18 @@ fn parse_gitfile(content: &str) -> anyhow::Result<&Path> {
     Ok(Path::new(path.trim()))
 }
 
+/// Returns the working directory configured with `core.worktree` in the local config of the
+/// repository at `dot_git_abs_path`. A relative value is resolved against the repository's git
+/// directory.
+async fn configured_work_tree(dot_git_abs_path: &Arc<Path>, fs: &dyn Fs) -> Option<PathBuf> {
+    let work_tree = fs
+        .git_local_config_value(dot_git_abs_path, "core.worktree")
+        .await
+        .log_err()??;
+    let (repository_dir_abs_path, _) = discover_git_paths(dot_git_abs_path, fs).await;
+    normalize_lexically(&repository_dir_abs_path.join(work_tree)).ok()
+}
+
 async fn discover_git_paths(dot_git_abs_path: &Arc<Path>, fs: &dyn Fs) -> (Arc<Path>, Arc<Path>) {
     let mut repository_dir_abs_path = dot_git_abs_path.clone();
     let mut common_dir_abs_path = dot_git_abs_path.clone();
@@ -5936,17 +8279,86 @@ impl fs::Watcher for NullWatcher {
 
 const FILE_ANALYSIS_BYTES: usize = 1024;
 
-async fn decode_file_text(
-    fs: &dyn Fs,
-    abs_path: &Path,
-) -> Result<(String, &'static Encoding, bool)> {
+async fn load_scan_overrides(fs: &dyn Fs, root_abs_path: &Path) -> WorktreeScanOverrides {
+    let abs_path = root_abs_path.join(local_worktree_file_relative_path().as_std_path());
+    let Ok(content) = fs.load(&abs_path).await else {
+        return WorktreeScanOverrides::default();
+    };
+    settings::parse_json_with_comments(&content)
+        .with_context(|| format!("parsing {abs_path:?}"))
+        .log_err()
+        .unwrap_or_default()
+}
+
+const BINARY_FILE_EXTENSIONS: &[&str] = &[
+    "7z", "a", "bin", "bmp", "class", "dll", "dylib", "exe", "gif", "gz", "ico", "jar", "jpeg",
+    "jpg", "o", "pdf", "png", "so", "tar", "wasm", "webp", "zip",
+];
+
+fn has_binary_extension(abs_path: &Path) -> bool {
+    abs_path
+        .extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|extension| {
+            BINARY_FILE_EXTENSIONS
+                .iter()
+                .any(|binary_extension| extension.eq_ignore_ascii_case(binary_extension))
+        })
+}
+
+/// Git LFS pointer files are smaller than this many bytes, per the LFS specification.
+const LFS_POINTER_MAX_BYTES: u64 = 1024;
+const LFS_POINTER_PREFIX: &[u8] = b"version https://git-lfs.github.com/spec/";
+
+fn may_be_lfs_pointer(size: u64) -> bool {
+    (LFS_POINTER_PREFIX.len() as u64..LFS_POINTER_MAX_BYTES).contains(&size)
+}
+
+async fn is_lfs_pointer_file(fs: &dyn Fs, abs_path: &Path) -> Result<bool> {
+    let content = fs
+        .load_bytes(abs_path)
+        .await
+        .with_context(|| format!("loading file {abs_path:?}"))?;
+    Ok(content.starts_with(LFS_POINTER_PREFIX))
+}
+
+/// Hashes the file in fixed-size chunks, so that its contents are never held in memory at once.
+/// Chunks are filled completely before hashing, keeping the hash independent of how reads
+/// happen to be split.
+fn hash_file_chunks(file: &mut dyn std::io::Read, abs_path: &Path) -> Result<u64> {
+    let mut hasher = FxHasher::default();
+    let mut buf = [0u8; 8 * 1024];
+    loop {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let n = file
+                .read(&mut buf[filled..])
+                .with_context(|| format!("reading bytes of the file {abs_path:?}"))?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        hasher.write(&buf[..filled]);
+        if filled < buf.len() {
+            return Ok(hasher.finish());
+        }
+    }
+}
+
+async fn sniff_binary_content(fs: &dyn Fs, abs_path: &Path) -> Result<bool> {
     let mut file = fs
-        .open_sync(&abs_path)
+        .open_sync(abs_path)
         .await
         .with_context(|| format!("opening file {abs_path:?}"))?;
+    let (file_first_bytes, _) = read_file_header(file.as_mut(), abs_path)?;
+    let (_, byte_content) = decode_byte_header(&file_first_bytes);
+    Ok(byte_content == ByteContent::Binary)
+}
 
-    // First, read the beginning of the file to determine its kind and encoding.
-    // We do not want to load an entire large blob into memory only to discard it.
+/// Reads up to [`FILE_ANALYSIS_BYTES`] from the beginning of the file, returning the bytes
+/// and whether the end of the file was reached.
+fn read_file_header(file: &mut dyn std::io::Read, abs_path: &Path) -> Result<(Vec<u8>, bool)> {
     let mut file_first_bytes = Vec::with_capacity(FILE_ANALYSIS_BYTES);
     let mut buf = [0u8; FILE_ANALYSIS_BYTES];
     let mut reached_eof = false;
@@ -5963,6 +8375,21 @@ async fn decode_file_text(
         }
         file_first_bytes.extend_from_slice(&buf[..n]);
     }
+    Ok((file_first_bytes, reached_eof))
+}
+
+async fn decode_file_text(
+    fs: &dyn Fs,
+    abs_path: &Path,
+) -> Result<(String, &'static Encoding, bool)> {
+    let mut file = fs
+        .open_sync(&abs_path)
+        .await
+        .with_context(|| format!("opening file {abs_path:?}"))?;
+
+    // First, read the beginning of the file to determine its kind and encoding.
+    // We do not want to load an entire large blob into memory only to discard it.
+    let (file_first_bytes, reached_eof) = read_file_header(file.as_mut(), abs_path)?;
     let (bom_encoding, byte_content) = decode_byte_header(&file_first_bytes);
     anyhow::ensure!(
         byte_content != ByteContent::Binary,
@@ -1,13 +1,17 @@
 use std::path::Path;
 
-use anyhow::Context as _;
-use settings::{RegisterSetting, Settings};
+use git::DOT_GIT;
+use serde::Deserialize;
+use settings::{RegisterSetting, ScanPermissionErrorSetting, Settings};
 use util::{
     ResultExt,
     paths::{PathMatcher, PathStyle},
     rel_path::RelPath,
 };
 
+/// Files larger than this aren't hashed during scans unless the setting overrides it.
+const DEFAULT_HASH_FILE_CONTENTS_MAX_BYTES: u64 = 1024 * 1024;
+
 #[derive(Clone, PartialEq, Eq, RegisterSetting)]
 pub struct WorktreeSettings {
     pub project_name: Option<String>,
@@ -21,9 +25,90 @@ pub struct WorktreeSettings {
     pub private_files: PathMatcher,
     pub hidden_files: PathMatcher,
     pub read_only_files: PathMatcher,
+    /// Whether entries of a worktree whose root is a `.git` directory are tagged as git internals.
+    pub treat_git_dir_root_as_repo_internals: bool,
+    /// Whether the contents of `.git` directories are scanned. When `false`, they're excluded
+    /// unless `file_scan_inclusions` includes them.
+    pub scan_dot_git: bool,
+    /// Whether file contents are sniffed for binary data while scanning, rather than on demand.
+    pub sniff_binary_files_during_scan: bool,
+    /// Whether the top-level directories of a worktree whose only repository is rooted at the
+    /// worktree root are loaded eagerly.
+    pub auto_expand_single_root_repo: bool,
+    /// Whether file contents are hashed while scanning to detect changes that keep the mtime.
+    pub hash_file_contents_during_scan: bool,
+    /// Files larger than this many bytes aren't hashed while scanning.
+    pub hash_file_contents_max_bytes: u64,
+    /// Whether small files are read while scanning to detect Git LFS pointers.
+    pub detect_lfs_pointers_during_scan: bool,
+    /// Whether recently loaded file contents are kept in memory.
+    pub cache_loaded_files: bool,
+    /// Files larger than this many bytes fail to load with [`crate::LoadFileError::TooLarge`].
+    pub max_loadable_file_bytes: Option<u64>,
+    /// Files larger than this many bytes are marked as ignored while scanning.
+    pub exclude_files_larger_than: Option<u64>,
+    /// How many directories the scanner reads concurrently, or `None` for one per CPU.
+    pub scan_concurrency: Option<usize>,
+    /// Whether reading a directory is retried after it fails with a permission error.
+    pub on_scan_permission_error: ScanPermissionErrorSetting,
+    /// Whether deletions that don't specify otherwise move entries to the trash.
+    pub delete_to_trash: bool,
+    /// How long changes to git repositories are collected before they're reported.
+    pub git_repository_events_window_ms: u64,
+    /// Describes the globs that failed to compile and were left out of the matchers above.
+    pub settings_errors: Vec<String>,
+}
+
+/// Scan settings read from a `.zed/worktree.json` file at a worktree's root.
+///
+/// Unlike `.zed/settings.json`, these apply to that worktree alone, and extend
+/// the global settings rather than replacing them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct WorktreeScanOverrides {
+    pub file_scan_exclusions: Vec<String>,
+    pub file_scan_inclusions: Vec<String>,
 }
 
 impl WorktreeSettings {
+    /// Returns these settings with the worktree's own exclusions and inclusions added.
+    pub fn with_scan_overrides(mut self, overrides: &WorktreeScanOverrides) -> Self {
+        if !overrides.file_scan_exclusions.is_empty() {
+            let file_scan_exclusions = self
+                .file_scan_exclusions
+                .sources()
+                .map(String::from)
+                .chain(overrides.file_scan_exclusions.iter().cloned())
+                .collect();
+            self.file_scan_exclusions = path_matchers(
+                file_scan_exclusions,
+                "file_scan_exclusions",
+                &mut self.settings_errors,
+            );
+        }
+        if !overrides.file_scan_inclusions.is_empty() {
+            let file_scan_inclusions: Vec<String> = self
+                .file_scan_inclusions
+                .sources()
+                .map(String::from)
+                .chain(overrides.file_scan_inclusions.iter().cloned())
+                .collect();
+            let parent_dir_scan_inclusions = parent_dir_scan_inclusions(&file_scan_inclusions);
+            self.file_scan_inclusions = path_matchers(
+                file_scan_inclusions,
+                "file_scan_inclusions",
+                &mut self.settings_errors,
+            );
+            // Invalid ancestors stem from invalid inclusions, which were reported above.
+            self.parent_dir_scan_inclusions = path_matchers(
+                parent_dir_scan_inclusions,
+                "file_scan_inclusions",
+                &mut Vec::new(),
+            );
+        }
+        self
+    }
+
     pub fn is_path_private(&self, path: &RelPath) -> bool {
         path.ancestors()
             .any(|ancestor| self.private_files.is_match(ancestor))
@@ -32,6 +117,20 @@ impl WorktreeSettings {
     pub fn is_path_excluded(&self, path: &RelPath) -> bool {
         path.ancestors()
             .any(|ancestor| self.file_scan_exclusions.is_match(ancestor))
+            || self.is_path_excluded_by_default(path)
+    }
+
+    /// Whether the path is inside a `.git` directory while `scan_dot_git` is off. Unlike
+    /// `file_scan_exclusions`, this default exclusion yields to `file_scan_inclusions`, and the
+    /// `.git` directory itself is still listed.
+    fn is_path_excluded_by_default(&self, path: &RelPath) -> bool {
+        !self.scan_dot_git
+            && path
+                .ancestors()
+                .skip(1)
+                .any(|ancestor| ancestor.file_name() == Some(DOT_GIT))
+            && !self.file_scan_inclusions.is_match(path)
+            && !self.parent_dir_scan_inclusions.is_match(path)
     }
 
     pub fn is_path_always_included(&self, path: &RelPath, is_dir: bool) -> bool {
@@ -42,6 +141,34 @@ impl WorktreeSettings {
         }
     }
 
+    /// Whether a file of `size` bytes exceeds `exclude_files_larger_than`.
+    pub fn is_file_too_large_to_scan(&self, size: u64) -> bool {
+        self.exclude_files_larger_than
+            .is_some_and(|max_bytes| size > max_bytes)
+    }
+
+    /// Returns the `file_scan_exclusions` glob that excludes the path or one of its ancestors.
+    /// Paths inside `.git` directories that only `scan_dot_git` excludes are reported as `**/.git/**`.
+    pub fn excluding_pattern(&self, path: &RelPath) -> Option<String> {
+        matching_source(
+            &self.file_scan_exclusions,
+            &path.ancestors().collect::<Vec<_>>(),
+        )
+        .or_else(|| {
+            self.is_path_excluded_by_default(path)
+                .then(|| format!("**/{DOT_GIT}/**"))
+        })
+    }
+
+    /// Returns the `file_scan_inclusions` glob that makes the path always included.
+    pub fn including_pattern(&self, path: &RelPath, is_dir: bool) -> Option<String> {
+        if is_dir {
+            matching_source(&self.parent_dir_scan_inclusions, &[path])
+        } else {
+            matching_source(&self.file_scan_inclusions, &[path])
+        }
+    }
+
     pub fn is_path_hidden(&self, path: &RelPath) -> bool {
         path.ancestors()
             .any(|ancestor| self.hidden_files.is_match(ancestor))
@@ -64,47 +191,108 @@ impl Settings for WorktreeSettings {
         let private_files = worktree.private_files.unwrap().0;
         let hidden_files = worktree.hidden_files.unwrap();
         let read_only_files = worktree.read_only_files.unwrap_or_default();
-        let parsed_file_scan_inclusions: Vec<String> = file_scan_inclusions
-            .iter()
-            .flat_map(|glob| {
-                Path::new(glob)
-                    .ancestors()
-                    .skip(1)
-                    .map(|a| a.to_string_lossy().into())
-            })
-            .filter(|p: &String| !p.is_empty())
-            .collect();
+        let parsed_file_scan_inclusions = parent_dir_scan_inclusions(&file_scan_inclusions);
+        let mut settings_errors = Vec::new();
 
         Self {
             project_name: worktree.project_name,
             prevent_sharing_in_public_channels: worktree.prevent_sharing_in_public_channels,
-            file_scan_exclusions: path_matchers(file_scan_exclusions, "file_scan_exclusions")
-                .log_err()
-                .unwrap_or_default(),
+            file_scan_exclusions: path_matchers(
+                file_scan_exclusions,
+                "file_scan_exclusions",
+                &mut settings_errors,
+            ),
+            // Invalid ancestors stem from invalid inclusions, which are reported below.
             parent_dir_scan_inclusions: path_matchers(
                 parsed_file_scan_inclusions,
                 "file_scan_inclusions",
-            )
-            .unwrap(),
-            file_scan_inclusions: path_matchers(file_scan_inclusions, "file_scan_inclusions")
-                .unwrap(),
-            private_files: path_matchers(private_files, "private_files")
-                .log_err()
+                &mut Vec::new(),
+            ),
+            file_scan_inclusions: path_matchers(
+                file_scan_inclusions,
+                "file_scan_inclusions",
+                &mut settings_errors,
+            ),
+            private_files: path_matchers(private_files, "private_files", &mut settings_errors),
+            hidden_files: path_matchers(hidden_files, "hidden_files", &mut settings_errors),
+            read_only_files: path_matchers(
+                read_only_files,
+                "read_only_files",
+                &mut settings_errors,
+            ),
+            treat_git_dir_root_as_repo_internals: worktree
+                .treat_git_dir_root_as_repo_internals
+                .unwrap_or_default(),
+            scan_dot_git: worktree.scan_dot_git.unwrap_or_default(),
+            sniff_binary_files_during_scan: worktree
+                .sniff_binary_files_during_scan
                 .unwrap_or_default(),
-            hidden_files: path_matchers(hidden_files, "hidden_files")
-                .log_err()
+            auto_expand_single_root_repo: worktree.auto_expand_single_root_repo.unwrap_or_default(),
+            hash_file_contents_during_scan: worktree
+                .hash_file_contents_during_scan
                 .unwrap_or_default(),
-            read_only_files: path_matchers(read_only_files, "read_only_files")
-                .log_err()
+            hash_file_contents_max_bytes: worktree
+                .hash_file_contents_max_bytes
+                .unwrap_or(DEFAULT_HASH_FILE_CONTENTS_MAX_BYTES),
+            detect_lfs_pointers_during_scan: worktree
+                .detect_lfs_pointers_during_scan
                 .unwrap_or_default(),
+            cache_loaded_files: worktree.cache_loaded_files.unwrap_or_default(),
+            max_loadable_file_bytes: worktree.max_loadable_file_bytes,
+            exclude_files_larger_than: worktree.exclude_files_larger_than,
+            scan_concurrency: worktree.scan_concurrency,
+            on_scan_permission_error: worktree.on_scan_permission_error.unwrap_or_default(),
+            delete_to_trash: worktree.delete_to_trash.unwrap(),
+            git_repository_events_window_ms: worktree
+                .git_repository_events_window_ms
+                .unwrap_or_default(),
+            settings_errors,
         }
     }
 }
 
-fn path_matchers(mut values: Vec<String>, context: &'static str) -> anyhow::Result<PathMatcher> {
+fn parent_dir_scan_inclusions(file_scan_inclusions: &[String]) -> Vec<String> {
+    file_scan_inclusions
+        .iter()
+        .flat_map(|glob| {
+            Path::new(glob)
+                .ancestors()
+                .skip(1)
+                .map(|a| a.to_string_lossy().into())
+        })
+        .filter(|p: &String| !p.is_empty())
+        .collect()
+}
+
+fn matching_source(matcher: &PathMatcher, paths: &[&RelPath]) -> Option<String> {
+    matcher
+        .sources()
+        .find(|source| {
+            PathMatcher::new([source], PathStyle::local())
+                .is_ok_and(|source_matcher| paths.iter().any(|path| source_matcher.is_match(path)))
+        })
+        .map(String::from)
+}
+
+/// Builds a matcher from the globs that compile, describing the others in `errors`.
+fn path_matchers(
+    mut values: Vec<String>,
+    context: &'static str,
+    errors: &mut Vec<String>,
+) -> PathMatcher {
     values.sort();
+    values.retain(|glob| match PathMatcher::new([glob], PathStyle::local()) {
+        Ok(_) => true,
+        Err(error) => {
+            let message = format!("Invalid glob {glob:?} in {context}: {error}");
+            log::error!("{message}");
+            errors.push(message);
+            false
+        }
+    });
     PathMatcher::new(values, PathStyle::local())
-        .with_context(|| format!("Failed to parse globs from {}", context))
+        .log_err()
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
@@ -126,6 +314,21 @@ mod tests {
                 PathStyle::local(),
             )
             .unwrap(),
+            treat_git_dir_root_as_repo_internals: false,
+            scan_dot_git: false,
+            sniff_binary_files_during_scan: false,
+            auto_expand_single_root_repo: false,
+            hash_file_contents_during_scan: false,
+            hash_file_contents_max_bytes: 0,
+            detect_lfs_pointers_during_scan: false,
+            cache_loaded_files: false,
+            max_loadable_file_bytes: None,
+            exclude_files_larger_than: None,
+            scan_concurrency: None,
+            on_scan_permission_error: ScanPermissionErrorSetting::Skip,
+            delete_to_trash: true,
+            git_repository_events_window_ms: 0,
+            settings_errors: Vec::new(),
         }
     }
 
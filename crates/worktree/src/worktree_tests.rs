@@ -1,8 +1,13 @@
-use crate::{Entry, EntryKind, Event, PathChange, Worktree, WorktreeModelHandle};
+use crate::{
+    CreateEntryError, DirCounts, Discrepancy, Entry, EntryKind, EntrySortOrder, Event,
+    ExpandEntryError, LoadFileError, PathChange, PathDecision, ProjectEntryId, RootEntryError,
+    WalkAction, WorkDirectory, Worktree, WorktreeModelHandle,
+};
 use anyhow::Result;
 use encoding_rs;
 use fs::{FakeFs, Fs, RealFs, RemoveOptions};
-use git::{DOT_GIT, GITIGNORE, REPO_EXCLUDE};
+use futures::{FutureExt as _, StreamExt as _};
+use git::{DOT_GIT, GITIGNORE, REPO_EXCLUDE, repository::RepoPath};
 use gpui::{AppContext as _, BackgroundExecutor, BorrowAppContext, Context, Task, TestAppContext};
 use parking_lot::Mutex;
 use postage::stream::Stream;
@@ -10,13 +15,14 @@ use pretty_assertions::assert_eq;
 use rand::prelude::*;
 
 use serde_json::json;
-use settings::SettingsStore;
+use settings::{ScanPermissionErrorSetting, SettingsStore};
 use std::{
     env,
     fmt::Write,
     mem,
     path::{Path, PathBuf},
     sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use util::{
     ResultExt, path,
@@ -165,6 +171,74 @@ async fn test_circular_symlinks(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_observe_loaded_entries(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "dir1": {
+                "deps": {},
+                "src": {
+                    "a.rs": "",
+                },
+            },
+            "dir2": {
+                "b.rs": "",
+            },
+        }),
+    )
+    .await;
+    fs.create_symlink("/root/dir1/deps/dep-dir2".as_ref(), "../../dir2".into())
+        .await
+        .unwrap();
+
+    let tree = Worktree::local(
+        Path::new("/root/dir1"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let mut loaded_entries = tree.update(cx, |tree, cx| tree.observe_loaded_entries(cx));
+
+    fs.create_file("/root/dir1/src/new.rs".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("src/new.rs")).is_some());
+    });
+
+    tree.read_with(cx, |tree, _| {
+        tree.as_local()
+            .unwrap()
+            .refresh_entries_for_paths(vec![rel_path("deps/dep-dir2").into()])
+    })
+    .recv()
+    .await;
+    cx.executor().run_until_parked();
+
+    let mut loaded_paths = Vec::new();
+    while let Some(Some(path)) = loaded_entries.next().now_or_never() {
+        loaded_paths.push(path);
+    }
+    assert_eq!(
+        loaded_paths,
+        [
+            rel_path("deps/dep-dir2").into(),
+            rel_path("deps/dep-dir2/b.rs").into()
+        ]
+    );
+}
+
 #[gpui::test]
 async fn test_symlinks_pointing_outside(cx: &mut TestAppContext) {
     init_test(cx);
@@ -345,20 +419,32 @@ async fn test_symlinks_pointing_outside(cx: &mut TestAppContext) {
 
 #[cfg(target_os = "macos")]
 #[gpui::test]
-async fn test_renaming_case_only(cx: &mut TestAppContext) {
-    cx.executor().allow_parking();
+async fn test_expand_entry(cx: &mut TestAppContext) {
     init_test(cx);
-
-    const OLD_NAME: &str = "aaa.rs";
-    const NEW_NAME: &str = "AAA.rs";
-
-    let fs = Arc::new(RealFs::new(None, cx.executor()));
-    let temp_root = TempTree::new(json!({
-        OLD_NAME: "",
-    }));
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "dir1": {
+                "deps": {},
+                "src": {
+                    "a.rs": "",
+                },
+            },
+            "dir2": {
+                "src": {
+                    "b.rs": "",
+                },
+            },
+        }),
+    )
+    .await;
+    fs.create_symlink("/root/dir1/deps/dep-dir2".as_ref(), "../../dir2".into())
+        .await
+        .unwrap();
 
     let tree = Worktree::local(
-        temp_root.path(),
+        Path::new("/root/dir1"),
         true,
         fs.clone(),
         Default::default(),
@@ -367,70 +453,119 @@ async fn test_renaming_case_only(cx: &mut TestAppContext) {
     )
     .await
     .unwrap();
-
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
+
+    let (external_dir_id, loaded_dir_id) = tree.read_with(cx, |tree, _| {
+        let external_dir = tree.entry_for_path(rel_path("deps/dep-dir2")).unwrap();
+        assert_eq!(external_dir.kind, EntryKind::UnloadedDir);
+        assert!(tree.entry_for_path(rel_path("deps/dep-dir2/src")).is_none());
+        (
+            external_dir.id,
+            tree.entry_for_path(rel_path("src")).unwrap().id,
+        )
+    });
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local().unwrap().expand_entry(external_dir_id, cx)
+    })
+    .await
+    .unwrap();
     tree.read_with(cx, |tree, _| {
         assert_eq!(
-            tree.entries(true, 0)
-                .map(|entry| entry.path.as_ref())
-                .collect::<Vec<_>>(),
-            vec![rel_path(""), rel_path(OLD_NAME)]
+            tree.entry_for_path(rel_path("deps/dep-dir2")).unwrap().kind,
+            EntryKind::Dir
         );
+        assert!(tree.entry_for_path(rel_path("deps/dep-dir2/src")).is_some());
     });
 
-    fs.rename(
-        &temp_root.path().join(OLD_NAME),
-        &temp_root.path().join(NEW_NAME),
-        fs::RenameOptions {
-            overwrite: true,
-            ignore_if_exists: true,
-            create_parents: false,
-        },
+    for (entry_id, expected_error) in [
+        (loaded_dir_id, ExpandEntryError::NotAnUnloadedDir),
+        (
+            ProjectEntryId::from_proto(u64::MAX),
+            ExpandEntryError::NotFound,
+        ),
+    ] {
+        let error = tree
+            .update(cx, |tree, cx| {
+                tree.as_local().unwrap().expand_entry(entry_id, cx)
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ExpandEntryError>(),
+            Some(&expected_error)
+        );
+    }
+}
+
+#[gpui::test]
+async fn test_root_entry_cannot_be_deleted(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "a" })).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
     )
     .await
     .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
 
-    tree.flush_fs_events(cx).await;
+    let deleted_entries = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let deleted_entries = deleted_entries.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::DeletedEntry(entry_id) = event {
+                deleted_entries.lock().push(*entry_id);
+            }
+        })
+        .detach();
+    });
+
+    let root_id = tree.read_with(cx, |tree, _| {
+        let root_entry = tree.root_entry().unwrap();
+        assert!(root_entry.is_root());
+        assert!(!tree.entry_for_path(rel_path("a.txt")).unwrap().is_root());
+        root_entry.id
+    });
+
+    let error = tree
+        .update(cx, |tree, cx| tree.delete_entry(root_id, Some(false), cx))
+        .unwrap()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<RootEntryError>(),
+        Some(&RootEntryError)
+    );
+    cx.executor().run_until_parked();
 
+    assert!(deleted_entries.lock().is_empty());
+    assert!(fs.is_file(Path::new("/root/a.txt")).await);
     tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true, 0)
-                .map(|entry| entry.path.as_ref())
-                .collect::<Vec<_>>(),
-            vec![rel_path(""), rel_path(NEW_NAME)]
-        );
+        assert!(tree.root_entry().is_some());
+        assert!(tree.entry_for_path(rel_path("a.txt")).is_some());
     });
 }
 
 #[gpui::test]
-async fn test_open_gitignored_files(cx: &mut TestAppContext) {
+async fn test_delete_to_trash(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
     fs.insert_tree(
         "/root",
         json!({
-            ".gitignore": "node_modules\n",
-            "one": {
-                "node_modules": {
-                    "a": {
-                        "a1.js": "a1",
-                        "a2.js": "a2",
-                    },
-                    "b": {
-                        "b1.js": "b1",
-                        "b2.js": "b2",
-                    },
-                    "c": {
-                        "c1.js": "c1",
-                        "c2.js": "c2",
-                    }
-                },
-            },
-            "two": {
-                "x.js": "",
-                "y.js": "",
-            },
+            "a.txt": "",
+            "b.txt": "",
+            "c.txt": "",
+            "d.txt": "",
         }),
     )
     .await;
@@ -445,157 +580,147 @@ async fn test_open_gitignored_files(cx: &mut TestAppContext) {
     )
     .await
     .unwrap();
-
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
 
-    tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true, 0)
-                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
-                .collect::<Vec<_>>(),
-            vec![
-                (rel_path(""), false),
-                (rel_path(".gitignore"), false),
-                (rel_path("one"), false),
-                (rel_path("one/node_modules"), true),
-                (rel_path("two"), false),
-                (rel_path("two/x.js"), false),
-                (rel_path("two/y.js"), false),
-            ]
-        );
-    });
-
-    // Open a file that is nested inside of a gitignored directory that
-    // has not yet been expanded.
-    let prev_read_dir_count = fs.read_dir_call_count();
-    let loaded = tree
-        .update(cx, |tree, cx| {
-            tree.load_file(rel_path("one/node_modules/b/b1.js"), cx)
+    let delete = |path: &str, trash: Option<bool>, cx: &mut TestAppContext| {
+        tree.update(cx, |tree, cx| {
+            let entry_id = tree.entry_for_path(rel_path(path)).unwrap().id;
+            tree.delete_entry(entry_id, trash, cx).unwrap()
         })
-        .await
-        .unwrap();
-
-    tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true, 0)
-                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
-                .collect::<Vec<_>>(),
-            vec![
-                (rel_path(""), false),
-                (rel_path(".gitignore"), false),
-                (rel_path("one"), false),
-                (rel_path("one/node_modules"), true),
-                (rel_path("one/node_modules/a"), true),
-                (rel_path("one/node_modules/b"), true),
-                (rel_path("one/node_modules/b/b1.js"), true),
-                (rel_path("one/node_modules/b/b2.js"), true),
-                (rel_path("one/node_modules/c"), true),
-                (rel_path("two"), false),
-                (rel_path("two/x.js"), false),
-                (rel_path("two/y.js"), false),
-            ]
-        );
+    };
 
-        assert_eq!(
-            loaded.file.path.as_ref(),
-            rel_path("one/node_modules/b/b1.js")
-        );
+    delete("a.txt", None, cx).await.unwrap();
+    delete("b.txt", Some(false), cx).await.unwrap();
+    assert_eq!(fs.trashed_paths(), [PathBuf::from("/root/a.txt")]);
 
-        // Only the newly-expanded directories are scanned.
-        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 2);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.delete_to_trash = Some(false);
+            });
+        });
     });
+    delete("c.txt", None, cx).await.unwrap();
+    delete("d.txt", Some(true), cx).await.unwrap();
+    assert_eq!(
+        fs.trashed_paths(),
+        [PathBuf::from("/root/a.txt"), PathBuf::from("/root/d.txt")]
+    );
+    assert!(!fs.is_file(Path::new("/root/c.txt")).await);
+}
 
-    // Open another file in a different subdirectory of the same
-    // gitignored directory.
-    let prev_read_dir_count = fs.read_dir_call_count();
-    let loaded = tree
-        .update(cx, |tree, cx| {
-            tree.load_file(rel_path("one/node_modules/a/a2.js"), cx)
-        })
-        .await
-        .unwrap();
+#[gpui::test]
+async fn test_file_replaced_by_directory(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "foo": "contents" })).await;
 
-    tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true, 0)
-                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
-                .collect::<Vec<_>>(),
-            vec![
-                (rel_path(""), false),
-                (rel_path(".gitignore"), false),
-                (rel_path("one"), false),
-                (rel_path("one/node_modules"), true),
-                (rel_path("one/node_modules/a"), true),
-                (rel_path("one/node_modules/a/a1.js"), true),
-                (rel_path("one/node_modules/a/a2.js"), true),
-                (rel_path("one/node_modules/b"), true),
-                (rel_path("one/node_modules/b/b1.js"), true),
-                (rel_path("one/node_modules/b/b2.js"), true),
-                (rel_path("one/node_modules/c"), true),
-                (rel_path("two"), false),
-                (rel_path("two/x.js"), false),
-                (rel_path("two/y.js"), false),
-            ]
-        );
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
 
-        assert_eq!(
-            loaded.file.path.as_ref(),
-            rel_path("one/node_modules/a/a2.js")
-        );
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let changes = changes.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::UpdatedEntries(update) = event {
+                changes.lock().extend(
+                    update
+                        .iter()
+                        .map(|(path, entry_id, change)| (path.clone(), *entry_id, *change)),
+                );
+            }
+        })
+        .detach();
+    });
 
-        // Only the newly-expanded directory is scanned.
-        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 1);
+    let file_id = tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path(rel_path("foo")).unwrap();
+        assert!(entry.is_file());
+        entry.id
     });
 
-    let path = PathBuf::from("/root/one/node_modules/c/lib");
+    fs.pause_events();
+    fs.remove_file("/root/foo".as_ref(), Default::default())
+        .await
+        .unwrap();
+    fs.create_dir("/root/foo".as_ref()).await.unwrap();
+    fs.create_file("/root/foo/bar".as_ref(), Default::default())
+        .await
+        .unwrap();
+    fs.unpause_events_and_flush();
+    cx.executor().run_until_parked();
 
-    // No work happens when files and directories change within an unloaded directory.
-    let prev_fs_call_count = fs.read_dir_call_count() + fs.metadata_call_count();
-    // When we open a directory, we check each ancestor whether it's a git
-    // repository. That means we have an fs.metadata call per ancestor that we
-    // need to subtract here.
-    let ancestors = path.ancestors().count();
+    let (dir_id, bar_id) = tree.read_with(cx, |tree, _| {
+        let dir_entry = tree.entry_for_path(rel_path("foo")).unwrap();
+        assert_eq!(dir_entry.kind, EntryKind::Dir);
+        let bar_entry = tree.entry_for_path(rel_path("foo/bar")).unwrap();
+        (dir_entry.id, bar_entry.id)
+    });
+    assert_ne!(dir_id, file_id);
+    assert_eq!(
+        mem::take(&mut *changes.lock()),
+        [
+            (rel_path("foo").into(), file_id, PathChange::Removed),
+            (rel_path("foo").into(), dir_id, PathChange::Added),
+            (rel_path("foo/bar").into(), bar_id, PathChange::Added),
+        ]
+    );
 
-    fs.create_dir(path.as_ref()).await.unwrap();
+    fs.pause_events();
+    fs.remove_dir(
+        "/root/foo".as_ref(),
+        RemoveOptions {
+            recursive: true,
+            ignore_if_not_exists: false,
+        },
+    )
+    .await
+    .unwrap();
+    fs.create_file("/root/foo".as_ref(), Default::default())
+        .await
+        .unwrap();
+    fs.unpause_events_and_flush();
     cx.executor().run_until_parked();
 
+    let new_file_id = tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path(rel_path("foo")).unwrap();
+        assert_eq!(entry.kind, EntryKind::File);
+        assert!(tree.entry_for_path(rel_path("foo/bar")).is_none());
+        entry.id
+    });
+    assert_ne!(new_file_id, dir_id);
     assert_eq!(
-        fs.read_dir_call_count() + fs.metadata_call_count() - prev_fs_call_count - ancestors,
-        0
+        mem::take(&mut *changes.lock()),
+        [
+            (rel_path("foo").into(), dir_id, PathChange::Removed),
+            (rel_path("foo").into(), new_file_id, PathChange::Added),
+            (rel_path("foo/bar").into(), bar_id, PathChange::Removed),
+        ]
     );
 }
 
 #[gpui::test]
-async fn test_dirs_no_longer_ignored(cx: &mut TestAppContext) {
+async fn test_undo_rename_entry(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
     fs.insert_tree(
         "/root",
         json!({
-            ".gitignore": "node_modules\n",
             "a": {
-                "a.js": "",
-            },
-            "b": {
-                "b.js": "",
-            },
-            "node_modules": {
-                "c": {
-                    "c.js": "",
-                },
-                "d": {
-                    "d.js": "",
-                    "e": {
-                        "e1.js": "",
-                        "e2.js": "",
-                    },
-                    "f": {
-                        "f1.js": "",
-                        "f2.js": "",
-                    }
-                },
+                "one.txt": "one",
             },
+            "b": {},
         }),
     )
     .await;
@@ -610,101 +735,115 @@ async fn test_dirs_no_longer_ignored(cx: &mut TestAppContext) {
     )
     .await
     .unwrap();
-
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
 
-    // Open a file within the gitignored directory, forcing some of its
-    // subdirectories to be read, but not all.
-    let read_dir_count_1 = fs.read_dir_call_count();
-    tree.read_with(cx, |tree, _| {
-        tree.as_local()
-            .unwrap()
-            .refresh_entries_for_paths(vec![rel_path("node_modules/d/d.js").into()])
-    })
-    .recv()
-    .await;
+    let entry_id = tree.read_with(cx, |tree, _| {
+        tree.entry_for_path(rel_path("a/one.txt")).unwrap().id
+    });
 
-    // Those subdirectories are now loaded.
+    let (renamed_entry, token) = tree
+        .update(cx, |tree, cx| {
+            tree.as_local()
+                .unwrap()
+                .rename_entry(entry_id, rel_path("b/two.txt").into(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(renamed_entry.id, entry_id);
+    assert_eq!(renamed_entry.path.as_ref(), rel_path("b/two.txt"));
     tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true, 0)
-                .map(|e| (e.path.as_ref(), e.is_ignored))
-                .collect::<Vec<_>>(),
-            &[
-                (rel_path(""), false),
-                (rel_path(".gitignore"), false),
-                (rel_path("a"), false),
-                (rel_path("a/a.js"), false),
-                (rel_path("b"), false),
-                (rel_path("b/b.js"), false),
-                (rel_path("node_modules"), true),
-                (rel_path("node_modules/c"), true),
-                (rel_path("node_modules/d"), true),
-                (rel_path("node_modules/d/d.js"), true),
-                (rel_path("node_modules/d/e"), true),
-                (rel_path("node_modules/d/f"), true),
-            ]
-        );
+        assert!(tree.entry_for_path(rel_path("a/one.txt")).is_none());
     });
-    let read_dir_count_2 = fs.read_dir_call_count();
-    assert_eq!(read_dir_count_2 - read_dir_count_1, 2);
 
-    // Update the gitignore so that node_modules is no longer ignored,
-    // but a subdirectory is ignored
-    fs.save("/root/.gitignore".as_ref(), &"e".into(), Default::default())
+    tree.update(cx, |tree, cx| tree.as_local().unwrap().undo(token, cx))
         .await
         .unwrap();
+    tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path(rel_path("a/one.txt")).unwrap();
+        assert_eq!(entry.id, entry_id);
+        assert!(tree.entry_for_path(rel_path("b/two.txt")).is_none());
+    });
+    assert_eq!(fs.load(Path::new("/root/a/one.txt")).await.unwrap(), "one");
+}
+
+#[gpui::test]
+async fn test_rename_entry_with_progress(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    let directory = (0..3)
+        .map(|dir_ix| {
+            let files = (0..10)
+                .map(|file_ix| (format!("file-{file_ix}.txt"), json!("")))
+                .collect::<serde_json::Map<_, _>>();
+            (format!("dir-{dir_ix}"), serde_json::Value::Object(files))
+        })
+        .collect::<serde_json::Map<_, _>>();
+    fs.insert_tree("/root", json!({ "big": directory })).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let entry_id = tree.read_with(cx, |tree, _| {
+        tree.entry_for_path(rel_path("big")).unwrap().id
+    });
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |tree, cx| {
+        let progress = progress.clone();
+        tree.as_local().unwrap().rename_entry_with_progress(
+            entry_id,
+            rel_path("moved").into(),
+            move |count| progress.lock().push(count),
+            cx,
+        )
+    })
+    .await
+    .unwrap();
     cx.executor().run_until_parked();
 
-    // All of the directories that are no longer ignored are now loaded.
+    let progress = progress.lock().clone();
+    assert!(!progress.is_empty());
+    assert!(
+        progress.windows(2).all(|counts| counts[0] < counts[1]),
+        "progress should increase, got {progress:?}"
+    );
+    assert_eq!(progress.last(), Some(&33));
     tree.read_with(cx, |tree, _| {
         assert_eq!(
-            tree.entries(true, 0)
-                .map(|e| (e.path.as_ref(), e.is_ignored))
-                .collect::<Vec<_>>(),
-            &[
-                (rel_path(""), false),
-                (rel_path(".gitignore"), false),
-                (rel_path("a"), false),
-                (rel_path("a/a.js"), false),
-                (rel_path("b"), false),
-                (rel_path("b/b.js"), false),
-                // This directory is no longer ignored
-                (rel_path("node_modules"), false),
-                (rel_path("node_modules/c"), false),
-                (rel_path("node_modules/c/c.js"), false),
-                (rel_path("node_modules/d"), false),
-                (rel_path("node_modules/d/d.js"), false),
-                // This subdirectory is now ignored
-                (rel_path("node_modules/d/e"), true),
-                (rel_path("node_modules/d/f"), false),
-                (rel_path("node_modules/d/f/f1.js"), false),
-                (rel_path("node_modules/d/f/f2.js"), false),
-            ]
+            tree.entry_for_path(rel_path("moved/dir-2/file-9.txt"))
+                .map(|entry| entry.is_file()),
+            Some(true)
         );
     });
-
-    // Each of the newly-loaded directories is scanned only once.
-    let read_dir_count_3 = fs.read_dir_call_count();
-    assert_eq!(read_dir_count_3 - read_dir_count_2, 2);
 }
 
 #[gpui::test]
-async fn test_write_file(cx: &mut TestAppContext) {
-    init_test(cx);
+async fn test_renaming_case_only(cx: &mut TestAppContext) {
     cx.executor().allow_parking();
-    let dir = TempTree::new(json!({
-        ".git": {},
-        ".gitignore": "ignored-dir\n",
-        "tracked-dir": {},
-        "ignored-dir": {}
+    init_test(cx);
+
+    const OLD_NAME: &str = "aaa.rs";
+    const NEW_NAME: &str = "AAA.rs";
+
+    let fs = Arc::new(RealFs::new(None, cx.executor()));
+    let temp_root = TempTree::new(json!({
+        OLD_NAME: "",
     }));
 
-    let worktree = Worktree::local(
-        dir.path(),
+    let tree = Worktree::local(
+        temp_root.path(),
         true,
-        Arc::new(RealFs::new(None, cx.executor())),
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
@@ -712,163 +851,121 @@ async fn test_write_file(cx: &mut TestAppContext) {
     .await
     .unwrap();
 
-    #[cfg(not(target_os = "macos"))]
-    fs::fs_watcher::global(|_| {}).unwrap();
-
-    cx.read(|cx| worktree.read(cx).as_local().unwrap().scan_complete())
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
-    worktree.flush_fs_events(cx).await;
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![rel_path(""), rel_path(OLD_NAME)]
+        );
+    });
 
-    worktree
-        .update(cx, |tree, cx| {
-            tree.write_file(
-                rel_path("tracked-dir/file.txt").into(),
-                "hello".into(),
-                Default::default(),
-                encoding_rs::UTF_8,
-                false,
-                cx,
-            )
-        })
-        .await
-        .unwrap();
-    worktree
-        .update(cx, |tree, cx| {
-            tree.write_file(
-                rel_path("ignored-dir/file.txt").into(),
-                "world".into(),
-                Default::default(),
-                encoding_rs::UTF_8,
-                false,
-                cx,
-            )
-        })
-        .await
-        .unwrap();
-    worktree.read_with(cx, |tree, _| {
-        let tracked = tree
-            .entry_for_path(rel_path("tracked-dir/file.txt"))
-            .unwrap();
-        let ignored = tree
-            .entry_for_path(rel_path("ignored-dir/file.txt"))
-            .unwrap();
-        assert!(!tracked.is_ignored);
-        assert!(ignored.is_ignored);
+    fs.rename(
+        &temp_root.path().join(OLD_NAME),
+        &temp_root.path().join(NEW_NAME),
+        fs::RenameOptions {
+            overwrite: true,
+            ignore_if_exists: true,
+            create_parents: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    tree.flush_fs_events(cx).await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![rel_path(""), rel_path(NEW_NAME)]
+        );
     });
 }
 
+#[cfg(unix)]
 #[gpui::test]
-async fn test_file_scan_inclusions(cx: &mut TestAppContext) {
-    init_test(cx);
+async fn test_broken_symlinks(cx: &mut TestAppContext) {
     cx.executor().allow_parking();
-    let dir = TempTree::new(json!({
-        ".gitignore": "**/target\n/node_modules\ntop_level.txt\n",
-        "target": {
-            "index": "blah2"
-        },
-        "node_modules": {
-            ".DS_Store": "",
-            "prettier": {
-                "package.json": "{}",
-            },
-            "package.json": "//package.json"
-        },
-        "src": {
-            ".DS_Store": "",
-            "foo": {
-                "foo.rs": "mod another;\n",
-                "another.rs": "// another",
-            },
-            "bar": {
-                "bar.rs": "// bar",
-            },
-            "lib.rs": "mod foo;\nmod bar;\n",
-        },
-        "top_level.txt": "top level file",
-        ".DS_Store": "",
+    init_test(cx);
+
+    let fs = Arc::new(RealFs::new(None, cx.executor()));
+    let temp_root = TempTree::new(json!({
+        "target.txt": "",
+        "removed.txt": "",
     }));
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings(cx, |settings| {
-                settings.project.worktree.file_scan_exclusions = Some(vec![]);
-                settings.project.worktree.file_scan_inclusions = Some(vec![
-                    "node_modules/**/package.json".to_string(),
-                    "**/.DS_Store".to_string(),
-                ]);
-            });
-        });
-    });
+    let removed_target = temp_root.path().join("removed.txt");
+    fs.create_symlink(&temp_root.path().join("link"), "target.txt".into())
+        .await
+        .unwrap();
+    fs.create_symlink(&temp_root.path().join("dangling"), removed_target.clone())
+        .await
+        .unwrap();
+    fs.remove_file(&removed_target, Default::default())
+        .await
+        .unwrap();
 
     let tree = Worktree::local(
-        dir.path(),
+        temp_root.path(),
         true,
-        Arc::new(RealFs::new(None, cx.executor())),
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
     )
     .await
     .unwrap();
+
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
+    tree.read_with(cx, |tree, _| {
+        let link = tree.entry_for_path(rel_path("link")).unwrap();
+        assert!(!link.is_broken_symlink);
+        assert_eq!(link.symlink_target, None);
+
+        let dangling = tree.entry_for_path(rel_path("dangling")).unwrap();
+        assert!(dangling.is_broken_symlink);
+        assert_eq!(
+            dangling.symlink_target.as_deref(),
+            Some(removed_target.as_path())
+        );
+    });
+
+    fs.create_symlink(&temp_root.path().join("new-dangling"), "missing.txt".into())
+        .await
+        .unwrap();
     tree.flush_fs_events(cx).await;
+
     tree.read_with(cx, |tree, _| {
-        // Assert that file_scan_inclusions overrides  file_scan_exclusions.
-        check_worktree_entries(
-            tree,
-            &[],
-            &["target", "node_modules"],
-            &["src/lib.rs", "src/bar/bar.rs", ".gitignore"],
-            &[
-                "node_modules/prettier/package.json",
-                ".DS_Store",
-                "node_modules/.DS_Store",
-                "src/.DS_Store",
-            ],
-        )
+        let dangling = tree.entry_for_path(rel_path("new-dangling")).unwrap();
+        assert!(dangling.is_broken_symlink);
+        assert_eq!(
+            dangling.symlink_target.as_deref(),
+            Some(Path::new("missing.txt"))
+        );
     });
 }
 
 #[gpui::test]
-async fn test_file_scan_exclusions_overrules_inclusions(cx: &mut TestAppContext) {
+async fn test_scan_id_and_completion(cx: &mut TestAppContext) {
     init_test(cx);
-    cx.executor().allow_parking();
-    let dir = TempTree::new(json!({
-        ".gitignore": "**/target\n/node_modules\n",
-        "target": {
-            "index": "blah2"
-        },
-        "node_modules": {
-            ".DS_Store": "",
-            "prettier": {
-                "package.json": "{}",
-            },
-        },
-        "src": {
-            ".DS_Store": "",
-            "foo": {
-                "foo.rs": "mod another;\n",
-                "another.rs": "// another",
-            },
-        },
-        ".DS_Store": "",
-    }));
-
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings(cx, |settings| {
-                settings.project.worktree.file_scan_exclusions =
-                    Some(vec!["**/.DS_Store".to_string()]);
-                settings.project.worktree.file_scan_inclusions =
-                    Some(vec!["**/.DS_Store".to_string()]);
-            });
-        });
-    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "",
+            "b": {},
+        }),
+    )
+    .await;
 
     let tree = Worktree::local(
-        dir.path(),
+        Path::new("/root"),
         true,
-        Arc::new(RealFs::new(None, cx.executor())),
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
@@ -877,57 +974,58 @@ async fn test_file_scan_exclusions_overrules_inclusions(cx: &mut TestAppContext)
     .unwrap();
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
-    tree.flush_fs_events(cx).await;
-    tree.read_with(cx, |tree, _| {
-        // Assert that file_scan_inclusions overrides  file_scan_exclusions.
-        check_worktree_entries(
-            tree,
-            &[".DS_Store, src/.DS_Store"],
-            &["target", "node_modules"],
-            &["src/foo/another.rs", "src/foo/foo.rs", ".gitignore"],
-            &[],
-        )
+
+    let mut prev_scan_id = tree.read_with(cx, |tree, _| {
+        let snapshot = tree.snapshot();
+        assert!(snapshot.is_scan_complete());
+        snapshot.scan_id()
     });
+
+    for path in ["/root/b/c.txt", "/root/d.txt"] {
+        fs.create_file(path.as_ref(), Default::default())
+            .await
+            .unwrap();
+        cx.executor().run_until_parked();
+        cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+            .await;
+
+        prev_scan_id = tree.read_with(cx, |tree, _| {
+            let snapshot = tree.snapshot();
+            assert!(snapshot.scan_id() > prev_scan_id);
+            assert!(snapshot.is_scan_complete());
+            snapshot.scan_id()
+        });
+    }
 }
 
 #[gpui::test]
-async fn test_file_scan_inclusions_reindexes_on_setting_change(cx: &mut TestAppContext) {
+async fn test_entries_batched(cx: &mut TestAppContext) {
     init_test(cx);
-    cx.executor().allow_parking();
-    let dir = TempTree::new(json!({
-        ".gitignore": "**/target\n/node_modules/\n",
-        "target": {
-            "index": "blah2"
-        },
-        "node_modules": {
-            ".DS_Store": "",
-            "prettier": {
-                "package.json": "{}",
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ignored.txt\n",
+            "a": {
+                "a1.txt": "",
+                "a2.txt": "",
             },
-        },
-        "src": {
-            ".DS_Store": "",
-            "foo": {
-                "foo.rs": "mod another;\n",
-                "another.rs": "// another",
+            "b": {
+                "b1.txt": "",
+                "c": {
+                    "c1.txt": "",
+                },
             },
-        },
-        ".DS_Store": "",
-    }));
+            "d.txt": "",
+            "ignored.txt": "",
+        }),
+    )
+    .await;
 
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings(cx, |settings| {
-                settings.project.worktree.file_scan_exclusions = Some(vec![]);
-                settings.project.worktree.file_scan_inclusions =
-                    Some(vec!["node_modules/**".to_string()]);
-            });
-        });
-    });
     let tree = Worktree::local(
-        dir.path(),
+        Path::new("/root"),
         true,
-        Arc::new(RealFs::new(None, cx.executor())),
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
@@ -936,84 +1034,53 @@ async fn test_file_scan_inclusions_reindexes_on_setting_change(cx: &mut TestAppC
     .unwrap();
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
-    tree.flush_fs_events(cx).await;
-
-    tree.read_with(cx, |tree, _| {
-        assert!(
-            tree.entry_for_path(rel_path("node_modules"))
-                .is_some_and(|f| f.is_always_included)
-        );
-        assert!(
-            tree.entry_for_path(rel_path("node_modules/prettier/package.json"))
-                .is_some_and(|f| f.is_always_included)
-        );
-    });
-
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings(cx, |settings| {
-                settings.project.worktree.file_scan_exclusions = Some(vec![]);
-                settings.project.worktree.file_scan_inclusions = Some(vec![]);
-            });
-        });
-    });
-    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
-        .await;
-    tree.flush_fs_events(cx).await;
 
     tree.read_with(cx, |tree, _| {
-        assert!(
-            tree.entry_for_path(rel_path("node_modules"))
-                .is_some_and(|f| !f.is_always_included)
-        );
-        assert!(
-            tree.entry_for_path(rel_path("node_modules/prettier/package.json"))
-                .is_some_and(|f| !f.is_always_included)
-        );
+        for include_ignored in [false, true] {
+            let entries = tree
+                .entries(include_ignored, 0)
+                .cloned()
+                .collect::<Vec<_>>();
+            let chunks = tree.entries_batched(include_ignored, 3).collect::<Vec<_>>();
+            assert_eq!(chunks.len(), entries.len().div_ceil(3));
+            assert!(
+                chunks
+                    .iter()
+                    .all(|chunk| !chunk.is_empty() && chunk.len() <= 3)
+            );
+            assert_eq!(chunks.concat(), entries);
+        }
     });
 }
 
 #[gpui::test]
-async fn test_file_scan_exclusions(cx: &mut TestAppContext) {
+async fn test_resume_traversal(cx: &mut TestAppContext) {
     init_test(cx);
-    cx.executor().allow_parking();
-    let dir = TempTree::new(json!({
-        ".gitignore": "**/target\n/node_modules\n",
-        "target": {
-            "index": "blah2"
-        },
-        "node_modules": {
-            ".DS_Store": "",
-            "prettier": {
-                "package.json": "{}",
-            },
-        },
-        "src": {
-            ".DS_Store": "",
-            "foo": {
-                "foo.rs": "mod another;\n",
-                "another.rs": "// another",
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ignored.txt\n",
+            "a": {
+                "a1.txt": "",
+                "a2.txt": "",
             },
-            "bar": {
-                "bar.rs": "// bar",
+            "b": {
+                "b1.txt": "",
+                "c": {
+                    "c1.txt": "",
+                },
             },
-            "lib.rs": "mod foo;\nmod bar;\n",
-        },
-        ".DS_Store": "",
-    }));
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings(cx, |settings| {
-                settings.project.worktree.file_scan_exclusions =
-                    Some(vec!["**/foo/**".to_string(), "**/.DS_Store".to_string()]);
-            });
-        });
-    });
+            "d.txt": "",
+            "ignored.txt": "",
+        }),
+    )
+    .await;
 
     let tree = Worktree::local(
-        dir.path(),
+        Path::new("/root"),
         true,
-        Arc::new(RealFs::new(None, cx.executor())),
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
@@ -1022,80 +1089,52 @@ async fn test_file_scan_exclusions(cx: &mut TestAppContext) {
     .unwrap();
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
-    tree.flush_fs_events(cx).await;
-    tree.read_with(cx, |tree, _| {
-        check_worktree_entries(
-            tree,
-            &[
-                "src/foo/foo.rs",
-                "src/foo/another.rs",
-                "node_modules/.DS_Store",
-                "src/.DS_Store",
-                ".DS_Store",
-            ],
-            &["target", "node_modules"],
-            &["src/lib.rs", "src/bar/bar.rs", ".gitignore"],
-            &[],
-        )
-    });
 
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings(cx, |settings| {
-                settings.project.worktree.file_scan_exclusions =
-                    Some(vec!["**/node_modules/**".to_string()]);
-            });
-        });
-    });
-    tree.flush_fs_events(cx).await;
-    cx.executor().run_until_parked();
-    tree.read_with(cx, |tree, _| {
-        check_worktree_entries(
-            tree,
-            &[
-                "node_modules/prettier/package.json",
-                "node_modules/.DS_Store",
-                "node_modules",
-            ],
-            &["target"],
-            &[
-                ".gitignore",
-                "src/lib.rs",
-                "src/bar/bar.rs",
-                "src/foo/foo.rs",
-                "src/foo/another.rs",
-                "src/.DS_Store",
-                ".DS_Store",
-            ],
-            &[],
-        )
-    });
+    let snapshot = tree.read_with(cx, |tree, _| tree.snapshot());
+    let entries = snapshot.entries(true, 0).cloned().collect::<Vec<_>>();
+
+    let mut traversal = snapshot.entries(true, 0);
+    let mut resumed_entries = traversal
+        .by_ref()
+        .take(entries.len() / 2)
+        .cloned()
+        .collect::<Vec<_>>();
+    let cursor = traversal.save_cursor();
+    assert!(!cursor.is_done());
+
+    let mut traversal = snapshot.resume_traversal(&cursor);
+    resumed_entries.extend(traversal.by_ref().cloned());
+    assert_eq!(resumed_entries, entries);
+    assert!(traversal.save_cursor().is_done());
+    assert_eq!(
+        snapshot.resume_traversal(&traversal.save_cursor()).next(),
+        None
+    );
 }
 
 #[gpui::test]
-async fn test_hidden_files(cx: &mut TestAppContext) {
+async fn test_find_entries(cx: &mut TestAppContext) {
     init_test(cx);
-    cx.executor().allow_parking();
-    let dir = TempTree::new(json!({
-        ".gitignore": "**/target\n",
-        ".hidden_file": "content",
-        ".hidden_dir": {
-            "nested.rs": "code",
-        },
-        "src": {
-            "visible.rs": "code",
-        },
-        "logs": {
-            "app.log": "logs",
-            "debug.log": "logs",
-        },
-        "visible.txt": "content",
-    }));
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ignored.txt\n",
+            "small.txt": "a",
+            "large.txt": "a".repeat(100),
+            "dir": {
+                "medium.txt": "a".repeat(20),
+                "huge.txt": "a".repeat(200),
+            },
+            "ignored.txt": "a".repeat(300),
+        }),
+    )
+    .await;
 
     let tree = Worktree::local(
-        dir.path(),
+        Path::new("/root"),
         true,
-        Arc::new(RealFs::new(None, cx.executor())),
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
@@ -1104,109 +1143,105 @@ async fn test_hidden_files(cx: &mut TestAppContext) {
     .unwrap();
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
-    tree.flush_fs_events(cx).await;
 
     tree.read_with(cx, |tree, _| {
+        let large_files = |include_ignored| {
+            tree.find_entries(include_ignored, |entry| entry.is_file() && entry.size > 50)
+                .map(|entry| entry.path.as_unix_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(large_files(false), vec!["dir/huge.txt", "large.txt"]);
         assert_eq!(
-            tree.entries(true, 0)
-                .map(|entry| (entry.path.as_ref(), entry.is_hidden))
-                .collect::<Vec<_>>(),
-            vec![
-                (rel_path(""), false),
-                (rel_path(".gitignore"), true),
-                (rel_path(".hidden_dir"), true),
-                (rel_path(".hidden_dir/nested.rs"), true),
-                (rel_path(".hidden_file"), true),
-                (rel_path("logs"), false),
-                (rel_path("logs/app.log"), false),
-                (rel_path("logs/debug.log"), false),
-                (rel_path("src"), false),
-                (rel_path("src/visible.rs"), false),
-                (rel_path("visible.txt"), false),
-            ]
+            large_files(true),
+            vec!["dir/huge.txt", "ignored.txt", "large.txt"]
         );
     });
+}
 
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings(cx, |settings| {
-                settings.project.worktree.hidden_files = Some(vec!["**/*.log".to_string()]);
-            });
-        });
-    });
-    tree.flush_fs_events(cx).await;
-    cx.executor().run_until_parked();
+#[gpui::test]
+async fn test_dir_child_counts(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "*.log\nbuild\n",
+            "dir": {
+                "a.txt": "",
+                "b.rs": "",
+                "c.log": "",
+                "sub1": {
+                    "d.txt": "",
+                },
+                "sub2": {},
+                "build": {
+                    "out": "",
+                },
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
 
     tree.read_with(cx, |tree, _| {
         assert_eq!(
-            tree.entries(true, 0)
-                .map(|entry| (entry.path.as_ref(), entry.is_hidden))
-                .collect::<Vec<_>>(),
-            vec![
-                (rel_path(""), false),
-                (rel_path(".gitignore"), false),
-                (rel_path(".hidden_dir"), false),
-                (rel_path(".hidden_dir/nested.rs"), false),
-                (rel_path(".hidden_file"), false),
-                (rel_path("logs"), false),
-                (rel_path("logs/app.log"), true),
-                (rel_path("logs/debug.log"), true),
-                (rel_path("src"), false),
-                (rel_path("src/visible.rs"), false),
-                (rel_path("visible.txt"), false),
-            ]
+            tree.dir_child_counts(rel_path("dir")),
+            Some(DirCounts {
+                files: 3,
+                dirs: 3,
+                ignored: 2,
+            })
+        );
+        assert_eq!(
+            tree.dir_child_counts(rel_path("")),
+            Some(DirCounts {
+                files: 1,
+                dirs: 1,
+                ignored: 0,
+            })
+        );
+        assert_eq!(
+            tree.dir_child_counts(rel_path("dir/sub2")),
+            Some(DirCounts::default())
         );
+        assert_eq!(tree.dir_child_counts(rel_path("dir/a.txt")), None);
+        assert_eq!(tree.dir_child_counts(rel_path("missing")), None);
     });
 }
 
 #[gpui::test]
-async fn test_fs_events_in_exclusions(cx: &mut TestAppContext) {
+async fn test_set_additional_ignores(cx: &mut TestAppContext) {
     init_test(cx);
-    cx.executor().allow_parking();
-    let dir = TempTree::new(json!({
-        ".git": {
-            "HEAD": "ref: refs/heads/main\n",
-            "foo": "bar",
-        },
-        ".gitignore": "**/target\n/node_modules\ntest_output\n",
-        "target": {
-            "index": "blah2"
-        },
-        "node_modules": {
-            ".DS_Store": "",
-            "prettier": {
-                "package.json": "{}",
-            },
-        },
-        "src": {
-            ".DS_Store": "",
-            "foo": {
-                "foo.rs": "mod another;\n",
-                "another.rs": "// another",
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "build": {
+                "output.txt": "",
             },
-            "bar": {
-                "bar.rs": "// bar",
+            "src": {
+                "main.rs": "",
             },
-            "lib.rs": "mod foo;\nmod bar;\n",
-        },
-        ".DS_Store": "",
-    }));
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings(cx, |settings| {
-                settings.project.worktree.file_scan_exclusions = Some(vec![
-                    "**/.git".to_string(),
-                    "node_modules/".to_string(),
-                    "build_output".to_string(),
-                ]);
-            });
-        });
-    });
+        }),
+    )
+    .await;
 
     let tree = Worktree::local(
-        dir.path(),
+        Path::new("/root"),
         true,
-        Arc::new(RealFs::new(None, cx.executor())),
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
@@ -1215,110 +1250,3997 @@ async fn test_fs_events_in_exclusions(cx: &mut TestAppContext) {
     .unwrap();
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
-    tree.flush_fs_events(cx).await;
+
     tree.read_with(cx, |tree, _| {
-        check_worktree_entries(
-            tree,
-            &[
-                ".git/HEAD",
-                ".git/foo",
-                "node_modules",
-                "node_modules/.DS_Store",
-                "node_modules/prettier",
-                "node_modules/prettier/package.json",
-            ],
-            &["target"],
-            &[
-                ".DS_Store",
-                "src/.DS_Store",
-                "src/lib.rs",
-                "src/foo/foo.rs",
-                "src/foo/another.rs",
-                "src/bar/bar.rs",
-                ".gitignore",
-            ],
-            &[],
+        assert!(!tree.entry_for_path(rel_path("build")).unwrap().is_ignored);
+        assert!(
+            !tree
+                .entry_for_path(rel_path("build/output.txt"))
+                .unwrap()
+                .is_ignored
+        );
+    });
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local_mut()
+            .unwrap()
+            .set_additional_ignores(&["build/"], cx)
+    })
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("build")).unwrap().is_ignored);
+        for entry in tree.entries(true, 0) {
+            assert_eq!(
+                entry.is_ignored,
+                entry.path.starts_with(rel_path("build")),
+                "{:?}",
+                entry.path
+            );
+        }
+    });
+}
+
+#[gpui::test]
+async fn test_semantic_eq(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ignored\n",
+            "a": {
+                "b.txt": "",
+            },
+            "ignored": {
+                "c.txt": "",
+            },
+            "d.txt": "",
+        }),
+    )
+    .await;
+
+    let mut snapshots = Vec::new();
+    for _ in 0..2 {
+        let tree = Worktree::local(
+            Path::new("/root"),
+            true,
+            fs.clone(),
+            Default::default(),
+            true,
+            &mut cx.to_async(),
         )
+        .await
+        .unwrap();
+        cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+            .await;
+        snapshots.push(tree.read_with(cx, |tree, _| tree.as_local().unwrap().snapshot()));
+    }
+
+    assert!(snapshots[0].semantic_eq(&snapshots[1]));
+
+    fs.insert_file("/root/e.txt", Vec::new()).await;
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    let changed_snapshot = tree.read_with(cx, |tree, _| tree.as_local().unwrap().snapshot());
+    assert!(!snapshots[0].semantic_eq(&changed_snapshot));
+}
+
+#[gpui::test]
+async fn test_nearest_gitignore(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "*.log\n",
+            "top.txt": "",
+            "a": {
+                ".gitignore": "*.tmp\n",
+                "b": {
+                    "c": {
+                        "deep.txt": "",
+                    },
+                },
+            },
+            "d": {
+                "e.txt": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        for (path, expected_gitignore) in [
+            ("a/b/c/deep.txt", Some("a/.gitignore")),
+            ("a/b", Some("a/.gitignore")),
+            ("a/.gitignore", Some(".gitignore")),
+            ("a", Some(".gitignore")),
+            ("d/e.txt", Some(".gitignore")),
+            ("top.txt", Some(".gitignore")),
+            ("", None),
+        ] {
+            assert_eq!(
+                tree.nearest_gitignore(rel_path(path))
+                    .map(|entry| entry.path.as_ref()),
+                expected_gitignore.map(rel_path),
+                "{path:?}"
+            );
+        }
     });
+}
 
-    let new_excluded_dir = dir.path().join("build_output");
-    let new_ignored_dir = dir.path().join("test_output");
-    std::fs::create_dir_all(&new_excluded_dir)
-        .unwrap_or_else(|e| panic!("Failed to create a {new_excluded_dir:?} directory: {e}"));
-    std::fs::create_dir_all(&new_ignored_dir)
-        .unwrap_or_else(|e| panic!("Failed to create a {new_ignored_dir:?} directory: {e}"));
-    let node_modules_dir = dir.path().join("node_modules");
-    let dot_git_dir = dir.path().join(".git");
-    let src_dir = dir.path().join("src");
-    for existing_dir in [&node_modules_dir, &dot_git_dir, &src_dir] {
-        assert!(
-            existing_dir.is_dir(),
-            "Expect {existing_dir:?} to be present in the FS already"
+#[gpui::test]
+async fn test_common_ancestor(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "b": {
+                    "c1.txt": "",
+                },
+                "d": {
+                    "e2.txt": "",
+                },
+            },
+            "f.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.common_ancestor(&[rel_path("a/b/c1.txt"), rel_path("a/d/e2.txt")]),
+            Some(rel_path("a").into())
+        );
+        assert_eq!(
+            tree.common_ancestor(&[rel_path("a/b/c1.txt")]),
+            Some(rel_path("a/b").into())
         );
+        assert_eq!(
+            tree.common_ancestor(&[rel_path("a/b"), rel_path("a/b/c1.txt")]),
+            Some(rel_path("a/b").into())
+        );
+        assert_eq!(
+            tree.common_ancestor(&[rel_path("a/b/c1.txt"), rel_path("f.txt")]),
+            Some(rel_path("").into())
+        );
+        assert_eq!(tree.common_ancestor(&[]), None);
+    });
+}
+
+#[gpui::test]
+async fn test_entries_modified_after(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ignored.txt\n",
+            "a.txt": "",
+            "b.txt": "",
+            "dir": {
+                "c.txt": "",
+                "d.txt": "",
+            },
+            "ignored.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let since = UNIX_EPOCH + Duration::from_secs(1000);
+    fs.set_next_mtime(since + Duration::from_secs(1));
+    for path in ["b.txt", "dir/c.txt", "ignored.txt"] {
+        tree.update(cx, |tree, cx| {
+            let local = tree.as_local().unwrap();
+            let entry_id = local.entry_for_path(rel_path(path)).unwrap().id;
+            local.touch_entry(entry_id, cx)
+        })
+        .await
+        .unwrap();
     }
 
-    for directory_for_new_file in [
-        new_excluded_dir,
-        new_ignored_dir,
-        node_modules_dir,
-        dot_git_dir,
-        src_dir,
-    ] {
-        std::fs::write(directory_for_new_file.join("new_file"), "new file contents")
-            .unwrap_or_else(|e| {
-                panic!("Failed to create in {directory_for_new_file:?} a new file: {e}")
-            });
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries_modified_after(since, false)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [rel_path("b.txt"), rel_path("dir/c.txt")]
+        );
+        assert_eq!(
+            tree.entries_modified_after(since, true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [
+                rel_path("b.txt"),
+                rel_path("dir/c.txt"),
+                rel_path("ignored.txt")
+            ]
+        );
+        assert_eq!(
+            tree.entries_modified_after(SystemTime::now(), true).count(),
+            0
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_newest_entries(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ignored.txt\n",
+            "a.txt": "",
+            "b.txt": "",
+            "dir": {
+                "c.txt": "",
+                "d.txt": "",
+            },
+            "ignored.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let start = UNIX_EPOCH + Duration::from_secs(1000);
+    for (seconds, path) in (1..).zip(["dir/c.txt", "a.txt", "dir/d.txt", "b.txt", "ignored.txt"]) {
+        fs.set_next_mtime(start + Duration::from_secs(seconds));
+        tree.update(cx, |tree, cx| {
+            let local = tree.as_local().unwrap();
+            let entry_id = local.entry_for_path(rel_path(path)).unwrap().id;
+            local.touch_entry(entry_id, cx)
+        })
+        .await
+        .unwrap();
     }
-    tree.flush_fs_events(cx).await;
 
     tree.read_with(cx, |tree, _| {
-        check_worktree_entries(
-            tree,
-            &[
-                ".git/HEAD",
-                ".git/foo",
-                ".git/new_file",
-                "node_modules",
-                "node_modules/.DS_Store",
-                "node_modules/prettier",
-                "node_modules/prettier/package.json",
-                "node_modules/new_file",
-                "build_output",
-                "build_output/new_file",
-                "test_output/new_file",
-            ],
-            &["target", "test_output"],
-            &[
-                ".DS_Store",
-                "src/.DS_Store",
-                "src/lib.rs",
-                "src/foo/foo.rs",
-                "src/foo/another.rs",
-                "src/bar/bar.rs",
-                "src/new_file",
-                ".gitignore",
-            ],
-            &[],
-        )
+        let newest = |n, include_ignored| {
+            tree.newest_entries(n, include_ignored)
+                .into_iter()
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            newest(3, false),
+            [rel_path("b.txt"), rel_path("dir/d.txt"), rel_path("a.txt")]
+        );
+        assert_eq!(
+            newest(3, true),
+            [
+                rel_path("ignored.txt"),
+                rel_path("b.txt"),
+                rel_path("dir/d.txt")
+            ]
+        );
+        assert_eq!(newest(10, false).len(), 5);
+        assert!(newest(0, true).is_empty());
+    });
+}
+
+#[gpui::test]
+async fn test_is_empty(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({})).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| assert!(tree.is_empty()));
+
+    fs.create_file("/root/a.txt".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| assert!(!tree.is_empty()));
+}
+
+#[gpui::test]
+async fn test_entry_relative_path_str(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            "a": {
+                "b": {
+                    "c.txt": "",
+                },
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new(path!("/root")),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let mut entry = tree.read_with(cx, |tree, _| {
+        assert_eq!(tree.root_entry().unwrap().relative_path_str(), "");
+        tree.entry_for_path(rel_path("a/b/c.txt")).unwrap().clone()
+    });
+    assert_eq!(entry.relative_path_str(), "a/b/c.txt");
+
+    entry.path = RelPath::new(Path::new("x\\y\\z.txt"), PathStyle::Windows)
+        .unwrap()
+        .into_arc();
+    assert_eq!(entry.relative_path_str(), "x/y/z.txt");
+}
+
+#[gpui::test]
+async fn test_deepest_common_directory(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "b": {
+                    "c.txt": "",
+                    "d": {
+                        "e.txt": "",
+                    },
+                    "bc": {
+                        "f.txt": "",
+                    },
+                },
+                "empty": {},
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(tree.deepest_common_directory().as_ref(), rel_path("a/b"));
+    });
+
+    fs.create_file("/root/a/g.txt".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(tree.deepest_common_directory().as_ref(), rel_path("a"));
+    });
+}
+
+#[gpui::test]
+async fn test_entries_with_extension(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "target\n",
+            ".rs": "",
+            "main.rs": "",
+            "README.md": "",
+            "src": {
+                "lib.rs": "",
+                "lib.rs.bak": "",
+                "data.json": "",
+                "rs": "",
+                "nested.rs": {
+                    "mod.rs": "",
+                },
+            },
+            "target": {
+                "generated.rs": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries_with_extension("rs", false)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [
+                rel_path("main.rs"),
+                rel_path("src/lib.rs"),
+                rel_path("src/nested.rs/mod.rs"),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_entries_named(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "generated/mod.rs\n",
+            "mod.rs": "",
+            "lib.rs": "",
+            "a": {
+                "mod.rs": "",
+                "b": {
+                    "mod.rs": {},
+                },
+            },
+            "generated": {
+                "mod.rs": "",
+            },
+            "not_mod.rs": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries_named("mod.rs", false)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [
+                rel_path("a/b/mod.rs"),
+                rel_path("a/mod.rs"),
+                rel_path("mod.rs"),
+            ]
+        );
+        assert_eq!(
+            tree.entries_named("mod.rs", true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [
+                rel_path("a/b/mod.rs"),
+                rel_path("a/mod.rs"),
+                rel_path("generated/mod.rs"),
+                rel_path("mod.rs"),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_directories(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ignored/\n",
+            "a": {
+                "a1.txt": "",
+                "b": {
+                    "c": {
+                        "c1.txt": "",
+                    },
+                },
+            },
+            "d": {},
+            "e.txt": "",
+            "ignored": {
+                "f": {},
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.directories(false, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [
+                rel_path(""),
+                rel_path("a"),
+                rel_path("a/b"),
+                rel_path("a/b/c"),
+                rel_path("d"),
+            ]
+        );
+        assert_eq!(
+            tree.directories(true, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [
+                rel_path(""),
+                rel_path("a"),
+                rel_path("a/b"),
+                rel_path("a/b/c"),
+                rel_path("d"),
+                rel_path("ignored"),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_walk(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "a1.txt": "",
+                "b": {
+                    "b1.txt": "",
+                    "c": {
+                        "c1.txt": "",
+                    },
+                },
+                "a2.txt": "",
+            },
+            "d": {
+                "d1.txt": "",
+            },
+            "e.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        let mut visited = Vec::new();
+        tree.walk(false, |entry| {
+            visited.push(entry.path.clone());
+            if entry.path.as_ref() == rel_path("a/b") {
+                WalkAction::SkipSubtree
+            } else {
+                WalkAction::Continue
+            }
+        });
+        assert_eq!(
+            visited.iter().map(|path| path.as_ref()).collect::<Vec<_>>(),
+            [
+                rel_path(""),
+                rel_path("a"),
+                rel_path("a/a1.txt"),
+                rel_path("a/a2.txt"),
+                rel_path("a/b"),
+                rel_path("d"),
+                rel_path("d/d1.txt"),
+                rel_path("e.txt"),
+            ]
+        );
+
+        let mut visited = Vec::new();
+        tree.walk(false, |entry| {
+            visited.push(entry.path.clone());
+            if entry.path.as_ref() == rel_path("a") {
+                WalkAction::SkipSubtree
+            } else if entry.path.as_ref() == rel_path("d") {
+                WalkAction::Stop
+            } else {
+                WalkAction::Continue
+            }
+        });
+        assert_eq!(
+            visited.iter().map(|path| path.as_ref()).collect::<Vec<_>>(),
+            [rel_path(""), rel_path("a"), rel_path("d")]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_settle(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    tree.update(cx, |tree, cx| tree.as_local().unwrap().settle(cx))
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("a.txt")).is_some());
+        assert!(tree.entry_for_path(rel_path("b.txt")).is_none());
+    });
+
+    fs.insert_file("/root/b.txt", Vec::new()).await;
+    tree.update(cx, |tree, cx| tree.as_local().unwrap().settle(cx))
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("b.txt")).is_some());
+    });
+}
+
+#[gpui::test]
+async fn test_contains_path(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ignored\n",
+            "tracked.txt": "",
+            "ignored": {
+                "file.txt": "",
+            },
+            "dir": {
+                "nested.txt": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        for (path, exists, is_ignored) in [
+            ("", true, false),
+            ("tracked.txt", true, false),
+            ("dir", true, false),
+            ("dir/nested.txt", true, false),
+            ("ignored", true, true),
+            ("absent.txt", false, false),
+            ("dir/absent.txt", false, false),
+            ("tracked.txt/absent", false, false),
+        ] {
+            let path = rel_path(path);
+            assert_eq!(tree.entry_for_path(path).is_some(), exists, "{path:?}");
+            assert_eq!(tree.contains_path(path, true), exists, "{path:?}");
+            assert_eq!(
+                tree.contains_path(path, false),
+                exists && !is_ignored,
+                "{path:?}"
+            );
+        }
+    });
+}
+
+#[gpui::test]
+async fn test_absolutize_and_relativize(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            "dir": {
+                "a.txt": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new(path!("/root")),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.absolutize(rel_path("dir/a.txt")),
+            PathBuf::from(path!("/root/dir/a.txt"))
+        );
+        assert_eq!(tree.absolutize(rel_path("")), PathBuf::from(path!("/root")));
+
+        assert_eq!(
+            tree.relativize(Path::new(path!("/root/dir/a.txt"))),
+            Some(rel_path("dir/a.txt").into())
+        );
+        assert_eq!(
+            tree.relativize(Path::new(path!("/root"))),
+            Some(rel_path("").into())
+        );
+        assert_eq!(tree.relativize(Path::new(path!("/other/a.txt"))), None);
+        assert_eq!(tree.relativize(Path::new(path!("/root2/a.txt"))), None);
+    });
+}
+
+#[gpui::test]
+async fn test_sorted_entries(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "c": {},
+            },
+            "e": {},
+        }),
+    )
+    .await;
+    fs.insert_file("/root/b.txt", b"bb".to_vec()).await;
+    fs.insert_file("/root/a/z.txt", b"zzzz".to_vec()).await;
+    fs.insert_file("/root/0.txt", b"0".to_vec()).await;
+    fs.insert_file("/root/a/c/d.txt", b"ddd".to_vec()).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        let sorted_paths = |order, files_only: bool| {
+            tree.sorted_entries(true, order)
+                .filter(|entry| !files_only || entry.is_file())
+                .map(|entry| entry.path.as_unix_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            sorted_paths(EntrySortOrder::PathAsc, false),
+            vec![
+                "",
+                "0.txt",
+                "a",
+                "a/c",
+                "a/c/d.txt",
+                "a/z.txt",
+                "b.txt",
+                "e"
+            ]
+        );
+        assert_eq!(
+            sorted_paths(EntrySortOrder::DirsFirst, false),
+            vec![
+                "",
+                "a",
+                "a/c",
+                "a/c/d.txt",
+                "a/z.txt",
+                "e",
+                "0.txt",
+                "b.txt"
+            ]
+        );
+        assert_eq!(
+            sorted_paths(EntrySortOrder::MtimeDesc, true),
+            vec!["a/c/d.txt", "0.txt", "a/z.txt", "b.txt"]
+        );
+        assert_eq!(
+            sorted_paths(EntrySortOrder::SizeDesc, true),
+            vec!["a/z.txt", "a/c/d.txt", "b.txt", "0.txt"]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_estimate(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions =
+                    Some(vec!["**/excluded".to_string()]);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "node_modules\n",
+            "excluded": {
+                "e1.js": "",
+                "e2.js": "",
+            },
+            "one": {
+                "node_modules": {
+                    "a": {
+                        "a1.js": "a1",
+                        "a2.js": "a2",
+                    },
+                    "b": {
+                        "b1.js": "b1",
+                        "b2.js": "b2",
+                    },
+                    "c": {
+                        "c1.js": "c1",
+                        "c2.js": "c2",
+                    }
+                },
+            },
+            "two": {
+                "x.js": "",
+                "y.js": "",
+            },
+        }),
+    )
+    .await;
+
+    let estimate = cx
+        .update(|cx| Worktree::estimate(Path::new("/root").into(), fs.clone(), cx))
+        .await;
+    assert_eq!(estimate.total, 9);
+    assert_eq!(estimate.ignored, 6);
+    assert_eq!(
+        estimate.largest_dirs[..3],
+        [
+            (PathBuf::from("one"), 6),
+            (PathBuf::from("one/node_modules"), 6),
+            (PathBuf::from("one/node_modules/a"), 2),
+        ]
+    );
+    assert_eq!(fs.read_dir_call_count(), 7);
+}
+
+#[gpui::test]
+async fn test_open_gitignored_files(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "node_modules\n",
+            "one": {
+                "node_modules": {
+                    "a": {
+                        "a1.js": "a1",
+                        "a2.js": "a2",
+                    },
+                    "b": {
+                        "b1.js": "b1",
+                        "b2.js": "b2",
+                    },
+                    "c": {
+                        "c1.js": "c1",
+                        "c2.js": "c2",
+                    }
+                },
+            },
+            "two": {
+                "x.js": "",
+                "y.js": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
+                .collect::<Vec<_>>(),
+            vec![
+                (rel_path(""), false),
+                (rel_path(".gitignore"), false),
+                (rel_path("one"), false),
+                (rel_path("one/node_modules"), true),
+                (rel_path("two"), false),
+                (rel_path("two/x.js"), false),
+                (rel_path("two/y.js"), false),
+            ]
+        );
+    });
+
+    // Open a file that is nested inside of a gitignored directory that
+    // has not yet been expanded.
+    let prev_read_dir_count = fs.read_dir_call_count();
+    let loaded = tree
+        .update(cx, |tree, cx| {
+            tree.load_file(rel_path("one/node_modules/b/b1.js"), cx)
+        })
+        .await
+        .unwrap();
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
+                .collect::<Vec<_>>(),
+            vec![
+                (rel_path(""), false),
+                (rel_path(".gitignore"), false),
+                (rel_path("one"), false),
+                (rel_path("one/node_modules"), true),
+                (rel_path("one/node_modules/a"), true),
+                (rel_path("one/node_modules/b"), true),
+                (rel_path("one/node_modules/b/b1.js"), true),
+                (rel_path("one/node_modules/b/b2.js"), true),
+                (rel_path("one/node_modules/c"), true),
+                (rel_path("two"), false),
+                (rel_path("two/x.js"), false),
+                (rel_path("two/y.js"), false),
+            ]
+        );
+
+        assert_eq!(
+            loaded.file.path.as_ref(),
+            rel_path("one/node_modules/b/b1.js")
+        );
+
+        // Only the newly-expanded directories are scanned.
+        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 2);
+    });
+
+    // Open another file in a different subdirectory of the same
+    // gitignored directory.
+    let prev_read_dir_count = fs.read_dir_call_count();
+    let loaded = tree
+        .update(cx, |tree, cx| {
+            tree.load_file(rel_path("one/node_modules/a/a2.js"), cx)
+        })
+        .await
+        .unwrap();
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
+                .collect::<Vec<_>>(),
+            vec![
+                (rel_path(""), false),
+                (rel_path(".gitignore"), false),
+                (rel_path("one"), false),
+                (rel_path("one/node_modules"), true),
+                (rel_path("one/node_modules/a"), true),
+                (rel_path("one/node_modules/a/a1.js"), true),
+                (rel_path("one/node_modules/a/a2.js"), true),
+                (rel_path("one/node_modules/b"), true),
+                (rel_path("one/node_modules/b/b1.js"), true),
+                (rel_path("one/node_modules/b/b2.js"), true),
+                (rel_path("one/node_modules/c"), true),
+                (rel_path("two"), false),
+                (rel_path("two/x.js"), false),
+                (rel_path("two/y.js"), false),
+            ]
+        );
+
+        assert_eq!(
+            loaded.file.path.as_ref(),
+            rel_path("one/node_modules/a/a2.js")
+        );
+
+        // Only the newly-expanded directory is scanned.
+        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 1);
+    });
+
+    let path = PathBuf::from("/root/one/node_modules/c/lib");
+
+    // No work happens when files and directories change within an unloaded directory.
+    let prev_fs_call_count = fs.read_dir_call_count() + fs.metadata_call_count();
+    // When we open a directory, we check each ancestor whether it's a git
+    // repository. That means we have an fs.metadata call per ancestor that we
+    // need to subtract here.
+    let ancestors = path.ancestors().count();
+
+    fs.create_dir(path.as_ref()).await.unwrap();
+    cx.executor().run_until_parked();
+
+    assert_eq!(
+        fs.read_dir_call_count() + fs.metadata_call_count() - prev_fs_call_count - ancestors,
+        0
+    );
+}
+
+#[gpui::test]
+async fn test_ensure_entry_in_gitignored_dir(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "node_modules\n",
+            "node_modules": {
+                "a": {
+                    "lib": {
+                        "index.js": "a",
+                    },
+                    "package.json": "",
+                },
+                "b": {
+                    "index.js": "b",
+                },
+            },
+            "src": {
+                "main.js": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("node_modules/a")).is_none());
+    });
+
+    let prev_read_dir_count = fs.read_dir_call_count();
+    let entry = tree
+        .update(cx, |tree, cx| {
+            tree.as_local()
+                .unwrap()
+                .ensure_entry(rel_path("node_modules/a/lib/index.js"), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(entry.path.as_ref(), rel_path("node_modules/a/lib/index.js"));
+    assert!(entry.is_file());
+    assert!(entry.is_ignored);
+
+    // Only the ancestors of the requested path are scanned, and the file itself is not read.
+    assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 3);
+    assert_eq!(
+        fs.read_count_for_path("/root/node_modules/a/lib/index.js"),
+        0
+    );
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entry_for_path(rel_path("node_modules/b"))
+                .unwrap()
+                .kind,
+            EntryKind::UnloadedDir
+        );
+        assert!(
+            tree.entry_for_path(rel_path("node_modules/b/index.js"))
+                .is_none()
+        );
+    });
+
+    // Requesting a loaded entry does not scan anything else.
+    let prev_read_dir_count = fs.read_dir_call_count();
+    tree.update(cx, |tree, cx| {
+        tree.as_local()
+            .unwrap()
+            .ensure_entry(rel_path("node_modules/a/package.json"), cx)
+    })
+    .await
+    .unwrap();
+    assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 0);
+}
+
+#[gpui::test]
+async fn test_dirs_no_longer_ignored(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "node_modules\n",
+            "a": {
+                "a.js": "",
+            },
+            "b": {
+                "b.js": "",
+            },
+            "node_modules": {
+                "c": {
+                    "c.js": "",
+                },
+                "d": {
+                    "d.js": "",
+                    "e": {
+                        "e1.js": "",
+                        "e2.js": "",
+                    },
+                    "f": {
+                        "f1.js": "",
+                        "f2.js": "",
+                    }
+                },
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // Open a file within the gitignored directory, forcing some of its
+    // subdirectories to be read, but not all.
+    let read_dir_count_1 = fs.read_dir_call_count();
+    tree.read_with(cx, |tree, _| {
+        tree.as_local()
+            .unwrap()
+            .refresh_entries_for_paths(vec![rel_path("node_modules/d/d.js").into()])
+    })
+    .recv()
+    .await;
+
+    // Those subdirectories are now loaded.
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|e| (e.path.as_ref(), e.is_ignored))
+                .collect::<Vec<_>>(),
+            &[
+                (rel_path(""), false),
+                (rel_path(".gitignore"), false),
+                (rel_path("a"), false),
+                (rel_path("a/a.js"), false),
+                (rel_path("b"), false),
+                (rel_path("b/b.js"), false),
+                (rel_path("node_modules"), true),
+                (rel_path("node_modules/c"), true),
+                (rel_path("node_modules/d"), true),
+                (rel_path("node_modules/d/d.js"), true),
+                (rel_path("node_modules/d/e"), true),
+                (rel_path("node_modules/d/f"), true),
+            ]
+        );
+    });
+    let read_dir_count_2 = fs.read_dir_call_count();
+    assert_eq!(read_dir_count_2 - read_dir_count_1, 2);
+
+    // Update the gitignore so that node_modules is no longer ignored,
+    // but a subdirectory is ignored
+    fs.save("/root/.gitignore".as_ref(), &"e".into(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    // All of the directories that are no longer ignored are now loaded.
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|e| (e.path.as_ref(), e.is_ignored))
+                .collect::<Vec<_>>(),
+            &[
+                (rel_path(""), false),
+                (rel_path(".gitignore"), false),
+                (rel_path("a"), false),
+                (rel_path("a/a.js"), false),
+                (rel_path("b"), false),
+                (rel_path("b/b.js"), false),
+                // This directory is no longer ignored
+                (rel_path("node_modules"), false),
+                (rel_path("node_modules/c"), false),
+                (rel_path("node_modules/c/c.js"), false),
+                (rel_path("node_modules/d"), false),
+                (rel_path("node_modules/d/d.js"), false),
+                // This subdirectory is now ignored
+                (rel_path("node_modules/d/e"), true),
+                (rel_path("node_modules/d/f"), false),
+                (rel_path("node_modules/d/f/f1.js"), false),
+                (rel_path("node_modules/d/f/f2.js"), false),
+            ]
+        );
+    });
+
+    // Each of the newly-loaded directories is scanned only once.
+    let read_dir_count_3 = fs.read_dir_call_count();
+    assert_eq!(read_dir_count_3 - read_dir_count_2, 2);
+}
+
+#[gpui::test]
+async fn test_editing_gitignore_does_not_reread_sibling_gitignores(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "*.log\n",
+            "a": {
+                ".gitignore": "*.tmp\n",
+                "one.tmp": "",
+                "one.txt": "",
+            },
+            "b": {
+                ".gitignore": "*.bak\n",
+                "two.bak": "",
+                "two.txt": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let prev_open_file_count = fs.open_file_call_count();
+    let prev_bytes_read_count = fs.bytes_read_count();
+    let prev_root_gitignore_reads = fs.read_count_for_path("/root/.gitignore");
+    let prev_sibling_gitignore_reads = fs.read_count_for_path("/root/b/.gitignore");
+    let prev_edited_gitignore_reads = fs.read_count_for_path("/root/a/.gitignore");
+
+    fs.write(Path::new("/root/a/.gitignore"), b"*.txt\n")
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("a/one.txt"))
+                .unwrap()
+                .is_ignored
+        );
+        assert!(
+            !tree
+                .entry_for_path(rel_path("a/one.tmp"))
+                .unwrap()
+                .is_ignored
+        );
+        assert!(
+            tree.entry_for_path(rel_path("b/two.bak"))
+                .unwrap()
+                .is_ignored
+        );
+    });
+    assert!(fs.read_count_for_path("/root/a/.gitignore") > prev_edited_gitignore_reads);
+    assert!(fs.open_file_call_count() > prev_open_file_count);
+    assert!(fs.bytes_read_count() > prev_bytes_read_count);
+    assert_eq!(
+        fs.read_count_for_path("/root/.gitignore"),
+        prev_root_gitignore_reads
+    );
+    assert_eq!(
+        fs.read_count_for_path("/root/b/.gitignore"),
+        prev_sibling_gitignore_reads
+    );
+}
+
+#[gpui::test]
+async fn test_reload_gitignores(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "*.log\n",
+            "a": {
+                ".gitignore": "*.tmp\n",
+                "one.tmp": "",
+                "one.txt": "",
+            },
+            "two.log": "",
+            "two.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    fs.pause_events();
+    fs.write(Path::new("/root/.gitignore"), b"*.txt\n")
+        .await
+        .unwrap();
+    fs.write(Path::new("/root/a/.gitignore"), b"")
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("two.log")).unwrap().is_ignored);
+        assert!(!tree.entry_for_path(rel_path("two.txt")).unwrap().is_ignored);
+        assert!(
+            tree.entry_for_path(rel_path("a/one.tmp"))
+                .unwrap()
+                .is_ignored
+        );
+    });
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local().unwrap().reload_gitignores(cx)
+    })
+    .await
+    .unwrap();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(!tree.entry_for_path(rel_path("two.log")).unwrap().is_ignored);
+        assert!(tree.entry_for_path(rel_path("two.txt")).unwrap().is_ignored);
+        assert!(
+            tree.entry_for_path(rel_path("a/one.txt"))
+                .unwrap()
+                .is_ignored
+        );
+        assert!(
+            !tree
+                .entry_for_path(rel_path("a/one.tmp"))
+                .unwrap()
+                .is_ignored
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_write_file(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = TempTree::new(json!({
+        ".git": {},
+        ".gitignore": "ignored-dir\n",
+        "tracked-dir": {},
+        "ignored-dir": {}
+    }));
+
+    let worktree = Worktree::local(
+        dir.path(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    #[cfg(not(target_os = "macos"))]
+    fs::fs_watcher::global(|_| {}).unwrap();
+
+    cx.read(|cx| worktree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    worktree.flush_fs_events(cx).await;
+
+    worktree
+        .update(cx, |tree, cx| {
+            tree.write_file(
+                rel_path("tracked-dir/file.txt").into(),
+                "hello".into(),
+                Default::default(),
+                encoding_rs::UTF_8,
+                false,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    worktree
+        .update(cx, |tree, cx| {
+            tree.write_file(
+                rel_path("ignored-dir/file.txt").into(),
+                "world".into(),
+                Default::default(),
+                encoding_rs::UTF_8,
+                false,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    worktree.read_with(cx, |tree, _| {
+        let tracked = tree
+            .entry_for_path(rel_path("tracked-dir/file.txt"))
+            .unwrap();
+        let ignored = tree
+            .entry_for_path(rel_path("ignored-dir/file.txt"))
+            .unwrap();
+        assert!(!tracked.is_ignored);
+        assert!(ignored.is_ignored);
+    });
+}
+
+#[gpui::test]
+async fn test_write_file_creating_parents(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "existing": {} })).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let (file, created_parents) = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().write_file_creating_parents(
+                rel_path("existing/new/dir/file.txt").into(),
+                "hello".into(),
+                Default::default(),
+                encoding_rs::UTF_8,
+                false,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(file.path.as_ref(), rel_path("existing/new/dir/file.txt"));
+    assert_eq!(
+        created_parents
+            .iter()
+            .map(|entry| entry.path.as_ref())
+            .collect::<Vec<_>>(),
+        [rel_path("existing/new"), rel_path("existing/new/dir")]
+    );
+    assert!(created_parents.iter().all(|entry| entry.is_dir()));
+
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("existing/new/dir/file.txt"))
+                .unwrap()
+                .is_file()
+        );
+        assert!(
+            tree.entry_for_path(rel_path("existing/new/dir"))
+                .unwrap()
+                .is_dir()
+        );
+        assert!(
+            tree.entry_for_path(rel_path("existing/new"))
+                .unwrap()
+                .is_dir()
+        );
+    });
+    assert_eq!(
+        fs.load(Path::new("/root/existing/new/dir/file.txt"))
+            .await
+            .unwrap(),
+        "hello"
+    );
+
+    let (_, created_parents) = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().write_file_creating_parents(
+                rel_path("existing/new/dir/other.txt").into(),
+                "world".into(),
+                Default::default(),
+                encoding_rs::UTF_8,
+                false,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert!(created_parents.is_empty());
+}
+
+#[gpui::test]
+async fn test_file_scan_inclusions(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = TempTree::new(json!({
+        ".gitignore": "**/target\n/node_modules\ntop_level.txt\n",
+        "target": {
+            "index": "blah2"
+        },
+        "node_modules": {
+            ".DS_Store": "",
+            "prettier": {
+                "package.json": "{}",
+            },
+            "package.json": "//package.json"
+        },
+        "src": {
+            ".DS_Store": "",
+            "foo": {
+                "foo.rs": "mod another;\n",
+                "another.rs": "// another",
+            },
+            "bar": {
+                "bar.rs": "// bar",
+            },
+            "lib.rs": "mod foo;\nmod bar;\n",
+        },
+        "top_level.txt": "top level file",
+        ".DS_Store": "",
+    }));
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions = Some(vec![]);
+                settings.project.worktree.file_scan_inclusions = Some(vec![
+                    "node_modules/**/package.json".to_string(),
+                    "**/.DS_Store".to_string(),
+                ]);
+            });
+        });
+    });
+
+    let tree = Worktree::local(
+        dir.path(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+    tree.read_with(cx, |tree, _| {
+        // Assert that file_scan_inclusions overrides  file_scan_exclusions.
+        check_worktree_entries(
+            tree,
+            &[],
+            &["target", "node_modules"],
+            &["src/lib.rs", "src/bar/bar.rs", ".gitignore"],
+            &[
+                "node_modules/prettier/package.json",
+                ".DS_Store",
+                "node_modules/.DS_Store",
+                "src/.DS_Store",
+            ],
+        )
+    });
+}
+
+#[gpui::test]
+async fn test_file_scan_exclusions_overrules_inclusions(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = TempTree::new(json!({
+        ".gitignore": "**/target\n/node_modules\n",
+        "target": {
+            "index": "blah2"
+        },
+        "node_modules": {
+            ".DS_Store": "",
+            "prettier": {
+                "package.json": "{}",
+            },
+        },
+        "src": {
+            ".DS_Store": "",
+            "foo": {
+                "foo.rs": "mod another;\n",
+                "another.rs": "// another",
+            },
+        },
+        ".DS_Store": "",
+    }));
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions =
+                    Some(vec!["**/.DS_Store".to_string()]);
+                settings.project.worktree.file_scan_inclusions =
+                    Some(vec!["**/.DS_Store".to_string()]);
+            });
+        });
+    });
+
+    let tree = Worktree::local(
+        dir.path(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+    tree.read_with(cx, |tree, _| {
+        // Assert that file_scan_inclusions overrides  file_scan_exclusions.
+        check_worktree_entries(
+            tree,
+            &[".DS_Store, src/.DS_Store"],
+            &["target", "node_modules"],
+            &["src/foo/another.rs", "src/foo/foo.rs", ".gitignore"],
+            &[],
+        )
+    });
+}
+
+#[gpui::test]
+async fn test_explain_path(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "target\n*.log\n",
+            "debug.log": "",
+            "target": {
+                "index": "",
+            },
+            "src": {
+                ".DS_Store": "",
+                "a.rs": "",
+            },
+        }),
+    )
+    .await;
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions =
+                    Some(vec!["**/.DS_Store".to_string()]);
+                settings.project.worktree.file_scan_inclusions =
+                    Some(vec!["**/.DS_Store".to_string(), "target/index".to_string()]);
+            });
+        });
+    });
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        let tree = tree.as_local().unwrap();
+
+        let explanation = tree.explain_path(rel_path("src/.DS_Store"));
+        assert_eq!(
+            explanation.decision,
+            PathDecision::Excluded {
+                pattern: "**/.DS_Store".to_string()
+            }
+        );
+        assert_eq!(explanation.entry, None);
+
+        let explanation = tree.explain_path(rel_path("target/index"));
+        assert_eq!(
+            explanation.decision,
+            PathDecision::AlwaysIncluded {
+                pattern: "target/index".to_string()
+            }
+        );
+        let entry = explanation.entry.unwrap();
+        assert!(entry.is_ignored && entry.is_always_included);
+
+        assert_eq!(
+            tree.explain_path(rel_path("debug.log")).decision,
+            PathDecision::Ignored
+        );
+        assert_eq!(
+            tree.explain_path(rel_path("src/a.rs")).decision,
+            PathDecision::Included
+        );
+        assert_eq!(
+            tree.explain_path(rel_path("missing.rs")).decision,
+            PathDecision::NotFound
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_file_scan_inclusions_reindexes_on_setting_change(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = TempTree::new(json!({
+        ".gitignore": "**/target\n/node_modules/\n",
+        "target": {
+            "index": "blah2"
+        },
+        "node_modules": {
+            ".DS_Store": "",
+            "prettier": {
+                "package.json": "{}",
+            },
+        },
+        "src": {
+            ".DS_Store": "",
+            "foo": {
+                "foo.rs": "mod another;\n",
+                "another.rs": "// another",
+            },
+        },
+        ".DS_Store": "",
+    }));
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions = Some(vec![]);
+                settings.project.worktree.file_scan_inclusions =
+                    Some(vec!["node_modules/**".to_string()]);
+            });
+        });
+    });
+    let tree = Worktree::local(
+        dir.path(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("node_modules"))
+                .is_some_and(|f| f.is_always_included)
+        );
+        assert!(
+            tree.entry_for_path(rel_path("node_modules/prettier/package.json"))
+                .is_some_and(|f| f.is_always_included)
+        );
+    });
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions = Some(vec![]);
+                settings.project.worktree.file_scan_inclusions = Some(vec![]);
+            });
+        });
+    });
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("node_modules"))
+                .is_some_and(|f| !f.is_always_included)
+        );
+        assert!(
+            tree.entry_for_path(rel_path("node_modules/prettier/package.json"))
+                .is_some_and(|f| !f.is_always_included)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_scan_dot_git(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions = Some(vec![]);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".git": {
+                "HEAD": "ref: refs/heads/main\n",
+            },
+            "src": {
+                "lib.rs": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("src/lib.rs")).is_some());
+        assert!(tree.entry_for_path(rel_path(".git")).is_some());
+        assert!(tree.entry_for_path(rel_path(".git/HEAD")).is_none());
+    });
+
+    // Inclusions override the default exclusion of `.git` contents.
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_inclusions = Some(vec![".git/HEAD".into()]);
+            });
+        });
+    });
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path(".git/HEAD")).is_some());
+    });
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_inclusions = Some(Vec::new());
+                settings.project.worktree.scan_dot_git = Some(true);
+            });
+        });
+    });
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("src/lib.rs")).is_some());
+        assert!(tree.entry_for_path(rel_path(".git/HEAD")).is_some());
+    });
+}
+
+#[gpui::test]
+async fn test_scan_paused_defers_reindex_on_setting_change(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "/node_modules/\n",
+            "node_modules": {
+                "prettier": {
+                    "package.json": "{}",
+                },
+            },
+            "src": {
+                "main.rs": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("node_modules/prettier/package.json"))
+                .is_none()
+        );
+    });
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local_mut().unwrap().set_scan_paused(true, cx);
+    });
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_inclusions =
+                    Some(vec!["node_modules/**".to_string()]);
+            });
+        });
+    });
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.as_local().unwrap().scan_paused());
+        assert!(
+            tree.entry_for_path(rel_path("node_modules/prettier/package.json"))
+                .is_none()
+        );
+    });
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local_mut().unwrap().set_scan_paused(false, cx);
+    });
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("node_modules/prettier/package.json"))
+                .is_some_and(|entry| entry.is_always_included)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_file_scan_exclusions(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = TempTree::new(json!({
+        ".gitignore": "**/target\n/node_modules\n",
+        "target": {
+            "index": "blah2"
+        },
+        "node_modules": {
+            ".DS_Store": "",
+            "prettier": {
+                "package.json": "{}",
+            },
+        },
+        "src": {
+            ".DS_Store": "",
+            "foo": {
+                "foo.rs": "mod another;\n",
+                "another.rs": "// another",
+            },
+            "bar": {
+                "bar.rs": "// bar",
+            },
+            "lib.rs": "mod foo;\nmod bar;\n",
+        },
+        ".DS_Store": "",
+    }));
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions =
+                    Some(vec!["**/foo/**".to_string(), "**/.DS_Store".to_string()]);
+            });
+        });
+    });
+
+    let tree = Worktree::local(
+        dir.path(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+    tree.read_with(cx, |tree, _| {
+        check_worktree_entries(
+            tree,
+            &[
+                "src/foo/foo.rs",
+                "src/foo/another.rs",
+                "node_modules/.DS_Store",
+                "src/.DS_Store",
+                ".DS_Store",
+            ],
+            &["target", "node_modules"],
+            &["src/lib.rs", "src/bar/bar.rs", ".gitignore"],
+            &[],
+        )
+    });
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions =
+                    Some(vec!["**/node_modules/**".to_string()]);
+            });
+        });
+    });
+    tree.flush_fs_events(cx).await;
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        check_worktree_entries(
+            tree,
+            &[
+                "node_modules/prettier/package.json",
+                "node_modules/.DS_Store",
+                "node_modules",
+            ],
+            &["target"],
+            &[
+                ".gitignore",
+                "src/lib.rs",
+                "src/bar/bar.rs",
+                "src/foo/foo.rs",
+                "src/foo/another.rs",
+                "src/.DS_Store",
+                ".DS_Store",
+            ],
+            &[],
+        )
+    });
+}
+
+#[gpui::test]
+async fn test_invalid_file_scan_exclusions(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions =
+                    Some(vec!["**/foo/**".to_string(), "src/[".to_string()]);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "src": {
+                "foo": {
+                    "foo.rs": "",
+                },
+                "lib.rs": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        let settings_errors = tree.settings_errors();
+        assert_eq!(settings_errors.len(), 1);
+        assert!(settings_errors[0].contains("\"src/[\""));
+        assert!(settings_errors[0].contains("file_scan_exclusions"));
+
+        // The remaining globs still apply.
+        assert!(tree.entry_for_path(rel_path("src/lib.rs")).is_some());
+        assert!(tree.entry_for_path(rel_path("src/foo/foo.rs")).is_none());
+    });
+}
+
+#[gpui::test]
+async fn test_worktree_file_scan_exclusions(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".zed": {
+                "worktree.json": r#"{ "file_scan_exclusions": ["generated"] }"#,
+            },
+            "generated": {
+                "out.rs": "// generated",
+            },
+            "src": {
+                "lib.rs": "// lib",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("generated")).is_none());
+        assert!(tree.entry_for_path(rel_path("generated/out.rs")).is_none());
+        assert!(tree.entry_for_path(rel_path("src/lib.rs")).is_some());
+    });
+
+    fs.remove_file(Path::new("/root/.zed/worktree.json"), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("generated")).is_some());
+        assert!(tree.entry_for_path(rel_path("generated/out.rs")).is_some());
+        assert!(tree.entry_for_path(rel_path("src/lib.rs")).is_some());
+    });
+}
+
+#[gpui::test]
+async fn test_hidden_files(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = TempTree::new(json!({
+        ".gitignore": "**/target\n",
+        ".hidden_file": "content",
+        ".hidden_dir": {
+            "nested.rs": "code",
+        },
+        "src": {
+            "visible.rs": "code",
+        },
+        "logs": {
+            "app.log": "logs",
+            "debug.log": "logs",
+        },
+        "visible.txt": "content",
+    }));
+
+    let tree = Worktree::local(
+        dir.path(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| (entry.path.as_ref(), entry.is_hidden))
+                .collect::<Vec<_>>(),
+            vec![
+                (rel_path(""), false),
+                (rel_path(".gitignore"), true),
+                (rel_path(".hidden_dir"), true),
+                (rel_path(".hidden_dir/nested.rs"), true),
+                (rel_path(".hidden_file"), true),
+                (rel_path("logs"), false),
+                (rel_path("logs/app.log"), false),
+                (rel_path("logs/debug.log"), false),
+                (rel_path("src"), false),
+                (rel_path("src/visible.rs"), false),
+                (rel_path("visible.txt"), false),
+            ]
+        );
+    });
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.hidden_files = Some(vec!["**/*.log".to_string()]);
+            });
+        });
+    });
+    tree.flush_fs_events(cx).await;
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| (entry.path.as_ref(), entry.is_hidden))
+                .collect::<Vec<_>>(),
+            vec![
+                (rel_path(""), false),
+                (rel_path(".gitignore"), false),
+                (rel_path(".hidden_dir"), false),
+                (rel_path(".hidden_dir/nested.rs"), false),
+                (rel_path(".hidden_file"), false),
+                (rel_path("logs"), false),
+                (rel_path("logs/app.log"), true),
+                (rel_path("logs/debug.log"), true),
+                (rel_path("src"), false),
+                (rel_path("src/visible.rs"), false),
+                (rel_path("visible.txt"), false),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_lfs_pointer_files(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.detect_lfs_pointers_during_scan = Some(true);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "model.bin": "version https://git-lfs.github.com/spec/v1\n\
+                oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n\
+                size 12345\n",
+            "notes.txt": "version 2 of the notes\n",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        let is_lfs_pointer = |path| tree.entry_for_path(rel_path(path)).unwrap().is_lfs_pointer;
+        assert_eq!(is_lfs_pointer("model.bin"), Some(true));
+        assert_eq!(is_lfs_pointer("notes.txt"), Some(false));
+    });
+
+    fs.insert_file("/root/model.bin", b"downloaded content".to_vec())
+        .await;
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entry_for_path(rel_path("model.bin"))
+                .unwrap()
+                .is_lfs_pointer,
+            Some(false)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_lfs_pointer_files_not_read_by_default(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "model.bin": "version https://git-lfs.github.com/spec/v1\n\
+                oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n\
+                size 12345\n",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entry_for_path(rel_path("model.bin"))
+                .unwrap()
+                .is_lfs_pointer,
+            None
+        );
+    });
+    assert_eq!(fs.read_count_for_path("/root/model.bin"), 0);
+
+    let is_lfs_pointer = tree
+        .update(cx, |tree, cx| {
+            tree.as_local()
+                .unwrap()
+                .check_lfs_pointer(rel_path("model.bin").into(), cx)
+        })
+        .await
+        .unwrap();
+    assert!(is_lfs_pointer);
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entry_for_path(rel_path("model.bin"))
+                .unwrap()
+                .is_lfs_pointer,
+            Some(true)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_sniff_binary_files(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "image.png": "",
+            "text.txt": "plain text\n",
+        }),
+    )
+    .await;
+    fs.insert_file("/root/blob.dat", b"\0\x01\x02\0\0\x03binary\0\0".to_vec())
+        .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        let is_binary = |path| tree.entry_for_path(rel_path(path)).unwrap().is_binary;
+        assert_eq!(is_binary("image.png"), Some(true));
+        assert_eq!(is_binary("blob.dat"), None);
+        assert_eq!(is_binary("text.txt"), None);
+    });
+
+    for (path, expected) in [("blob.dat", true), ("text.txt", false)] {
+        let is_binary = tree
+            .update(cx, |tree, cx| {
+                tree.as_local()
+                    .unwrap()
+                    .sniff_binary(rel_path(path).into(), cx)
+            })
+            .await
+            .unwrap();
+        assert_eq!(is_binary, expected);
+    }
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        let is_binary = |path| tree.entry_for_path(rel_path(path)).unwrap().is_binary;
+        assert_eq!(is_binary("blob.dat"), Some(true));
+        assert_eq!(is_binary("text.txt"), Some(false));
+    });
+
+    fs.insert_file("/root/other.dat", b"\0\0\0\x7f\0\0\0\x01".to_vec())
+        .await;
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.sniff_binary_files_during_scan = Some(true);
+            });
+        });
+    });
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        let is_binary = |path| tree.entry_for_path(rel_path(path)).unwrap().is_binary;
+        assert_eq!(is_binary("other.dat"), Some(true));
+        assert_eq!(is_binary("text.txt"), Some(false));
+    });
+}
+
+#[gpui::test]
+async fn test_auto_expand_single_root_repo(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.auto_expand_single_root_repo = Some(true);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".git": {},
+            ".gitignore": "target\n",
+            "src": {
+                "lib.rs": "",
+            },
+            "target": {
+                "debug": {
+                    "app": "",
+                },
+                "CACHEDIR.TAG": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        let kind = |path| tree.entry_for_path(rel_path(path)).unwrap().kind;
+        assert_eq!(kind("src"), EntryKind::Dir);
+        assert_eq!(kind("target"), EntryKind::Dir);
+        assert!(
+            tree.entry_for_path(rel_path("target/CACHEDIR.TAG"))
+                .is_some()
+        );
+        assert_eq!(kind("target/debug"), EntryKind::UnloadedDir);
+        assert!(tree.entry_for_path(rel_path("target/debug/app")).is_none());
+    });
+
+    // A nested repository means the root repository isn't the only one, so nothing is expanded.
+    fs.insert_tree(
+        "/multi",
+        json!({
+            ".git": {},
+            ".gitignore": "target\n",
+            "vendor": {
+                "lib": {
+                    ".git": {},
+                    "lib.rs": "",
+                },
+            },
+            "target": {
+                "CACHEDIR.TAG": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/multi"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(tree.as_local().unwrap().git_repositories_count(), 2);
+        assert_eq!(
+            tree.entry_for_path(rel_path("target")).unwrap().kind,
+            EntryKind::UnloadedDir
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_content_hash_detects_changes_with_same_mtime(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.hash_file_contents_during_scan = Some(true);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "aaaa" })).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let changes = changes.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::UpdatedEntries(update) = event {
+                changes.lock().extend(
+                    update
+                        .iter()
+                        .map(|(path, _, change)| (path.as_unix_str().to_string(), *change)),
+                );
+            }
+        })
+        .detach();
+    });
+    let entry = tree.read_with(cx, |tree, _| {
+        tree.entry_for_path(rel_path("a.txt")).unwrap().clone()
+    });
+    assert!(entry.content_hash.is_some());
+
+    // Same size and mtime, different content.
+    fs.set_next_mtime(entry.mtime.unwrap().timestamp_for_user());
+    fs.write(Path::new("/root/a.txt"), b"bbbb").await.unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        mem::take(&mut *changes.lock()),
+        [("a.txt".to_string(), PathChange::Updated)]
+    );
+    tree.read_with(cx, |tree, _| {
+        let new_entry = tree.entry_for_path(rel_path("a.txt")).unwrap();
+        assert_eq!(new_entry.mtime, entry.mtime);
+        assert_eq!(new_entry.size, entry.size);
+        assert_ne!(new_entry.content_hash, entry.content_hash);
+    });
+
+    // Rewriting identical content is not a change.
+    fs.set_next_mtime(entry.mtime.unwrap().timestamp_for_user());
+    fs.write(Path::new("/root/a.txt"), b"bbbb").await.unwrap();
+    cx.executor().run_until_parked();
+    assert!(changes.lock().is_empty());
+}
+
+#[gpui::test]
+async fn test_content_hash_skips_large_files(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.hash_file_contents_during_scan = Some(true);
+                settings.project.worktree.hash_file_contents_max_bytes = Some(4);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "small.txt": "aaaa",
+            "large.txt": "aaaaa",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("small.txt"))
+                .unwrap()
+                .content_hash
+                .is_some()
+        );
+        assert!(
+            tree.entry_for_path(rel_path("large.txt"))
+                .unwrap()
+                .content_hash
+                .is_none()
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_touch_entry(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "a" })).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let changes = changes.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::UpdatedEntries(update) = event {
+                changes.lock().extend(
+                    update
+                        .iter()
+                        .map(|(path, _, change)| (path.as_unix_str().to_string(), *change)),
+                );
+            }
+        })
+        .detach();
+    });
+    let entry = tree.read_with(cx, |tree, _| {
+        tree.entry_for_path(rel_path("a.txt")).unwrap().clone()
+    });
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local().unwrap().touch_entry(entry.id, cx)
+    })
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        let touched_entry = tree.entry_for_path(rel_path("a.txt")).unwrap();
+        assert_eq!(touched_entry.id, entry.id);
+        assert!(
+            touched_entry
+                .mtime
+                .unwrap()
+                .bad_is_greater_than(entry.mtime.unwrap())
+        );
+    });
+    assert_eq!(
+        mem::take(&mut *changes.lock()),
+        [("a.txt".to_string(), PathChange::Updated)]
+    );
+    assert_eq!(
+        fs.load(Path::new("/root/a.txt")).await.unwrap(),
+        "a".to_string()
+    );
+}
+
+#[gpui::test(iterations = 10)]
+async fn test_scan_concurrency_bounds_concurrent_read_dirs(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.scan_concurrency = Some(2);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": { "a1": { "file": "" }, "a2": { "file": "" } },
+            "b": { "b1": { "file": "" }, "b2": { "file": "" } },
+            "c": { "c1": { "file": "" }, "c2": { "file": "" } },
+            "d": { "d1": { "file": "" }, "d2": { "file": "" } },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("d/d2/file")).is_some());
+    });
+    assert!(fs.read_dir_call_count() >= 13);
+    assert!(fs.max_concurrent_read_dirs() <= 2);
+}
+
+#[gpui::test]
+async fn test_retry_directories_with_permission_errors(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "skipped": {
+                "locked": { "file": "" },
+            },
+            "retried": {
+                "locked": { "file": "" },
+                "denied": { "file": "" },
+            },
+        }),
+    )
+    .await;
+
+    fs.fail_read_dir("/root/skipped/locked", 2);
+    let tree = Worktree::local(
+        Path::new("/root/skipped"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("locked")).unwrap().is_dir());
+        assert!(tree.entry_for_path(rel_path("locked/file")).is_none());
+    });
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.on_scan_permission_error =
+                    Some(ScanPermissionErrorSetting::Retry);
+            });
+        });
+    });
+    fs.fail_read_dir("/root/retried/locked", 2);
+    fs.fail_read_dir("/root/retried/denied", usize::MAX);
+    let tree = Worktree::local(
+        Path::new("/root/retried"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(rel_path("locked/file")).is_some());
+        assert!(tree.entry_for_path(rel_path("denied")).unwrap().is_dir());
+        assert!(tree.entry_for_path(rel_path("denied/file")).is_none());
+    });
+}
+
+#[gpui::test]
+async fn test_load_file_respects_max_loadable_file_bytes(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.max_loadable_file_bytes = Some(8);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "big.txt": "0123456789abcdef",
+            "small.txt": "0123",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let error = tree
+        .update(cx, |tree, cx| tree.load_file(rel_path("big.txt"), cx))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<LoadFileError>(),
+        Some(&LoadFileError::TooLarge { size: 16 })
+    );
+
+    let loaded = tree
+        .update(cx, |tree, cx| tree.load_file(rel_path("small.txt"), cx))
+        .await
+        .unwrap();
+    assert_eq!(loaded.text, "0123");
+}
+
+#[cfg(target_os = "windows")]
+#[gpui::test]
+async fn test_paths_longer_than_max_path(cx: &mut TestAppContext) {
+    cx.executor().allow_parking();
+    init_test(cx);
+
+    let temp_root = TempTree::new(json!({}));
+    let component = "a".repeat(40);
+    let relative_dir = vec![component.as_str(); 8].join("/");
+    let dir_abs_path = temp_root.path().join(relative_dir.replace('/', "\\"));
+    assert!(dir_abs_path.as_os_str().len() > 260);
+    std::fs::create_dir_all(util::paths::extended_length_path(&dir_abs_path)).unwrap();
+    std::fs::write(
+        util::paths::extended_length_path(&dir_abs_path.join("deep.txt")),
+        "deep",
+    )
+    .unwrap();
+
+    let tree = Worktree::local(
+        temp_root.path(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let file_path = RelPath::unix(&format!("{relative_dir}/deep.txt"))
+        .unwrap()
+        .into_arc();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(&file_path).unwrap().is_file());
+    });
+
+    let loaded = tree
+        .update(cx, |tree, cx| tree.load_file(&file_path, cx))
+        .await
+        .unwrap();
+    assert_eq!(loaded.text, "deep");
+}
+
+#[gpui::test]
+async fn test_exclude_files_larger_than(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.exclude_files_larger_than = Some(8);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "big.txt": "0123456789abcdef",
+            "small.txt": "0123",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        let big = tree.entry_for_path(rel_path("big.txt")).unwrap();
+        assert_eq!(big.size, 16);
+        assert!(big.is_ignored);
+        assert!(
+            !tree
+                .entry_for_path(rel_path("small.txt"))
+                .unwrap()
+                .is_ignored
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_load_file_caches_unchanged_files(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.cache_loaded_files = Some(true);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "one",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let loaded = tree
+        .update(cx, |tree, cx| tree.load_file(rel_path("a.txt"), cx))
+        .await
+        .unwrap();
+    assert_eq!(loaded.text, "one");
+    let open_file_count = fs.open_file_call_count();
+
+    let loaded = tree
+        .update(cx, |tree, cx| tree.load_file(rel_path("a.txt"), cx))
+        .await
+        .unwrap();
+    assert_eq!(loaded.text, "one");
+    assert_eq!(fs.open_file_call_count(), open_file_count);
+
+    fs.write(Path::new("/root/a.txt"), b"two").await.unwrap();
+    cx.executor().run_until_parked();
+    let loaded = tree
+        .update(cx, |tree, cx| tree.load_file(rel_path("a.txt"), cx))
+        .await
+        .unwrap();
+    assert_eq!(loaded.text, "two");
+    assert!(fs.open_file_call_count() > open_file_count);
+}
+
+#[gpui::test]
+async fn test_load_file_cache_ignores_unchanged_mtime(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.cache_loaded_files = Some(true);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "aaaa",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let loaded = tree
+        .update(cx, |tree, cx| tree.load_file(rel_path("a.txt"), cx))
+        .await
+        .unwrap();
+    assert_eq!(loaded.text, "aaaa");
+
+    // Same size and mtime, different content.
+    let mtime = fs
+        .metadata(Path::new("/root/a.txt"))
+        .await
+        .unwrap()
+        .unwrap()
+        .mtime;
+    fs.set_next_mtime(mtime.timestamp_for_user());
+    fs.write(Path::new("/root/a.txt"), b"bbbb").await.unwrap();
+    cx.executor().run_until_parked();
+    let loaded = tree
+        .update(cx, |tree, cx| tree.load_file(rel_path("a.txt"), cx))
+        .await
+        .unwrap();
+    assert_eq!(loaded.text, "bbbb");
+}
+
+#[gpui::test]
+async fn test_sample_verify(cx: &mut TestAppContext, mut rng: StdRng) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "a",
+            "b.txt": "b",
+            "dir": {
+                "c.txt": "c",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let discrepancies = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().sample_verify(3, &mut rng, cx)
+        })
+        .await;
+    assert_eq!(discrepancies, []);
+
+    // Simulate a watcher that drops events, leaving the snapshot stale.
+    fs.pause_events();
+    fs.write(Path::new("/root/b.txt"), b"changed")
+        .await
+        .unwrap();
+    fs.remove_file(Path::new("/root/dir/c.txt"), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    let mut discrepancies = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().sample_verify(3, &mut rng, cx)
+        })
+        .await;
+    discrepancies.sort_by_key(|discrepancy| match discrepancy {
+        Discrepancy::Missing { path } | Discrepancy::Changed { path } => path.clone(),
+    });
+    assert_eq!(
+        discrepancies,
+        [
+            Discrepancy::Changed {
+                path: rel_path("b.txt").into(),
+            },
+            Discrepancy::Missing {
+                path: rel_path("dir/c.txt").into(),
+            },
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_fs_events_in_exclusions(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = TempTree::new(json!({
+        ".git": {
+            "HEAD": "ref: refs/heads/main\n",
+            "foo": "bar",
+        },
+        ".gitignore": "**/target\n/node_modules\ntest_output\n",
+        "target": {
+            "index": "blah2"
+        },
+        "node_modules": {
+            ".DS_Store": "",
+            "prettier": {
+                "package.json": "{}",
+            },
+        },
+        "src": {
+            ".DS_Store": "",
+            "foo": {
+                "foo.rs": "mod another;\n",
+                "another.rs": "// another",
+            },
+            "bar": {
+                "bar.rs": "// bar",
+            },
+            "lib.rs": "mod foo;\nmod bar;\n",
+        },
+        ".DS_Store": "",
+    }));
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions = Some(vec![
+                    "**/.git".to_string(),
+                    "node_modules/".to_string(),
+                    "build_output".to_string(),
+                ]);
+            });
+        });
+    });
+
+    let tree = Worktree::local(
+        dir.path(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+    tree.read_with(cx, |tree, _| {
+        check_worktree_entries(
+            tree,
+            &[
+                ".git/HEAD",
+                ".git/foo",
+                "node_modules",
+                "node_modules/.DS_Store",
+                "node_modules/prettier",
+                "node_modules/prettier/package.json",
+            ],
+            &["target"],
+            &[
+                ".DS_Store",
+                "src/.DS_Store",
+                "src/lib.rs",
+                "src/foo/foo.rs",
+                "src/foo/another.rs",
+                "src/bar/bar.rs",
+                ".gitignore",
+            ],
+            &[],
+        )
+    });
+
+    let new_excluded_dir = dir.path().join("build_output");
+    let new_ignored_dir = dir.path().join("test_output");
+    std::fs::create_dir_all(&new_excluded_dir)
+        .unwrap_or_else(|e| panic!("Failed to create a {new_excluded_dir:?} directory: {e}"));
+    std::fs::create_dir_all(&new_ignored_dir)
+        .unwrap_or_else(|e| panic!("Failed to create a {new_ignored_dir:?} directory: {e}"));
+    let node_modules_dir = dir.path().join("node_modules");
+    let dot_git_dir = dir.path().join(".git");
+    let src_dir = dir.path().join("src");
+    for existing_dir in [&node_modules_dir, &dot_git_dir, &src_dir] {
+        assert!(
+            existing_dir.is_dir(),
+            "Expect {existing_dir:?} to be present in the FS already"
+        );
+    }
+
+    for directory_for_new_file in [
+        new_excluded_dir,
+        new_ignored_dir,
+        node_modules_dir,
+        dot_git_dir,
+        src_dir,
+    ] {
+        std::fs::write(directory_for_new_file.join("new_file"), "new file contents")
+            .unwrap_or_else(|e| {
+                panic!("Failed to create in {directory_for_new_file:?} a new file: {e}")
+            });
+    }
+    tree.flush_fs_events(cx).await;
+
+    tree.read_with(cx, |tree, _| {
+        check_worktree_entries(
+            tree,
+            &[
+                ".git/HEAD",
+                ".git/foo",
+                ".git/new_file",
+                "node_modules",
+                "node_modules/.DS_Store",
+                "node_modules/prettier",
+                "node_modules/prettier/package.json",
+                "node_modules/new_file",
+                "build_output",
+                "build_output/new_file",
+                "test_output/new_file",
+            ],
+            &["target", "test_output"],
+            &[
+                ".DS_Store",
+                "src/.DS_Store",
+                "src/lib.rs",
+                "src/foo/foo.rs",
+                "src/foo/another.rs",
+                "src/bar/bar.rs",
+                "src/new_file",
+                ".gitignore",
+            ],
+            &[],
+        )
+    });
+}
+
+#[gpui::test]
+async fn test_fs_events_in_dot_git_worktree(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = TempTree::new(json!({
+        ".git": {
+            "HEAD": "ref: refs/heads/main\n",
+            "foo": "foo contents",
+        },
+    }));
+    let dot_git_worktree_dir = dir.path().join(".git");
+
+    let tree = Worktree::local(
+        dot_git_worktree_dir.clone(),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+    tree.read_with(cx, |tree, _| {
+        check_worktree_entries(tree, &[], &["HEAD", "foo"], &[], &[])
+    });
+
+    std::fs::write(dot_git_worktree_dir.join("new_file"), "new file contents")
+        .unwrap_or_else(|e| panic!("Failed to create in {dot_git_worktree_dir:?} a new file: {e}"));
+    tree.flush_fs_events(cx).await;
+    tree.read_with(cx, |tree, _| {
+        check_worktree_entries(tree, &[], &["HEAD", "foo", "new_file"], &[], &[])
+    });
+}
+
+#[gpui::test]
+async fn test_dot_git_worktree_entries_marked_as_git_internal(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings
+                    .project
+                    .worktree
+                    .treat_git_dir_root_as_repo_internals = Some(true);
+            });
+        });
+    });
+    let dir = TempTree::new(json!({
+        ".git": {
+            "HEAD": "ref: refs/heads/main\n",
+            "config": "",
+        },
+        "project": {
+            "file.txt": "",
+        },
+    }));
+
+    let tree = Worktree::local(
+        dir.path().join(".git"),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        let entries = tree.entries(true, 0).collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        for entry in entries {
+            assert!(
+                entry.is_git_internal,
+                "{:?} is not git internal",
+                entry.path
+            );
+        }
+    });
+
+    let project_tree = Worktree::local(
+        dir.path().join("project"),
+        true,
+        Arc::new(RealFs::new(None, cx.executor())),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| project_tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    project_tree.read_with(cx, |tree, _| {
+        assert!(tree.entries(true, 0).all(|entry| !entry.is_git_internal));
+    });
+}
+
+#[gpui::test(iterations = 30)]
+async fn test_create_directory_during_initial_scan(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "b": {},
+            "c": {},
+            "d": {},
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        "/root".as_ref(),
+        true,
+        fs,
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    let snapshot1 = tree.update(cx, |tree, cx| {
+        let tree = tree.as_local_mut().unwrap();
+        let snapshot = Arc::new(Mutex::new(tree.snapshot()));
+        tree.observe_updates(0, cx, {
+            let snapshot = snapshot.clone();
+            let settings = tree.settings();
+            move |update| {
+                snapshot
+                    .lock()
+                    .apply_remote_update(update, &settings.file_scan_inclusions);
+                async { true }
+            }
+        });
+        snapshot
+    });
+
+    let entry = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_entry(rel_path("a/e").into(), true, None, cx)
+        })
+        .await
+        .unwrap()
+        .into_included()
+        .unwrap();
+    assert!(entry.is_dir());
+
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entry_for_path(rel_path("a/e")).unwrap().kind,
+            EntryKind::Dir
+        );
+    });
+
+    let snapshot2 = tree.update(cx, |tree, _| tree.as_local().unwrap().snapshot());
+    assert_eq!(
+        snapshot1.lock().entries(true, 0).collect::<Vec<_>>(),
+        snapshot2.entries(true, 0).collect::<Vec<_>>()
+    );
+}
+
+#[gpui::test]
+async fn test_create_dir_all_on_create_entry(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let fs_fake = FakeFs::new(cx.background_executor.clone());
+    fs_fake
+        .insert_tree(
+            "/root",
+            json!({
+                "a": {},
+            }),
+        )
+        .await;
+
+    let tree_fake = Worktree::local(
+        "/root".as_ref(),
+        true,
+        fs_fake,
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    let entry = tree_fake
+        .update(cx, |tree, cx| {
+            tree.as_local_mut().unwrap().create_entry(
+                rel_path("a/b/c/d.txt").into(),
+                false,
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap()
+        .into_included()
+        .unwrap();
+    assert!(entry.is_file());
+
+    cx.executor().run_until_parked();
+    tree_fake.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("a/b/c/d.txt"))
+                .unwrap()
+                .is_file()
+        );
+        assert!(tree.entry_for_path(rel_path("a/b/c")).unwrap().is_dir());
+        assert!(tree.entry_for_path(rel_path("a/b")).unwrap().is_dir());
+    });
+
+    let fs_real = Arc::new(RealFs::new(None, cx.executor()));
+    let temp_root = TempTree::new(json!({
+        "a": {}
+    }));
+
+    let tree_real = Worktree::local(
+        temp_root.path(),
+        true,
+        fs_real,
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    let entry = tree_real
+        .update(cx, |tree, cx| {
+            tree.as_local_mut().unwrap().create_entry(
+                rel_path("a/b/c/d.txt").into(),
+                false,
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap()
+        .into_included()
+        .unwrap();
+    assert!(entry.is_file());
+
+    cx.executor().run_until_parked();
+    tree_real.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("a/b/c/d.txt"))
+                .unwrap()
+                .is_file()
+        );
+        assert!(tree.entry_for_path(rel_path("a/b/c")).unwrap().is_dir());
+        assert!(tree.entry_for_path(rel_path("a/b")).unwrap().is_dir());
+    });
+
+    // Test smallest change
+    let entry = tree_real
+        .update(cx, |tree, cx| {
+            tree.as_local_mut().unwrap().create_entry(
+                rel_path("a/b/c/e.txt").into(),
+                false,
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap()
+        .into_included()
+        .unwrap();
+    assert!(entry.is_file());
+
+    cx.executor().run_until_parked();
+    tree_real.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("a/b/c/e.txt"))
+                .unwrap()
+                .is_file()
+        );
+    });
+
+    // Test largest change
+    let entry = tree_real
+        .update(cx, |tree, cx| {
+            tree.as_local_mut().unwrap().create_entry(
+                rel_path("d/e/f/g.txt").into(),
+                false,
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap()
+        .into_included()
+        .unwrap();
+    assert!(entry.is_file());
+
+    cx.executor().run_until_parked();
+    tree_real.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path(rel_path("d/e/f/g.txt"))
+                .unwrap()
+                .is_file()
+        );
+        assert!(tree.entry_for_path(rel_path("d/e/f")).unwrap().is_dir());
+        assert!(tree.entry_for_path(rel_path("d/e")).unwrap().is_dir());
+        assert!(tree.entry_for_path(rel_path("d")).unwrap().is_dir());
+    });
+}
+
+#[gpui::test]
+async fn test_create_entry_with_ancestors(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a": {} })).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let created_entries = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().create_entry_with_ancestors(
+                rel_path("d/e/f/g.txt").into(),
+                false,
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        created_entries
+            .iter()
+            .map(|entry| (entry.path.as_ref(), entry.is_dir()))
+            .collect::<Vec<_>>(),
+        [
+            (rel_path("d"), true),
+            (rel_path("d/e"), true),
+            (rel_path("d/e/f"), true),
+            (rel_path("d/e/f/g.txt"), false),
+        ]
+    );
+
+    let created_entries = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().create_entry_with_ancestors(
+                rel_path("a/h").into(),
+                true,
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        created_entries
+            .iter()
+            .map(|entry| entry.path.as_ref())
+            .collect::<Vec<_>>(),
+        [rel_path("a/h")]
+    );
+
+    let created_entries = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().create_entry_with_ancestors(
+                rel_path("a/h").into(),
+                true,
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert!(created_entries.is_empty());
+}
+
+#[gpui::test]
+async fn test_create_entry_for_existing_path(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "dir": {},
+            "file.txt": "original",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let (dir_id, file_id) = tree.read_with(cx, |tree, _| {
+        (
+            tree.entry_for_path(rel_path("dir")).unwrap().id,
+            tree.entry_for_path(rel_path("file.txt")).unwrap().id,
+        )
+    });
+
+    let entry = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_entry(rel_path("dir").into(), true, None, cx)
+        })
+        .await
+        .unwrap()
+        .into_included()
+        .unwrap();
+    assert_eq!(entry.id, dir_id);
+
+    let entry = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_entry(rel_path("file.txt").into(), false, None, cx)
+        })
+        .await
+        .unwrap()
+        .into_included()
+        .unwrap();
+    assert_eq!(entry.id, file_id);
+
+    let error = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut().unwrap().create_entry(
+                rel_path("file.txt").into(),
+                false,
+                Some(b"replaced".to_vec()),
+                cx,
+            )
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<CreateEntryError>(),
+        Some(&CreateEntryError::AlreadyExists)
+    );
+    assert_eq!(
+        fs.load(Path::new("/root/file.txt")).await.unwrap(),
+        "original"
+    );
+
+    let error = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_entry(rel_path("dir").into(), false, None, cx)
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<CreateEntryError>(),
+        Some(&CreateEntryError::ExistsAsDirectory)
+    );
+
+    let error = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_entry(rel_path("file.txt").into(), true, None, cx)
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<CreateEntryError>(),
+        Some(&CreateEntryError::ExistsAsFile)
+    );
+    assert!(fs.is_dir(Path::new("/root/dir")).await);
+    assert!(fs.is_file(Path::new("/root/file.txt")).await);
+}
+
+#[gpui::test]
+async fn test_symlinked_root(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/target",
+        json!({
+            "src": {
+                "a.rs": "",
+            },
+            "b.txt": "",
+        }),
+    )
+    .await;
+    fs.create_symlink("/link".as_ref(), "/target".into())
+        .await
+        .unwrap();
+
+    let tree = Worktree::local(
+        Path::new("/link"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(tree.abs_path().as_ref(), Path::new("/link"));
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| (entry.path.as_ref(), entry.is_external))
+                .collect::<Vec<_>>(),
+            vec![
+                (rel_path(""), false),
+                (rel_path("b.txt"), false),
+                (rel_path("src"), false),
+                (rel_path("src/a.rs"), false),
+            ]
+        );
+    });
+
+    fs.create_file("/target/src/c.rs".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path(rel_path("src/c.rs")).unwrap();
+        assert!(!entry.is_external);
     });
 }
 
 #[gpui::test]
-async fn test_fs_events_in_dot_git_worktree(cx: &mut TestAppContext) {
+async fn test_batch_emits_single_event(cx: &mut TestAppContext) {
     init_test(cx);
-    cx.executor().allow_parking();
-    let dir = TempTree::new(json!({
-        ".git": {
-            "HEAD": "ref: refs/heads/main\n",
-            "foo": "foo contents",
-        },
-    }));
-    let dot_git_worktree_dir = dir.path().join(".git");
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "a" })).await;
 
     let tree = Worktree::local(
-        dot_git_worktree_dir.clone(),
+        Path::new("/root"),
         true,
-        Arc::new(RealFs::new(None, cx.executor())),
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
@@ -1327,232 +5249,407 @@ async fn test_fs_events_in_dot_git_worktree(cx: &mut TestAppContext) {
     .unwrap();
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
-    tree.flush_fs_events(cx).await;
-    tree.read_with(cx, |tree, _| {
-        check_worktree_entries(tree, &[], &["HEAD", "foo"], &[], &[])
+    cx.executor().run_until_parked();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let events = events.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::UpdatedEntries(update) = event {
+                events.lock().push(
+                    update
+                        .iter()
+                        .map(|(path, _, change)| (path.as_unix_str().to_string(), *change))
+                        .collect::<Vec<_>>(),
+                );
+            }
+        })
+        .detach();
     });
 
-    std::fs::write(dot_git_worktree_dir.join("new_file"), "new file contents")
-        .unwrap_or_else(|e| panic!("Failed to create in {dot_git_worktree_dir:?} a new file: {e}"));
-    tree.flush_fs_events(cx).await;
-    tree.read_with(cx, |tree, _| {
-        check_worktree_entries(tree, &[], &["HEAD", "foo", "new_file"], &[], &[])
+    let created = tree.update(cx, |tree, cx| {
+        tree.as_local_mut().unwrap().batch(cx, |ops| {
+            ops.create_entry(rel_path("b.txt").into(), false, None);
+            ops.create_entry(rel_path("c.txt").into(), false, Some(b"c".to_vec()));
+            ops.create_entry(rel_path("d").into(), true, None);
+        })
     });
+    let created = created.await.unwrap();
+    assert_eq!(created.len(), 3);
+    cx.executor().run_until_parked();
+
+    assert_eq!(
+        mem::take(&mut *events.lock()),
+        [vec![
+            ("b.txt".to_string(), PathChange::Added),
+            ("c.txt".to_string(), PathChange::Added),
+            ("d".to_string(), PathChange::Added),
+        ]]
+    );
 }
 
-#[gpui::test(iterations = 30)]
-async fn test_create_directory_during_initial_scan(cx: &mut TestAppContext) {
+#[gpui::test]
+async fn test_batch_does_not_hold_unrelated_changes(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "a" })).await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let events = events.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::UpdatedEntries(update) = event {
+                events.lock().push(
+                    update
+                        .iter()
+                        .map(|(path, _, change)| (path.as_unix_str().to_string(), *change))
+                        .collect::<Vec<_>>(),
+                );
+            }
+        })
+        .detach();
+    });
+
+    let created = tree.update(cx, |tree, cx| {
+        tree.as_local_mut().unwrap().batch(cx, |ops| {
+            ops.create_entry(rel_path("b.txt").into(), false, None);
+            ops.create_entry(rel_path("c.txt").into(), false, None);
+        })
+    });
+    fs.insert_file("/root/x.txt", Vec::new()).await;
+    created.await.unwrap();
+    cx.executor().run_until_parked();
+
+    let mut emitted = mem::take(&mut *events.lock());
+    emitted.sort_by_key(|changes| changes[0].0.clone());
+    assert_eq!(
+        emitted,
+        [
+            vec![
+                ("b.txt".to_string(), PathChange::Added),
+                ("c.txt".to_string(), PathChange::Added),
+            ],
+            vec![("x.txt".to_string(), PathChange::Added)],
+        ]
+    );
+
+    // Dropping a batch's task stops its paths from being held back.
+    drop(tree.update(cx, |tree, cx| {
+        tree.as_local_mut().unwrap().batch(cx, |ops| {
+            ops.create_entry(rel_path("d.txt").into(), false, None);
+        })
+    }));
+    cx.executor().run_until_parked();
+    fs.insert_file("/root/d.txt", b"d".to_vec()).await;
+    cx.executor().run_until_parked();
+    assert!(
+        events
+            .lock()
+            .iter()
+            .flatten()
+            .any(|(path, _)| path == "d.txt")
+    );
+}
+
+#[test]
+fn test_coalesce_entry_changes() {
+    let path: Arc<RelPath> = rel_path("a.txt").into();
+    let old_id = ProjectEntryId::from_proto(1);
+    let new_id = ProjectEntryId::from_proto(2);
+    let coalesced = crate::coalesce_entry_changes(vec![
+        (path.clone(), old_id, PathChange::Removed),
+        (path.clone(), new_id, PathChange::Added),
+        (path.clone(), new_id, PathChange::Updated),
+    ]);
+    assert_eq!(
+        &*coalesced,
+        [
+            (path.clone(), old_id, PathChange::Removed),
+            (path.clone(), new_id, PathChange::Added),
+        ]
+    );
+
+    let coalesced = crate::coalesce_entry_changes(vec![
+        (path.clone(), new_id, PathChange::Added),
+        (path.clone(), new_id, PathChange::Removed),
+    ]);
+    assert!(coalesced.is_empty());
+}
+
+#[gpui::test]
+async fn test_changed_since_open(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
     fs.insert_tree(
         "/root",
         json!({
-            "b": {},
-            "c": {},
-            "d": {},
+            "a.txt": "a",
+            "b.txt": "b",
+            "dir": {
+                "c.txt": "c",
+            },
         }),
     )
     .await;
 
     let tree = Worktree::local(
-        "/root".as_ref(),
+        Path::new("/root"),
         true,
-        fs,
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
     )
     .await
     .unwrap();
-
-    let snapshot1 = tree.update(cx, |tree, cx| {
-        let tree = tree.as_local_mut().unwrap();
-        let snapshot = Arc::new(Mutex::new(tree.snapshot()));
-        tree.observe_updates(0, cx, {
-            let snapshot = snapshot.clone();
-            let settings = tree.settings();
-            move |update| {
-                snapshot
-                    .lock()
-                    .apply_remote_update(update, &settings.file_scan_inclusions);
-                async { true }
-            }
-        });
-        snapshot
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.as_local().unwrap().changed_since_open().is_empty());
     });
 
-    let entry = tree
-        .update(cx, |tree, cx| {
-            tree.as_local_mut()
-                .unwrap()
-                .create_entry(rel_path("a/e").into(), true, None, cx)
-        })
+    fs.insert_file("/root/a.txt", b"aa".to_vec()).await;
+    fs.remove_file("/root/b.txt".as_ref(), Default::default())
         .await
-        .unwrap()
-        .into_included()
         .unwrap();
-    assert!(entry.is_dir());
-
+    fs.insert_file("/root/dir/d.txt", b"d".to_vec()).await;
     cx.executor().run_until_parked();
+
     tree.read_with(cx, |tree, _| {
         assert_eq!(
-            tree.entry_for_path(rel_path("a/e")).unwrap().kind,
-            EntryKind::Dir
+            tree.as_local().unwrap().changed_since_open(),
+            [
+                (rel_path("a.txt").into_arc(), PathChange::Updated),
+                (rel_path("b.txt").into_arc(), PathChange::Removed),
+                (rel_path("dir/d.txt").into_arc(), PathChange::Added),
+            ]
         );
     });
-
-    let snapshot2 = tree.update(cx, |tree, _| tree.as_local().unwrap().snapshot());
-    assert_eq!(
-        snapshot1.lock().entries(true, 0).collect::<Vec<_>>(),
-        snapshot2.entries(true, 0).collect::<Vec<_>>()
-    );
 }
 
 #[gpui::test]
-async fn test_create_dir_all_on_create_entry(cx: &mut TestAppContext) {
+async fn test_create_symlink(cx: &mut TestAppContext) {
     init_test(cx);
-    cx.executor().allow_parking();
-
-    let fs_fake = FakeFs::new(cx.background_executor.clone());
-    fs_fake
-        .insert_tree(
-            "/root",
-            json!({
-                "a": {},
-            }),
-        )
-        .await;
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.file_scan_exclusions =
+                    Some(vec!["**/excluded".to_string()]);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "a",
+            "dir": {},
+        }),
+    )
+    .await;
 
-    let tree_fake = Worktree::local(
-        "/root".as_ref(),
+    let tree = Worktree::local(
+        Path::new("/root"),
         true,
-        fs_fake,
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
     )
     .await
     .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
 
-    let entry = tree_fake
+    let entry = tree
         .update(cx, |tree, cx| {
-            tree.as_local_mut().unwrap().create_entry(
-                rel_path("a/b/c/d.txt").into(),
-                false,
-                None,
+            tree.as_local().unwrap().create_symlink(
+                rel_path("dir/link.txt").into(),
+                PathBuf::from("/root/a.txt"),
                 cx,
             )
         })
         .await
-        .unwrap()
-        .into_included()
         .unwrap();
     assert!(entry.is_file());
+    assert_eq!(
+        entry.canonical_path.as_deref(),
+        Some(Path::new("/root/a.txt"))
+    );
 
     cx.executor().run_until_parked();
-    tree_fake.read_with(cx, |tree, _| {
-        assert!(
-            tree.entry_for_path(rel_path("a/b/c/d.txt"))
-                .unwrap()
-                .is_file()
+    tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path(rel_path("dir/link.txt")).unwrap();
+        assert_eq!(
+            entry.canonical_path.as_deref(),
+            Some(Path::new("/root/a.txt"))
         );
-        assert!(tree.entry_for_path(rel_path("a/b/c")).unwrap().is_dir());
-        assert!(tree.entry_for_path(rel_path("a/b")).unwrap().is_dir());
+        assert!(!entry.is_external);
     });
 
-    let fs_real = Arc::new(RealFs::new(None, cx.executor()));
-    let temp_root = TempTree::new(json!({
-        "a": {}
-    }));
+    let result = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().create_symlink(
+                rel_path("excluded").into(),
+                PathBuf::from("/root/a.txt"),
+                cx,
+            )
+        })
+        .await;
+    assert!(result.is_err());
+    assert!(
+        fs.metadata("/root/excluded".as_ref())
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[gpui::test]
+async fn test_open_buffer_at_head(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".git": {},
+            "src": {
+                "a.txt": "modified",
+            },
+            "untracked.txt": "new",
+        }),
+    )
+    .await;
+    fs.set_head_and_index_for_repo(
+        Path::new("/root/.git"),
+        &[("src/a.txt", "original".to_string())],
+    );
 
-    let tree_real = Worktree::local(
-        temp_root.path(),
+    let tree = Worktree::local(
+        Path::new("/root"),
         true,
-        fs_real,
+        fs.clone(),
         Default::default(),
         true,
         &mut cx.to_async(),
     )
     .await
     .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
 
-    let entry = tree_real
+    let loaded = tree
         .update(cx, |tree, cx| {
-            tree.as_local_mut().unwrap().create_entry(
-                rel_path("a/b/c/d.txt").into(),
-                false,
-                None,
-                cx,
-            )
+            tree.as_local()
+                .unwrap()
+                .open_buffer_at_head(rel_path("src/a.txt"), cx)
         })
         .await
-        .unwrap()
-        .into_included()
         .unwrap();
-    assert!(entry.is_file());
-
-    cx.executor().run_until_parked();
-    tree_real.read_with(cx, |tree, _| {
-        assert!(
-            tree.entry_for_path(rel_path("a/b/c/d.txt"))
-                .unwrap()
-                .is_file()
-        );
-        assert!(tree.entry_for_path(rel_path("a/b/c")).unwrap().is_dir());
-        assert!(tree.entry_for_path(rel_path("a/b")).unwrap().is_dir());
-    });
+    assert_eq!(loaded.working_copy.text, "modified");
+    assert_eq!(loaded.head_text.as_deref(), Some("original"));
+    assert_ne!(Some(loaded.working_copy.text), loaded.head_text);
 
-    // Test smallest change
-    let entry = tree_real
+    let loaded = tree
         .update(cx, |tree, cx| {
-            tree.as_local_mut().unwrap().create_entry(
-                rel_path("a/b/c/e.txt").into(),
-                false,
-                None,
-                cx,
-            )
+            tree.as_local()
+                .unwrap()
+                .open_buffer_at_head(rel_path("untracked.txt"), cx)
         })
         .await
-        .unwrap()
-        .into_included()
         .unwrap();
-    assert!(entry.is_file());
+    assert_eq!(loaded.working_copy.text, "new");
+    assert_eq!(loaded.head_text, None);
+}
 
-    cx.executor().run_until_parked();
-    tree_real.read_with(cx, |tree, _| {
-        assert!(
-            tree.entry_for_path(rel_path("a/b/c/e.txt"))
-                .unwrap()
-                .is_file()
-        );
-    });
+#[gpui::test]
+async fn test_apply_entries_change(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "file1.txt": "",
+                "file2.txt": "",
+            },
+            "b": {
+                "c": {
+                    "file3.txt": "",
+                },
+            },
+        }),
+    )
+    .await;
 
-    // Test largest change
-    let entry = tree_real
-        .update(cx, |tree, cx| {
-            tree.as_local_mut().unwrap().create_entry(
-                rel_path("d/e/f/g.txt").into(),
-                false,
-                None,
-                cx,
-            )
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let mirror = Arc::new(Mutex::new(tree.read_with(cx, |tree, _| tree.snapshot())));
+    tree.update(cx, |_, cx| {
+        let mirror = mirror.clone();
+        cx.subscribe(&cx.entity(), move |tree, _, event, _| {
+            if let Event::UpdatedEntries(changes) = event {
+                mirror.lock().apply_entries_change(changes, tree);
+            }
         })
+        .detach();
+    });
+
+    fs.create_file("/root/a/file4.txt".as_ref(), Default::default())
         .await
-        .unwrap()
-        .into_included()
         .unwrap();
-    assert!(entry.is_file());
-
+    fs.rename(
+        "/root/a/file1.txt".as_ref(),
+        "/root/b/file1.txt".as_ref(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.remove_dir(
+        "/root/b/c".as_ref(),
+        RemoveOptions {
+            recursive: true,
+            ignore_if_not_exists: false,
+        },
+    )
+    .await
+    .unwrap();
     cx.executor().run_until_parked();
-    tree_real.read_with(cx, |tree, _| {
-        assert!(
-            tree.entry_for_path(rel_path("d/e/f/g.txt"))
-                .unwrap()
-                .is_file()
+
+    tree.read_with(cx, |tree, _| {
+        let mirror = mirror.lock();
+        assert_eq!(
+            mirror.entries(true, 0).cloned().collect::<Vec<_>>(),
+            tree.entries(true, 0).cloned().collect::<Vec<_>>()
         );
-        assert!(tree.entry_for_path(rel_path("d/e/f")).unwrap().is_dir());
-        assert!(tree.entry_for_path(rel_path("d/e")).unwrap().is_dir());
-        assert!(tree.entry_for_path(rel_path("d")).unwrap().is_dir());
+        assert_eq!(mirror.entry_count(), tree.entry_count());
     });
 }
 
@@ -2023,7 +6120,7 @@ fn randomly_mutate_worktree(
     match rng.random_range(0_u32..100) {
         0..=33 if entry.path.as_ref() != RelPath::empty() => {
             log::info!("deleting entry {:?} ({})", entry.path, entry.id.0);
-            worktree.delete_entry(entry.id, false, cx).unwrap()
+            worktree.delete_entry(entry.id, Some(false), cx).unwrap()
         }
         _ => {
             if entry.is_dir() {
@@ -2227,13 +6324,271 @@ fn random_filename(rng: &mut impl Rng) -> String {
 }
 
 #[gpui::test]
-async fn test_private_single_file_worktree(cx: &mut TestAppContext) {
+async fn test_private_single_file_worktree(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/", json!({".env": "PRIVATE=secret\n"}))
+        .await;
+    let tree = Worktree::local(
+        Path::new("/.env"),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path(rel_path("")).unwrap();
+        assert!(entry.is_private);
+    });
+}
+
+#[gpui::test]
+async fn test_git_config_changed_event(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {
+                "config": "",
+                "info": {
+                    "exclude": "",
+                },
+            },
+            ".gitignore": "*.txt\n",
+            "a.xml": "<a></a>",
+            "b.txt": "Some text",
+        }),
+    )
+    .await;
+    let tree = Worktree::local(
+        Path::new(path!("/root")),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    let config_changes = Arc::new(Mutex::new(Vec::new()));
+    let entry_changes = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let config_changes = config_changes.clone();
+        let entry_changes = entry_changes.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| match event {
+            Event::GitConfigChanged { work_directory } => {
+                config_changes.lock().push(work_directory.clone());
+            }
+            Event::UpdatedEntries(update) => {
+                entry_changes
+                    .lock()
+                    .extend(update.iter().map(|(path, _, _)| path.clone()));
+            }
+            _ => {}
+        })
+        .detach();
+    });
+    let work_directory = WorkDirectory::InProject {
+        relative_path: RelPath::empty().into(),
+    };
+
+    fs.write(Path::new(path!("/root/.gitignore")), b"*.xml\n")
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        mem::take(&mut *config_changes.lock()),
+        [work_directory.clone()]
+    );
+    assert!(
+        mem::take(&mut *entry_changes.lock()).contains(&rel_path(".gitignore").into()),
+        "gitignore edits are also reported as entry updates"
+    );
+
+    fs.write(
+        Path::new(path!("/root/.git/config")),
+        b"[core]\n\tautocrlf = true\n",
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        mem::take(&mut *config_changes.lock()),
+        [work_directory.clone()]
+    );
+
+    fs.write(Path::new(path!("/root/.git/info/exclude")), b"*.log\n")
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        mem::take(&mut *config_changes.lock()),
+        [work_directory.clone()]
+    );
+
+    fs.write(Path::new(path!("/root/a.xml")), b"<b></b>")
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert!(config_changes.lock().is_empty());
+}
+
+#[gpui::test]
+async fn test_git_repository_updates_are_coalesced(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project.worktree.git_repository_events_window_ms = Some(100);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {
+                "objects": {},
+            },
+            "a.txt": "",
+        }),
+    )
+    .await;
+    let tree = Worktree::local(
+        Path::new(path!("/root")),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    let repository_updates = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let repository_updates = repository_updates.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::UpdatedGitRepositories(updates) = event {
+                repository_updates.lock().push(
+                    updates
+                        .iter()
+                        .filter_map(|update| update.new_work_directory_abs_path.clone())
+                        .collect::<Vec<_>>(),
+                );
+            }
+        })
+        .detach();
+    });
+
+    // Each write is scanned and applied on its own, but the repository is reported only once
+    // the window opened by the first of them closes.
+    for path in [
+        path!("/root/.git/packed-refs"),
+        path!("/root/.git/ORIG_HEAD"),
+        path!("/root/.git/index"),
+        path!("/root/.git/objects/pack-1.pack"),
+    ] {
+        fs.insert_file(path, Vec::new()).await;
+        cx.executor().run_until_parked();
+        cx.executor().advance_clock(Duration::from_millis(20));
+    }
+    assert!(repository_updates.lock().is_empty());
+
+    cx.executor().advance_clock(Duration::from_millis(100));
+    cx.executor().run_until_parked();
+    assert_eq!(
+        mem::take(&mut *repository_updates.lock()),
+        [vec![Arc::<Path>::from(Path::new(path!("/root")))]]
+    );
+}
+
+#[gpui::test]
+async fn test_git_repository_event_order(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
-    fs.insert_tree("/", json!({".env": "PRIVATE=secret\n"}))
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {
+                "config": "",
+            },
+            "a.txt": "",
+        }),
+    )
+    .await;
+    let tree = Worktree::local(
+        Path::new(path!("/root")),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
+    cx.executor().run_until_parked();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let events = events.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            let kind = match event {
+                Event::UpdatedEntries(_) => "entries",
+                Event::UpdatedGitRepositories(_) => "repositories",
+                Event::GitConfigChanged { .. } => "config",
+                _ => return,
+            };
+            events.lock().push(kind);
+        })
+        .detach();
+    });
+
+    fs.pause_events();
+    fs.insert_file(path!("/root/b.txt"), Vec::new()).await;
+    fs.insert_file(path!("/root/.git/index"), Vec::new()).await;
+    fs.write(Path::new(path!("/root/.git/config")), b"[core]\n")
+        .await
+        .unwrap();
+    fs.unpause_events_and_flush();
+    cx.executor().run_until_parked();
+
+    assert_eq!(
+        mem::take(&mut *events.lock()),
+        ["entries", "repositories", "config"]
+    );
+}
+
+#[gpui::test]
+async fn test_root_changed_event_on_root_rename(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/code"),
+        json!({
+            "project1": {
+                "a.txt": "a",
+            },
+        }),
+    )
+    .await;
     let tree = Worktree::local(
-        Path::new("/.env"),
+        Path::new(path!("/code/project1")),
         true,
         fs.clone(),
         Default::default(),
@@ -2244,9 +6599,44 @@ async fn test_private_single_file_worktree(cx: &mut TestAppContext) {
     .unwrap();
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
+    cx.executor().run_until_parked();
+
+    let root_changes = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let root_changes = root_changes.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::RootChanged {
+                old_abs_path,
+                new_abs_path,
+            } = event
+            {
+                root_changes
+                    .lock()
+                    .push((old_abs_path.clone(), new_abs_path.clone()));
+            }
+        })
+        .detach();
+    });
+
+    fs.rename(
+        Path::new(path!("/code/project1")),
+        Path::new(path!("/code/project2")),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+
+    assert_eq!(
+        mem::take(&mut *root_changes.lock()),
+        [(
+            Arc::<Path>::from(Path::new(path!("/code/project1"))),
+            Arc::<Path>::from(Path::new(path!("/code/project2"))),
+        )]
+    );
     tree.read_with(cx, |tree, _| {
-        let entry = tree.entry_for_path(rel_path("")).unwrap();
-        assert!(entry.is_private);
+        assert_eq!(tree.abs_path().as_ref(), Path::new(path!("/code/project2")));
+        assert_eq!(tree.root_name_str(), "project2");
     });
 }
 
@@ -2312,6 +6702,109 @@ async fn test_repository_above_root(executor: BackgroundExecutor, cx: &mut TestA
     pretty_assertions::assert_eq!(repos, [Path::new(path!("/root")).into()]);
 }
 
+#[gpui::test]
+async fn test_entry_for_repo_path(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor);
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "other.txt": "",
+            "subproject": {
+                "a.txt": "A"
+            }
+        }),
+    )
+    .await;
+    let worktree = Worktree::local(
+        path!("/root/subproject").as_ref(),
+        true,
+        fs.clone(),
+        Arc::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    worktree
+        .update(cx, |worktree, _| {
+            worktree.as_local().unwrap().scan_complete()
+        })
+        .await;
+    cx.run_until_parked();
+
+    worktree.read_with(cx, |worktree, _| {
+        let worktree = worktree.as_local().unwrap();
+        let work_directory = &worktree
+            .git_repositories
+            .values()
+            .next()
+            .unwrap()
+            .work_directory;
+        assert!(matches!(work_directory, WorkDirectory::AboveProject { .. }));
+
+        let entry = worktree
+            .entry_for_repo_path(work_directory, &RepoPath::new("subproject/a.txt").unwrap())
+            .unwrap();
+        assert_eq!(entry.path.as_ref(), rel_path("a.txt"));
+        assert!(
+            worktree
+                .entry_for_repo_path(work_directory, &RepoPath::new("other.txt").unwrap())
+                .is_none()
+        );
+    });
+}
+
+#[test]
+fn test_work_directory_directory_contains() {
+    let in_project = WorkDirectory::InProject {
+        relative_path: rel_path("a/b").into(),
+    };
+    assert!(in_project.directory_contains(rel_path("a/b")));
+    assert!(in_project.directory_contains(rel_path("a/b/c.txt")));
+    assert!(!in_project.directory_contains(rel_path("a")));
+    assert!(!in_project.directory_contains(rel_path("a/bc")));
+    assert!(!in_project.directory_contains(rel_path("d/a/b")));
+
+    let above_project = WorkDirectory::AboveProject {
+        absolute_path: Path::new(path!("/grandparent")).into(),
+        location_in_repo: Path::new("parent/project").into(),
+    };
+    assert!(above_project.directory_contains(RelPath::empty()));
+    assert!(above_project.directory_contains(rel_path("a.txt")));
+    assert!(above_project.directory_contains(rel_path("a/b/c.txt")));
+}
+
+#[test]
+fn test_work_directory_try_unrelativize() {
+    let in_project = WorkDirectory::InProject {
+        relative_path: rel_path("a/b").into(),
+    };
+    assert_eq!(
+        in_project.try_unrelativize(&RepoPath::new("c.txt").unwrap()),
+        Some(rel_path("a/b/c.txt").into_arc())
+    );
+
+    let above_project = WorkDirectory::AboveProject {
+        absolute_path: Path::new(path!("/grandparent")).into(),
+        location_in_repo: Path::new("parent/project").into(),
+    };
+    assert_eq!(
+        above_project.try_unrelativize(&RepoPath::new("parent/project/c/d.txt").unwrap()),
+        Some(rel_path("c/d.txt").into_arc())
+    );
+    assert_eq!(
+        above_project.try_unrelativize(&RepoPath::new("parent/project").unwrap()),
+        Some(RelPath::empty().into_arc())
+    );
+    assert_eq!(
+        above_project.try_unrelativize(&RepoPath::new("parent/other.txt").unwrap()),
+        None
+    );
+}
+
 #[gpui::test]
 async fn test_global_gitignore(executor: BackgroundExecutor, cx: &mut TestAppContext) {
     init_test(cx);
@@ -2565,6 +7058,185 @@ fn init_test(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_git_repositories_count(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {},
+            "a.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new(path!("/root")),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(tree.as_local().unwrap().git_repositories_count(), 1);
+    });
+
+    let removed_work_directories = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let removed_work_directories = removed_work_directories.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::UpdatedGitRepositories(updates) = event {
+                removed_work_directories.lock().extend(
+                    updates
+                        .iter()
+                        .filter(|update| update.new_work_directory_abs_path.is_none())
+                        .filter_map(|update| update.old_work_directory_abs_path.clone()),
+                );
+            }
+        })
+        .detach();
+    });
+
+    fs.remove_dir(Path::new(path!("/root/.git")), RemoveOptions::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(tree.as_local().unwrap().git_repositories_count(), 0);
+    });
+    assert_eq!(
+        mem::take(&mut *removed_work_directories.lock()),
+        [Arc::<Path>::from(Path::new(path!("/root")))]
+    );
+}
+
+#[gpui::test]
+async fn test_entries_under_repo(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            "x": {
+                ".git": {},
+                "a.txt": "",
+                "c": {
+                    "d.txt": "",
+                },
+                "y": {
+                    ".git": {},
+                    "b.txt": "",
+                },
+            },
+            "z.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new(path!("/root")),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        let tree = tree.as_local().unwrap();
+        let paths_under_repo = |path: &str| {
+            let work_directory = WorkDirectory::InProject {
+                relative_path: rel_path(path).into(),
+            };
+            tree.entries_under_repo(&work_directory)
+                .map(|entry| entry.path.as_unix_str().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths_under_repo("x"), ["x", "x/a.txt", "x/c", "x/c/d.txt"]);
+        assert_eq!(paths_under_repo("x/y"), ["x/y", "x/y/b.txt"]);
+    });
+}
+
+#[gpui::test]
+async fn test_head_moved_in_git_repository_updates(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+    let fs = FakeFs::new(executor);
+    fs.insert_tree(
+        path!("/root"),
+        json!({
+            ".git": {
+                "HEAD": "ref: refs/heads/main\n",
+                "index": "",
+                "logs": {
+                    "HEAD": "",
+                },
+            },
+            "a.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        path!("/root").as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        true,
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+
+    let head_moved_events = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let head_moved_events = head_moved_events.clone();
+        cx.subscribe(&cx.entity(), move |_, _, event, _| {
+            if let Event::UpdatedGitRepositories(updates) = event {
+                head_moved_events
+                    .lock()
+                    .extend(updates.iter().map(|update| update.head_moved));
+            }
+        })
+        .detach();
+    });
+
+    // Staging only touches the index, so HEAD hasn't moved.
+    fs.write(Path::new(path!("/root/.git/index")), b"staged")
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(mem::take(&mut *head_moved_events.lock()), [false]);
+
+    // Committing appends to the HEAD reflog.
+    fs.write(
+        Path::new(path!("/root/.git/logs/HEAD")),
+        b"0000000 1111111 Some One <one@example.com> 0 +0000\tcommit: initial\n",
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(mem::take(&mut *head_moved_events.lock()), [true]);
+}
+
 #[gpui::test]
 async fn test_load_file_encoding(cx: &mut TestAppContext) {
     init_test(cx);